@@ -0,0 +1,626 @@
+use crate::device::Lut;
+use crate::DeviceParams;
+use crate::Netlist;
+use crate::NetlistAssembler;
+use crate::NetlistParseError;
+use crate::PinDirection;
+use std::collections::HashMap;
+
+/// Keywords of the timing and library extensions, which do not change the
+/// logic.
+const IGNORED: [&str; 13] = [
+    ".area",
+    ".delay",
+    ".wire_load_slope",
+    ".wire",
+    ".input_arrival",
+    ".default_input_arrival",
+    ".output_required",
+    ".default_output_required",
+    ".input_drive",
+    ".default_input_drive",
+    ".output_load",
+    ".default_output_load",
+    ".max_input_load",
+];
+
+/// The input that clocks the latches declared without a clock.
+const GLOBAL_CLOCK: &str = "$clock";
+
+impl Netlist {
+    /// Imports a BLIF netlist, as the ISCAS and MCNC benchmarks are written:
+    /// `.model`, `.inputs`, `.outputs`, `.clock`, `.names`, `.latch`,
+    /// `.subckt` and `.end`.
+    ///
+    /// `top` is the model to import, by default the first one. Its inputs and
+    /// outputs become test probes named after them like
+    /// [`Netlist::from_verilog`] does, the other models become netlist
+    /// modules. Every signal is a one bit net of the same name.
+    ///
+    /// A `.names` cover becomes a `lut`, so it can have up to
+    /// `Lut::MAX_INPUTS` inputs. A `.latch` of type `re` or `fe` becomes a
+    /// `d_flip_flop`, `ah` or `al` a `d_latch`, with a `not` in front of
+    /// falling or active low controls, and its initial value of 0 or 1 becomes
+    /// the `init` of the device. Latches without a type or clock are clocked by
+    /// the first `.clock` of the model or else by an added input called
+    /// `$clock`, which sub circuits share with the model using them.
+    pub fn from_blif(text: &str, top: Option<&str>) -> Result<Netlist, NetlistParseError> {
+        let models = parse_models(text)?;
+        let top = match top {
+            Some(top) => models
+                .iter()
+                .find(|model| model.name == top)
+                .ok_or_else(|| {
+                    NetlistParseError::new(1, &format!("model {} is not defined", top))
+                })?,
+            None => models
+                .first()
+                .ok_or_else(|| NetlistParseError::new(1, "no model found"))?,
+        };
+        let mut netlist = Elaborator::new(&models).elaborate(top, true)?;
+        for model in &models {
+            if model.name != top.name {
+                netlist.add_module(Elaborator::new(&models).elaborate(model, false)?);
+            }
+        }
+        return Ok(netlist);
+    }
+}
+
+/// A `.names` cover, its signals are the inputs followed by the output and its
+/// rows `(inputs, output)`, e.g. `("1-0", '1')`.
+#[derive(Debug)]
+struct Cover {
+    signals: Vec<String>,
+    rows: Vec<(String, char)>,
+    line: usize,
+}
+
+#[derive(Debug)]
+struct Latch {
+    input: String,
+    output: String,
+    kind: Option<String>,
+    control: Option<String>,
+    init: char,
+    line: usize,
+}
+
+#[derive(Debug)]
+struct SubCircuit {
+    model: String,
+    // (formal, actual)
+    connections: Vec<(String, String)>,
+    line: usize,
+}
+
+#[derive(Debug)]
+struct Model {
+    name: String,
+    inputs: Vec<String>,
+    outputs: Vec<String>,
+    clocks: Vec<String>,
+    covers: Vec<Cover>,
+    latches: Vec<Latch>,
+    sub_circuits: Vec<SubCircuit>,
+    line: usize,
+}
+
+impl Model {
+    fn new(name: &str, line: usize) -> Model {
+        return Model {
+            name: name.to_string(),
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            clocks: Vec::new(),
+            covers: Vec::new(),
+            latches: Vec::new(),
+            sub_circuits: Vec::new(),
+            line,
+        };
+    }
+
+    /// Tells if this model or its sub circuits have latches without a clock
+    /// and no `.clock` to clock them, so it needs a `$clock` input. `path` holds
+    /// the models being checked, to stop at recursion.
+    fn needs_global_clock<'a>(&'a self, models: &'a [Model], path: &mut Vec<&'a str>) -> bool {
+        if !self.clocks.is_empty() || path.contains(&self.name.as_str()) {
+            return false;
+        }
+        path.push(&self.name);
+        let needs_clock = self.latches.iter().any(|latch| latch.control.is_none())
+            || self.sub_circuits.iter().any(|sub_circuit| {
+                models
+                    .iter()
+                    .find(|model| model.name == sub_circuit.model)
+                    .is_some_and(|model| model.needs_global_clock(models, path))
+            });
+        path.pop();
+        return needs_clock;
+    }
+}
+
+/// Splits `text` into the tokens of each line, joining lines ending in `\`
+/// and dropping comments and blank lines.
+fn split_lines(text: &str) -> Vec<(usize, Vec<&str>)> {
+    let mut lines: Vec<(usize, Vec<&str>)> = Vec::new();
+    let mut continued = false;
+    for (index, line) in text.lines().enumerate() {
+        let code = line.split('#').next().unwrap_or("").trim_end();
+        let (code, continues) = match code.strip_suffix('\\') {
+            Some(code) => (code, true),
+            None => (code, false),
+        };
+        let tokens: Vec<&str> = code.split_whitespace().collect();
+        match lines.last_mut() {
+            Some((_, last)) if continued => last.extend(tokens),
+            _ if !tokens.is_empty() => lines.push((index + 1, tokens)),
+            _ => {}
+        }
+        continued = continues;
+    }
+    return lines;
+}
+
+fn parse_models(text: &str) -> Result<Vec<Model>, NetlistParseError> {
+    let mut models: Vec<Model> = Vec::new();
+    // the model being read, until its .end
+    let mut model: Option<Model> = None;
+    // rows may follow a .names
+    let mut in_cover = false;
+    for (line, tokens) in split_lines(text) {
+        let error = |message: String| NetlistParseError::new(line, &message);
+        let (keyword, args) = tokens.split_first().expect("lines have tokens");
+        let row = !keyword.starts_with('.');
+        in_cover = (in_cover && row) || *keyword == ".names";
+        if *keyword == ".model" {
+            if model.is_some() {
+                return Err(error("expected .end before .model".to_string()));
+            }
+            let name = match args {
+                [name] => name,
+                _ => return Err(error("expected .model <name>".to_string())),
+            };
+            model = Some(Model::new(name, line));
+            continue;
+        }
+        let Some(current) = model.as_mut() else {
+            return Err(error(format!("expected .model, got {}", keyword)));
+        };
+        match *keyword {
+            ".inputs" => current
+                .inputs
+                .extend(args.iter().map(|arg| arg.to_string())),
+            ".outputs" => current
+                .outputs
+                .extend(args.iter().map(|arg| arg.to_string())),
+            ".clock" => current
+                .clocks
+                .extend(args.iter().map(|arg| arg.to_string())),
+            ".names" => {
+                if args.is_empty() {
+                    return Err(error("expected .names <input>... <output>".to_string()));
+                }
+                current.covers.push(Cover {
+                    signals: args.iter().map(|arg| arg.to_string()).collect(),
+                    rows: Vec::new(),
+                    line,
+                });
+            }
+            ".latch" => {
+                let (input, output, kind, control, init) = match args {
+                    [input, output] => (input, output, None, None, "3"),
+                    [input, output, init] => (input, output, None, None, *init),
+                    [input, output, kind, control] => {
+                        (input, output, Some(kind), Some(control), "3")
+                    }
+                    [input, output, kind, control, init] => {
+                        (input, output, Some(kind), Some(control), *init)
+                    }
+                    _ => {
+                        return Err(error(
+                            ".latch takes <input> <output> [<type> <control>] [<init>]".to_string(),
+                        ))
+                    }
+                };
+                if let Some(kind) = kind.filter(|kind| !["re", "fe", "ah", "al"].contains(kind)) {
+                    return Err(error(format!("latches of type {} are not supported", kind)));
+                }
+                let init = match init {
+                    "0" | "1" | "2" | "3" => init.chars().next().expect("init is one digit"),
+                    _ => return Err(error(format!("invalid initial value {}", init))),
+                };
+                current.latches.push(Latch {
+                    input: input.to_string(),
+                    output: output.to_string(),
+                    kind: kind.map(|kind| kind.to_string()),
+                    // NIL stands for the global clock
+                    control: control
+                        .filter(|control| **control != "NIL")
+                        .map(|control| control.to_string()),
+                    init,
+                    line,
+                });
+            }
+            ".subckt" => {
+                let Some((model_name, connections)) = args.split_first() else {
+                    return Err(error(
+                        "expected .subckt <model> <formal>=<actual>...".to_string(),
+                    ));
+                };
+                let connections = connections
+                    .iter()
+                    .map(|connection| {
+                        connection
+                            .split_once('=')
+                            .map(|(formal, actual)| (formal.to_string(), actual.to_string()))
+                            .ok_or_else(|| {
+                                error(format!("expected <formal>=<actual>, got {}", connection))
+                            })
+                    })
+                    .collect::<Result<Vec<(String, String)>, NetlistParseError>>()?;
+                current.sub_circuits.push(SubCircuit {
+                    model: model_name.to_string(),
+                    connections,
+                    line,
+                });
+            }
+            ".end" => models.push(model.take().expect("a model is being read")),
+            _ if IGNORED.contains(keyword) => {}
+            _ if keyword.starts_with('.') => {
+                return Err(error(format!("{} is not supported", keyword)))
+            }
+            _ => {
+                // a row of the cover just declared
+                let Some(cover) = current.covers.last_mut().filter(|_| in_cover) else {
+                    return Err(error(format!("unexpected {}", keyword)));
+                };
+                let input_count = cover.signals.len() - 1;
+                let (inputs, output) = match (input_count, &tokens[..]) {
+                    (0, [output]) => ("", *output),
+                    (_, [inputs, output]) => (*inputs, *output),
+                    _ => return Err(error("expected <inputs> <output>".to_string())),
+                };
+                if inputs.len() != input_count
+                    || !inputs.chars().all(|c| c == '0' || c == '1' || c == '-')
+                {
+                    return Err(error(format!(
+                        "expected {} inputs made of 0, 1 and -, got {}",
+                        input_count, inputs
+                    )));
+                }
+                let output = match output {
+                    "0" => '0',
+                    "1" => '1',
+                    _ => return Err(error(format!("invalid output {}", output))),
+                };
+                if cover
+                    .rows
+                    .first()
+                    .is_some_and(|(_, first)| *first != output)
+                {
+                    return Err(error(
+                        "the rows of a cover must have the same output".to_string(),
+                    ));
+                }
+                cover.rows.push((inputs.to_string(), output));
+            }
+        }
+    }
+    if let Some(model) = model {
+        return Err(NetlistParseError::new(
+            model.line,
+            &format!("model {} has no .end", model.name),
+        ));
+    }
+    return Ok(models);
+}
+
+/// Turns a model into a netlist module.
+struct Elaborator<'a> {
+    models: &'a [Model],
+    assembler: NetlistAssembler,
+    // the net of each signal
+    signals: HashMap<String, usize>,
+    // clocks the latches without a clock
+    global_clock: Option<usize>,
+}
+
+impl<'a> Elaborator<'a> {
+    fn new(models: &'a [Model]) -> Elaborator<'a> {
+        return Elaborator {
+            models,
+            assembler: NetlistAssembler::new(None),
+            signals: HashMap::new(),
+            global_clock: None,
+        };
+    }
+
+    fn elaborate(mut self, model: &Model, top: bool) -> Result<Netlist, NetlistParseError> {
+        if !top {
+            self.assembler = NetlistAssembler::new(Some(&model.name));
+        }
+        for input in &model.inputs {
+            let net = self.get_net(input);
+            self.assembler
+                .add_port(input, PinDirection::Input, net, model.line);
+        }
+        for clock in &model.clocks {
+            if !model.inputs.contains(clock) {
+                let net = self.get_net(clock);
+                self.assembler
+                    .add_port(clock, PinDirection::Input, net, model.line);
+            }
+        }
+        if model.needs_global_clock(self.models, &mut Vec::new()) {
+            let net = self.get_net(GLOBAL_CLOCK);
+            self.assembler
+                .add_port(GLOBAL_CLOCK, PinDirection::Input, net, model.line);
+        }
+        self.global_clock = match model.clocks.first() {
+            Some(clock) => Some(self.signals[clock]),
+            None => self.signals.get(GLOBAL_CLOCK).copied(),
+        };
+        for output in &model.outputs {
+            if model.inputs.contains(output) || model.clocks.contains(output) {
+                return Err(NetlistParseError::new(
+                    model.line,
+                    &format!("{} is both an input and an output", output),
+                ));
+            }
+            let net = self.get_net(output);
+            self.assembler
+                .add_port(output, PinDirection::Output, net, model.line);
+        }
+        for cover in &model.covers {
+            self.add_cover(cover)?;
+        }
+        for latch in &model.latches {
+            self.add_latch(latch);
+        }
+        for sub_circuit in &model.sub_circuits {
+            self.add_sub_circuit(sub_circuit)?;
+        }
+        return Ok(self.assembler.finish());
+    }
+
+    fn get_net(&mut self, signal: &str) -> usize {
+        if let Some(net) = self.signals.get(signal) {
+            return *net;
+        }
+        let net = self.assembler.add_net(signal, 1);
+        self.signals.insert(signal.to_string(), net);
+        return net;
+    }
+
+    fn add_cover(&mut self, cover: &Cover) -> Result<(), NetlistParseError> {
+        let (output, inputs) = cover.signals.split_last().expect("covers have an output");
+        if inputs.len() > Lut::MAX_INPUTS {
+            return Err(NetlistParseError::new(
+                cover.line,
+                &format!(
+                    "a cover takes up to {} inputs, got {}",
+                    Lut::MAX_INPUTS,
+                    inputs.len()
+                ),
+            ));
+        }
+        // the rows list the entries that are 1, or those that are 0 if their
+        // output is 0, a cover without rows is 0
+        let on_set = cover.rows.first().is_none_or(|(_, output)| *output == '1');
+        let table: String = (0..1usize << inputs.len())
+            .rev()
+            .map(|index| {
+                let listed = cover.rows.iter().any(|(row, _)| {
+                    return row.chars().enumerate().all(|(bit, c)| match c {
+                        '0' => (index >> bit) & 1 == 0,
+                        '1' => (index >> bit) & 1 == 1,
+                        _ => true,
+                    });
+                });
+                return if listed == on_set { '1' } else { '0' };
+            })
+            .collect();
+        let name = self.assembler.generate_name("lut");
+        let mut params = DeviceParams::new();
+        params.set("table", &table);
+        self.assembler.add_device(&name, "lut", params, cover.line);
+        for (index, input) in inputs.iter().enumerate() {
+            let net = self.get_net(input);
+            self.assembler.connect(
+                &format!("{}.{}", name, Lut::get_input_name(index)),
+                &[(net, 0)],
+            );
+        }
+        let net = self.get_net(output);
+        self.assembler.connect(&format!("{}.Y", name), &[(net, 0)]);
+        return Ok(());
+    }
+
+    fn add_latch(&mut self, latch: &Latch) {
+        let (type_name, control_pin, inverted) = match latch.kind.as_deref() {
+            Some("fe") => ("d_flip_flop", "CLK", true),
+            Some("ah") => ("d_latch", "EN", false),
+            Some("al") => ("d_latch", "EN", true),
+            _ => ("d_flip_flop", "CLK", false),
+        };
+        let name = self.assembler.generate_name(type_name);
+        let mut params = DeviceParams::new();
+        if latch.init == '0' || latch.init == '1' {
+            params.set("init", &latch.init.to_string());
+        }
+        self.assembler
+            .add_device(&name, type_name, params, latch.line);
+        let mut control = match &latch.control {
+            Some(control) => self.get_net(control),
+            None => self
+                .global_clock
+                .expect("models with latches without a clock have one"),
+        };
+        if inverted {
+            let not_name = self.assembler.generate_name("not");
+            self.assembler
+                .add_device(&not_name, "not", DeviceParams::new(), latch.line);
+            self.assembler
+                .connect(&format!("{}.A", not_name), &[(control, 0)]);
+            control = self.assembler.add_net(&not_name, 1);
+            self.assembler
+                .connect(&format!("{}.Y", not_name), &[(control, 0)]);
+        }
+        self.assembler
+            .connect(&format!("{}.{}", name, control_pin), &[(control, 0)]);
+        let input = self.get_net(&latch.input);
+        self.assembler
+            .connect(&format!("{}.D", name), &[(input, 0)]);
+        let output = self.get_net(&latch.output);
+        self.assembler
+            .connect(&format!("{}.Q", name), &[(output, 0)]);
+    }
+
+    fn add_sub_circuit(&mut self, sub_circuit: &SubCircuit) -> Result<(), NetlistParseError> {
+        let name = self.assembler.generate_name(&sub_circuit.model);
+        self.assembler.add_device(
+            &name,
+            &sub_circuit.model,
+            DeviceParams::new(),
+            sub_circuit.line,
+        );
+        let model = self
+            .models
+            .iter()
+            .find(|model| model.name == sub_circuit.model);
+        for (formal, actual) in &sub_circuit.connections {
+            let is_port = |model: &Model| {
+                return model.inputs.contains(formal)
+                    || model.outputs.contains(formal)
+                    || model.clocks.contains(formal);
+            };
+            if let Some(model) = model.filter(|model| !is_port(model)) {
+                return Err(NetlistParseError::new(
+                    sub_circuit.line,
+                    &format!("model {} has no port {}", model.name, formal),
+                ));
+            }
+            let net = self.get_net(actual);
+            self.assembler
+                .connect(&format!("{}.{}", name, formal), &[(net, 0)]);
+        }
+        if model.is_some_and(|model| model.needs_global_clock(self.models, &mut Vec::new())) {
+            let clock = self
+                .global_clock
+                .expect("models using one have a global clock");
+            self.assembler
+                .connect(&format!("{}.{}", name, GLOBAL_CLOCK), &[(clock, 0)]);
+        }
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::device::TestProbe;
+    use crate::Circuit;
+    use crate::DeviceRegistry;
+    use crate::LogicValue;
+    use crate::Netlist;
+    use crate::NetlistParseError;
+
+    const ADDER_TOGGLE: &str = "
+# a full adder and a toggle
+.model top
+.inputs a b \\
+  c
+.outputs s co t
+.subckt fa x=a y=b z=c s=s co=co
+.names one
+1
+.latch tn t 0
+.names t one tn
+10 1
+01 1
+.end
+
+.model fa
+.inputs x y z
+.outputs s co
+.names x y z s
+100 1
+010 1
+001 1
+111 1
+.names x y z co
+00- 0
+0-0 0
+-00 0
+.end
+";
+
+    #[test]
+    fn imports_models() {
+        let netlist = Netlist::from_blif(ADDER_TOGGLE, None).unwrap();
+        let text = netlist.to_string();
+        assert!(text.contains("\ndevice $clock test_probe direction=output\n"));
+        assert!(text.contains("\ndevice $lut1 lut table=1\n"));
+        assert!(text.contains("\ndevice $lut2 lut table=0110\n"));
+        assert!(text.contains("\ndevice $d_flip_flop3 d_flip_flop init=0\n"));
+        assert!(text.contains("\nnet a a.pin $fa4.x\n"));
+        assert!(text.contains("\nnet $clock $clock.pin $d_flip_flop3.CLK\n"));
+        assert!(text.contains("\n  device $lut1 lut table=10010110\n"));
+        assert!(text.contains("\n  device $lut2 lut table=11101000\n"));
+        assert_eq!(text, Netlist::parse(&text).unwrap().to_string());
+
+        let (devices, nets) = netlist.build(&DeviceRegistry::new()).unwrap();
+        let mut circuit = Circuit::new(devices, nets).unwrap();
+        let set = |circuit: &Circuit, name: &str, value: LogicValue| {
+            TestProbe::set_output(circuit, name, value).unwrap();
+        };
+        let get_value = |circuit: &Circuit, name: &str| {
+            return TestProbe::get_value(circuit, name).unwrap();
+        };
+        set(&circuit, "a", LogicValue::One);
+        set(&circuit, "b", LogicValue::Zero);
+        set(&circuit, "c", LogicValue::One);
+        set(&circuit, "$clock", LogicValue::Zero);
+        circuit.run_until(10).unwrap();
+        assert_eq!(LogicValue::Zero, get_value(&circuit, "s"));
+        assert_eq!(LogicValue::One, get_value(&circuit, "co"));
+        assert_eq!(LogicValue::Zero, get_value(&circuit, "t"));
+        set(&circuit, "$clock", LogicValue::One);
+        circuit.run_until(20).unwrap();
+        assert_eq!(LogicValue::One, get_value(&circuit, "t"));
+        set(&circuit, "$clock", LogicValue::Zero);
+        circuit.run_until(30).unwrap();
+        set(&circuit, "$clock", LogicValue::One);
+        circuit.run_until(40).unwrap();
+        assert_eq!(LogicValue::Zero, get_value(&circuit, "t"));
+    }
+
+    #[test]
+    fn reports_lines() {
+        let error = |text: &str| Netlist::from_blif(text, None).unwrap_err();
+        assert_eq!(
+            NetlistParseError::new(3, ".gate is not supported"),
+            error(".model m\n.inputs a\n.gate and2 A=a\n.end")
+        );
+        assert_eq!(
+            NetlistParseError::new(4, "the rows of a cover must have the same output"),
+            error(".model m\n.names a b y\n11 1\n00 0\n.end")
+        );
+        assert_eq!(
+            NetlistParseError::new(3, "expected 2 inputs made of 0, 1 and -, got 1x"),
+            error(".model m\n.names a b y\n1x 1\n.end")
+        );
+        assert_eq!(
+            NetlistParseError::new(2, "latches of type as are not supported"),
+            error(".model m\n.latch d q as c 0\n.end")
+        );
+        assert_eq!(
+            NetlistParseError::new(3, "model fa has no port w"),
+            error(".model m\n.inputs a\n.subckt fa w=a\n.end\n.model fa\n.inputs x\n.end")
+        );
+        assert_eq!(
+            NetlistParseError::new(1, "model m has no .end"),
+            error(".model m\n.inputs a")
+        );
+    }
+}
@@ -0,0 +1,212 @@
+use crate::LogicValue;
+use std::fmt;
+
+/// Four-state value of a pin or net up to `BusValue::MAX_WIDTH` bits wide.
+/// Single-bit pins carry a one bit wide value.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct BusValue {
+    width: usize,
+    // each bit is encoded by the pair (value, unknown): 00 = 0, 10 = 1, 11 = X, 01 = Z
+    value: u64,
+    unknown: u64,
+}
+
+impl BusValue {
+    pub const MAX_WIDTH: usize = 64;
+
+    /// Creates a value with every bit set to `bit`.
+    pub fn filled(width: usize, bit: LogicValue) -> BusValue {
+        if width == 0 || width > BusValue::MAX_WIDTH {
+            panic!(
+                "bus width must be 1 to {}, got {}",
+                BusValue::MAX_WIDTH,
+                width
+            );
+        }
+        let mask = BusValue::mask(width);
+        let (value, unknown) = match bit {
+            LogicValue::Zero => (0, 0),
+            LogicValue::One => (mask, 0),
+            LogicValue::X => (mask, mask),
+            LogicValue::Z => (0, mask),
+        };
+        return BusValue {
+            width,
+            value,
+            unknown,
+        };
+    }
+
+    /// A value with every bit floating.
+    pub fn z(width: usize) -> BusValue {
+        return BusValue::filled(width, LogicValue::Z);
+    }
+
+    /// A value with every bit unknown.
+    pub fn x(width: usize) -> BusValue {
+        return BusValue::filled(width, LogicValue::X);
+    }
+
+    /// Creates a fully known value from the low `width` bits of `value`.
+    pub fn from_u64(width: usize, value: u64) -> BusValue {
+        let mut bus = BusValue::filled(width, LogicValue::Zero);
+        bus.value = value & BusValue::mask(width);
+        return bus;
+    }
+
+    /// Parses a string of `0`, `1`, `x` and `z` characters, most significant bit
+    /// first. Underscores are ignored.
+    pub fn parse(text: &str) -> Option<BusValue> {
+        let bits: Vec<char> = text.chars().filter(|c| *c != '_').collect();
+        if bits.is_empty() || bits.len() > BusValue::MAX_WIDTH {
+            return None;
+        }
+        let mut bus = BusValue::z(bits.len());
+        for (i, c) in bits.iter().rev().enumerate() {
+            bus.set_bit(i, LogicValue::from_char(*c)?);
+        }
+        return Some(bus);
+    }
+
+    pub fn get_width(&self) -> usize {
+        return self.width;
+    }
+
+    pub fn get_bit(&self, bit: usize) -> LogicValue {
+        self.check_bit(bit);
+        let value = (self.value >> bit) & 1 == 1;
+        let unknown = (self.unknown >> bit) & 1 == 1;
+        match (value, unknown) {
+            (false, false) => LogicValue::Zero,
+            (true, false) => LogicValue::One,
+            (true, true) => LogicValue::X,
+            (false, true) => LogicValue::Z,
+        }
+    }
+
+    pub fn set_bit(&mut self, bit: usize, bit_value: LogicValue) {
+        self.check_bit(bit);
+        let (value, unknown) = match bit_value {
+            LogicValue::Zero => (0, 0),
+            LogicValue::One => (1, 0),
+            LogicValue::X => (1, 1),
+            LogicValue::Z => (0, 1),
+        };
+        self.value = (self.value & !(1 << bit)) | (value << bit);
+        self.unknown = (self.unknown & !(1 << bit)) | (unknown << bit);
+    }
+
+    /// True if every bit is 0 or 1.
+    pub fn is_known(&self) -> bool {
+        return self.unknown == 0;
+    }
+
+    /// Gets the value as an integer, or `None` if any bit is X or Z.
+    pub fn to_u64(&self) -> Option<u64> {
+        if self.is_known() {
+            return Some(self.value);
+        }
+        return None;
+    }
+
+    /// Gets `width` bits starting at bit `lsb`.
+    pub fn slice(&self, lsb: usize, width: usize) -> BusValue {
+        if lsb + width > self.width {
+            panic!(
+                "slice [{}..{}] is outside of a {} bit value",
+                lsb,
+                lsb + width,
+                self.width
+            );
+        }
+        let mask = BusValue::mask(width);
+        return BusValue {
+            width,
+            value: (self.value >> lsb) & mask,
+            unknown: (self.unknown >> lsb) & mask,
+        };
+    }
+
+    /// Overwrites the bits starting at `lsb` with `value`.
+    pub fn set_slice(&mut self, lsb: usize, value: BusValue) {
+        if lsb + value.width > self.width {
+            panic!(
+                "slice [{}..{}] is outside of a {} bit value",
+                lsb,
+                lsb + value.width,
+                self.width
+            );
+        }
+        let mask = BusValue::mask(value.width) << lsb;
+        self.value = (self.value & !mask) | (value.value << lsb);
+        self.unknown = (self.unknown & !mask) | (value.unknown << lsb);
+    }
+
+    /// Joins two values, `self` becomes the most significant bits.
+    pub fn concat(&self, low: BusValue) -> BusValue {
+        let mut result = BusValue::z(self.width + low.width);
+        result.set_slice(0, low);
+        result.set_slice(low.width, *self);
+        return result;
+    }
+
+    fn check_bit(&self, bit: usize) {
+        if bit >= self.width {
+            panic!("bit {} is outside of a {} bit value", bit, self.width);
+        }
+    }
+
+    fn mask(width: usize) -> u64 {
+        if width >= 64 {
+            return u64::MAX;
+        }
+        return (1 << width) - 1;
+    }
+}
+
+impl From<LogicValue> for BusValue {
+    fn from(value: LogicValue) -> BusValue {
+        return BusValue::filled(1, value);
+    }
+}
+
+impl fmt::Display for BusValue {
+    /// Writes the bits most significant first, e.g. `01xz`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for bit in (0..self.width).rev() {
+            write!(f, "{}", self.get_bit(bit))?;
+        }
+        return Ok(());
+    }
+}
+
+impl fmt::Debug for BusValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}'b{}", self.width, self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BusValue;
+    use crate::LogicValue;
+
+    #[test]
+    fn slice_and_concat() {
+        let value = BusValue::parse("1010_xz01").unwrap();
+        assert_eq!(8, value.get_width());
+        assert_eq!(LogicValue::One, value.get_bit(0));
+        assert_eq!(LogicValue::Z, value.get_bit(2));
+        assert_eq!(LogicValue::X, value.get_bit(3));
+        assert_eq!(Some(0b1010), value.slice(4, 4).to_u64());
+        assert_eq!(None, value.slice(0, 4).to_u64());
+        assert_eq!("xz01", value.slice(0, 4).to_string());
+
+        let joined = value.slice(0, 4).concat(value.slice(4, 4));
+        assert_eq!("xz011010", joined.to_string());
+        assert_eq!(
+            BusValue::from_u64(16, 0xffff),
+            BusValue::filled(16, LogicValue::One)
+        );
+    }
+}
@@ -1,242 +1,1361 @@
+use crate::device::flatten_sub_circuits;
 use crate::device::Device;
+use crate::device::DeviceHost;
+use crate::dot;
+use crate::dot::DotNet;
+use crate::netlist_validation::validate_netlist;
+use crate::BusValue;
 use crate::CircuitToDeviceMessage;
+use crate::Delay;
+use crate::DelayMode;
 use crate::DeviceData;
 use crate::DeviceToCircuitMessage;
+use crate::DotOptions;
+use crate::Engine;
 use crate::Net;
+use crate::NetPull;
+use crate::NetResolution;
 use crate::PinDirection;
+use crate::PinInfo;
+use crate::RunSummary;
+use crate::SimError;
+use crate::SimEvent;
+use crate::Waveform;
 use std::cell::RefCell;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::io;
+use std::io::Write;
+use std::panic;
+use std::panic::AssertUnwindSafe;
 use std::sync::mpsc;
 use std::thread;
 use std::thread::JoinHandle;
 
 #[derive(Debug)]
 pub struct Circuit {
-    device_wrappers: Vec<Box<DeviceWrapper>>,
+    device_wrappers: Vec<DeviceWrapper>,
+    engine: Engine,
     last_tick: u64,
-    // nets[device_index][pin_index] = Vec<> of connected pins
-    nets: Vec<Vec<Vec<PinRef>>>,
+    // pin_nets[device_index][pin_index] = nets and connections the pin belongs to
+    pin_nets: Vec<Vec<Vec<NetSlot>>>,
+    // pin_values[device_index][pin_index] = value last delivered to the pin
+    pin_values: Vec<Vec<BusValue>>,
+    nets: Vec<NetState>,
+    // nets whose drivers changed since they were last resolved
+    dirty_nets: Vec<usize>,
+    // pins reading their default value, delivered on the first tick
+    default_pins: Vec<(usize, usize)>,
+    events: Vec<SimEvent>,
+    // net changes recorded since start_waveform, if it was called
+    waveform: Option<Waveform>,
+    // error the last failed tick returned, the circuit cannot continue after it
+    failure: Option<SimError>,
+    // wake_ticks[device_index] = tick the device last asked to be woken at
+    wake_ticks: Vec<u64>,
+    // pending wake-ups ordered by tick, entries not matching wake_ticks are stale
+    wake_queue: BinaryHeap<Reverse<(u64, usize)>>,
+    // devices that received data since the last tick and must be woken
+    data_wakes: RefCell<Vec<usize>>,
+    // most events a single run may process
+    event_budget: u64,
+    // (changes, window): a net changing more than `changes` times within
+    // `window` ticks is oscillating
+    oscillation_limit: Option<(usize, u64)>,
+    // device_delays[device_index] = delay of every output of the device
+    device_delays: Vec<Delay>,
+    // (device index, input pin, output pin) = delay from a change on the input to the output
+    pin_delays: HashMap<(usize, usize, usize), Delay>,
+    delay_mode: DelayMode,
+    // drives waiting for their delay to pass, in the order they were made
+    pending_drives: Vec<PendingDrive>,
 }
 
 impl Circuit {
-    pub fn new(devices: Vec<RefCell<Box<dyn Device>>>, nets: Vec<Net>) -> Circuit {
-        let mut circuit_nets: Vec<Vec<Vec<PinRef>>> = Vec::new();
-        let mut device_wrappers: Vec<Box<DeviceWrapper>> = Vec::new();
+    /// Number of times outputs may be routed within a single tick before the
+    /// circuit is considered to be oscillating.
+    pub const MAX_DELTA_CYCLES: usize = 1000;
+
+    /// Number of events a run may process unless changed by `set_event_budget`.
+    pub const DEFAULT_EVENT_BUDGET: u64 = 1_000_000;
+
+    /// Builds a circuit run by the threaded engine, see `with_engine`.
+    pub fn new(
+        devices: Vec<RefCell<Box<dyn Device>>>,
+        nets: Vec<Net>,
+    ) -> Result<Circuit, SimError> {
+        return Circuit::with_engine(devices, nets, Engine::Threaded);
+    }
+
+    /// Builds a circuit from `devices` and the `nets` connecting their pins.
+    /// Connections made by pin name are resolved and the netlist is checked
+    /// first, `SimError::InvalidNetlist` lists every problem found.
+    pub fn with_engine(
+        devices: Vec<RefCell<Box<dyn Device>>>,
+        nets: Vec<Net>,
+        engine: Engine,
+    ) -> Result<Circuit, SimError> {
+        let (devices, mut nets, mut errors) = flatten_sub_circuits(devices, nets);
+        errors.extend(validate_netlist(&devices, &mut nets));
+        if !errors.is_empty() {
+            return Err(SimError::InvalidNetlist { errors });
+        }
+
+        let mut pin_nets: Vec<Vec<Vec<NetSlot>>> = Vec::new();
+        let mut pin_values: Vec<Vec<BusValue>> = Vec::new();
+        let mut device_wrappers: Vec<DeviceWrapper> = Vec::new();
         for device in devices {
-            let device_index = circuit_nets.len();
-            let mut device_nets: Vec<Vec<PinRef>> = Vec::new();
-            for _i in 0..(device.borrow().get_pin_count() + 1) {
-                device_nets.push(Vec::new());
-            }
-            circuit_nets.push(device_nets);
-
-            let (device_to_circuit_tx, device_to_circuit_rx): (
-                mpsc::Sender<DeviceToCircuitMessage>,
-                mpsc::Receiver<DeviceToCircuitMessage>,
-            ) = mpsc::channel();
-            let (circuit_to_device_tx, circuit_to_device_rx): (
-                mpsc::Sender<CircuitToDeviceMessage>,
-                mpsc::Receiver<CircuitToDeviceMessage>,
-            ) = mpsc::channel();
+            let pins: Vec<PinInfo> = {
+                let device = device.borrow();
+                (1..=device.get_pin_count())
+                    .map(|pin| device.get_pin_info(pin))
+                    .collect()
+            };
+            pin_nets.push(vec![Vec::new(); pins.len() + 1]);
+            // pin 0 does not exist, it keeps a placeholder so pins can be indexed by number
+            pin_values.push(
+                std::iter::once(BusValue::z(1))
+                    .chain(pins.iter().map(|pin| {
+                        pin.get_default_value()
+                            .unwrap_or_else(|| BusValue::z(pin.get_width()))
+                    }))
+                    .collect(),
+            );
+
             let device_name = device.borrow().get_name().to_string();
-            let device_thread = thread::spawn(move || {
-                device
-                    .borrow_mut()
-                    .run(device_to_circuit_tx, circuit_to_device_rx);
-            });
-            device_wrappers.push(Box::new(DeviceWrapper {
-                index: device_index,
+            let runner = match engine {
+                Engine::Threaded => DeviceRunner::spawn(device),
+                Engine::EventDriven => DeviceRunner::Direct {
+                    host: RefCell::new(DeviceHost::new(device.into_inner())),
+                    replies: RefCell::new(VecDeque::new()),
+                },
+            };
+            device_wrappers.push(DeviceWrapper {
                 name: device_name,
-                rx: device_to_circuit_rx,
-                tx: circuit_to_device_tx,
-                thread: Some(device_thread),
-            }));
-        }
-
-        for net in nets {
-            for from_conn in net.connections_iter() {
-                let from_device = from_conn.get_device();
-                let from_pin = from_conn.get_pin();
-                for to_conn in net.connections_iter() {
-                    let to_device = to_conn.get_device();
-                    let to_pin = to_conn.get_pin();
-                    if from_device != to_device || from_pin != to_pin {
-                        circuit_nets[from_device][from_pin].push(PinRef {
-                            device: to_device,
-                            pin: to_pin,
-                        });
-                    }
+                pins,
+                runner,
+                failure: RefCell::new(None),
+                changed_inputs: Vec::new(),
+                input_tick: 0,
+            });
+        }
+
+        let mut net_states: Vec<NetState> = Vec::new();
+        for (net_index, net) in nets.iter().enumerate() {
+            let mut connections: Vec<PinRef> = Vec::new();
+            for (connection_index, conn) in net.connections_iter().enumerate() {
+                pin_nets[conn.get_device()][conn.get_pin()].push(NetSlot {
+                    net: net_index,
+                    connection: connection_index,
+                });
+                let pin_width = pin_values[conn.get_device()][conn.get_pin()].get_width();
+                connections.push(PinRef {
+                    device: conn.get_device(),
+                    pin: conn.get_pin(),
+                    pin_lsb: conn.get_pin_lsb(),
+                    net_lsb: conn.get_net_lsb(),
+                    width: conn
+                        .get_width()
+                        .unwrap_or_else(|| pin_width - conn.get_pin_lsb()),
+                });
+            }
+            let name = net.get_display_name(net_index);
+            let width = match net.get_width() {
+                Some(width) => width,
+                None => connections
+                    .iter()
+                    .map(|connection| connection.net_lsb + connection.width)
+                    .max()
+                    .unwrap_or(1),
+            };
+            net_states.push(NetState {
+                name,
+                width,
+                drivers: vec![BusValue::z(width); connections.len()],
+                driving: vec![false; connections.len()],
+                new_receivers: Vec::new(),
+                connections,
+                resolution: net.get_resolution(),
+                pull: net.get_pull(),
+                value: BusValue::z(width),
+                changes: VecDeque::new(),
+                delay: net.get_delay(),
+            });
+        }
+
+        // bits connected to a net read the net instead of the pin's default value
+        for net in &net_states {
+            for connection in &net.connections {
+                pin_values[connection.device][connection.pin]
+                    .set_slice(connection.pin_lsb, BusValue::z(connection.width));
+            }
+        }
+        let mut default_pins: Vec<(usize, usize)> = Vec::new();
+        for (device_index, device) in device_wrappers.iter().enumerate() {
+            for (pin_index, pin) in device.pins.iter().enumerate() {
+                let value = pin_values[device_index][pin_index + 1];
+                if pin.get_default_value().is_some() && value != BusValue::z(pin.get_width()) {
+                    default_pins.push((device_index, pin_index + 1));
                 }
             }
         }
-        return Circuit {
+
+        // every device is woken on the first tick
+        let device_count = device_wrappers.len();
+        let mut wake_queue = BinaryHeap::new();
+        for device_index in 0..device_count {
+            wake_queue.push(Reverse((0, device_index)));
+        }
+        return Ok(Circuit {
             device_wrappers,
+            engine,
             last_tick: 0,
-            nets: circuit_nets,
-        };
+            pin_nets,
+            pin_values,
+            // resolve every net on the first tick so pulls reach their receivers
+            dirty_nets: (0..net_states.len()).collect(),
+            nets: net_states,
+            default_pins,
+            events: Vec::new(),
+            waveform: None,
+            failure: None,
+            wake_ticks: vec![0; device_count],
+            wake_queue,
+            data_wakes: RefCell::new(Vec::new()),
+            event_budget: Circuit::DEFAULT_EVENT_BUDGET,
+            oscillation_limit: None,
+            device_delays: vec![Delay::default(); device_count],
+            pin_delays: HashMap::new(),
+            delay_mode: DelayMode::default(),
+            pending_drives: Vec::new(),
+        });
+    }
+
+    pub fn get_engine(&self) -> Engine {
+        return self.engine;
     }
 
-    pub fn tick(&mut self, tick: u64) -> u64 {
+    /// Advances the circuit to `tick` and returns the earliest tick a device
+    /// asked to be woken at, `u64::MAX` if none did.
+    pub fn tick(&mut self, tick: u64) -> Result<u64, SimError> {
+        if let Some(err) = &self.failure {
+            return Err(err.clone());
+        }
         if tick <= self.last_tick {
-            panic!("tick must be greater than last tick");
+            return Err(SimError::NonIncreasingTick {
+                tick,
+                last_tick: self.last_tick,
+            });
         }
+        match self.run_tick(tick) {
+            Result::Ok(next_tick) => {
+                return Ok(next_tick);
+            }
+            Result::Err(err) => {
+                self.failure = Some(err.clone());
+                return Err(err);
+            }
+        }
+    }
 
-        // notify devices of nex tick
-        for device in &self.device_wrappers {
-            device
-                .tx
-                .send(CircuitToDeviceMessage::NextTick { tick: tick })
-                .unwrap();
+    pub fn get_last_tick(&self) -> u64 {
+        return self.last_tick;
+    }
+
+    /// Sets the number of events `run_until`, `run_until_stable` and `run_for`
+    /// may process before failing with `SimError::EventBudgetExceeded`. An
+    /// event is a tick at which some device had something to do.
+    pub fn set_event_budget(&mut self, event_budget: u64) {
+        self.event_budget = event_budget;
+    }
+
+    pub fn get_event_budget(&self) -> u64 {
+        return self.event_budget;
+    }
+
+    /// Makes `tick` fail with `SimError::Oscillation` once a net changes more
+    /// than `max_changes` times within `window` ticks. Off by default, since a
+    /// clock is expected to keep changing.
+    pub fn set_oscillation_limit(&mut self, max_changes: usize, window: u64) {
+        self.oscillation_limit = Some((max_changes, window));
+    }
+
+    /// Sets the delay of every output of a device, used unless a pin-to-pin
+    /// delay applies. It counts from the input change the device responds to,
+    /// so it replaces rather than adds to the tick a built-in device takes to
+    /// respond, see `Delay`.
+    pub fn set_device_delay(&mut self, device_index: usize, delay: Delay) -> Result<(), SimError> {
+        self.get_device_wrapper(device_index)?;
+        self.device_delays[device_index] = delay;
+        return Ok(());
+    }
+
+    /// Sets the delay of `output_pin` when it changes in response to a change
+    /// of `input_pin`, counted from that change like the device delay. When
+    /// several inputs change at once the longest delay is used.
+    pub fn set_pin_delay(
+        &mut self,
+        device_index: usize,
+        input_pin: usize,
+        output_pin: usize,
+        delay: Delay,
+    ) -> Result<(), SimError> {
+        self.get_device_wrapper(device_index)?;
+        self.pin_delays
+            .insert((device_index, input_pin, output_pin), delay);
+        return Ok(());
+    }
+
+    pub fn get_delay_mode(&self) -> DelayMode {
+        return self.delay_mode;
+    }
+
+    pub fn set_delay_mode(&mut self, delay_mode: DelayMode) {
+        self.delay_mode = delay_mode;
+    }
+
+    /// Finds the number of the pin of a device called `name`.
+    pub fn get_pin_by_name(&self, device_index: usize, name: &str) -> Option<usize> {
+        let device = self.device_wrappers.get(device_index)?;
+        let pin_index = device.pins.iter().position(|pin| pin.get_name() == name)?;
+        return Some(pin_index + 1);
+    }
+
+    /// Runs every event up to and including `tick`, then moves the circuit to
+    /// `tick`.
+    pub fn run_until(&mut self, tick: u64) -> Result<RunSummary, SimError> {
+        let summary = self.run(tick, u64::MAX)?;
+        if tick > self.last_tick {
+            self.last_tick = tick;
         }
+        return Ok(RunSummary::new(
+            self.last_tick,
+            summary.get_events(),
+            summary.is_quiescent(),
+        ));
+    }
 
-        // wait for devices to send next tick reply
-        let mut devices_set_pins: Vec<Vec<SetPin>> = Vec::new();
-        for _device in &self.device_wrappers {
-            devices_set_pins.push(Vec::new());
+    /// Runs until no device is waiting to be woken.
+    pub fn run_until_stable(&mut self) -> Result<RunSummary, SimError> {
+        return self.run(u64::MAX, u64::MAX);
+    }
+
+    /// Runs at most `events` events.
+    pub fn run_for(&mut self, events: u64) -> Result<RunSummary, SimError> {
+        return self.run(u64::MAX, events);
+    }
+
+    pub fn get_device_name(&self, device_index: usize) -> Option<&str> {
+        return self
+            .device_wrappers
+            .get(device_index)
+            .map(|device| device.name.as_str());
+    }
+
+    /// Finds the index of the device called `name`.
+    pub fn get_device_index(&self, name: &str) -> Option<usize> {
+        return self
+            .device_wrappers
+            .iter()
+            .position(|device| device.name == name);
+    }
+
+    /// Describes pin `pin` of a device, pins are numbered from 1.
+    pub fn get_pin_info(&self, device_index: usize, pin: usize) -> Option<&PinInfo> {
+        let device = self.device_wrappers.get(device_index)?;
+        return device.pins.get(pin.checked_sub(1)?);
+    }
+
+    pub fn send_device_data(
+        &self,
+        device_index: usize,
+        data: Box<dyn DeviceData>,
+    ) -> Result<(), SimError> {
+        let device = self.get_device_wrapper(device_index)?;
+        self.data_wakes.borrow_mut().push(device_index);
+        return device.send(CircuitToDeviceMessage::Data { data });
+    }
+
+    pub fn recv_device_data(
+        &self,
+        device_index: usize,
+        data: Box<dyn DeviceData>,
+    ) -> Result<Box<dyn DeviceData>, SimError> {
+        let device = self.get_device_wrapper(device_index)?;
+        self.data_wakes.borrow_mut().push(device_index);
+        device.send(CircuitToDeviceMessage::DataRequest { data })?;
+        match device.recv()? {
+            DeviceToCircuitMessage::Data { data } => {
+                return Ok(data);
+            }
+
+            message => {
+                return Err(device.unexpected_message(message));
+            }
         }
+    }
 
-        let mut min_next_tick = u64::MAX;
-        for device in &self.device_wrappers {
-            let mut rx_next_tick = false;
-            while !rx_next_tick {
-                match device.rx.recv() {
-                    Result::Ok(message) => match message {
-                        DeviceToCircuitMessage::NextTick { tick } => {
-                            min_next_tick = min_next_tick.min(tick);
-                            rx_next_tick = true;
-                        }
-
-                        DeviceToCircuitMessage::SetPin {
-                            pin,
-                            value,
-                            direction,
-                        } => match direction {
-                            PinDirection::Output => {
-                                let connections: &Vec<PinRef> = &self.nets[device.index][pin];
-                                for connection in connections.iter() {
-                                    devices_set_pins[connection.device].push(SetPin {
-                                        pin: connection.pin,
-                                        value,
-                                    });
-                                }
-                            }
-                            PinDirection::Input => (),
-                        },
-
-                        DeviceToCircuitMessage::Data { data: _ } => {
-                            panic!("unexpected data");
-                        }
-                    },
-                    Result::Err(_err) => {
-                        panic!("failed to receive from device");
-                    }
-                }
+    /// Removes and returns the events collected so far.
+    pub fn take_events(&mut self) -> Vec<SimEvent> {
+        return std::mem::take(&mut self.events);
+    }
+
+    /// Starts recording every net change routed by `tick`, any earlier
+    /// recording is discarded.
+    pub fn start_waveform(&mut self) {
+        let nets = self
+            .nets
+            .iter()
+            .map(|net| (net.name.clone(), net.value))
+            .collect();
+        self.waveform = Some(Waveform::new(self.last_tick, nets));
+    }
+
+    /// Gets the changes recorded since `start_waveform`.
+    pub fn get_waveform(&self) -> Option<&Waveform> {
+        return self.waveform.as_ref();
+    }
+
+    /// Stops recording and returns the changes recorded so far.
+    pub fn stop_waveform(&mut self) -> Option<Waveform> {
+        return self.waveform.take();
+    }
+
+    /// Writes the devices and nets as a Graphviz DOT graph. Devices are
+    /// records with their inputs on the left and other pins on the right, see
+    /// `DotOptions` for how nets are drawn.
+    pub fn write_dot<W: Write>(&self, out: &mut W, options: &DotOptions) -> io::Result<()> {
+        let devices: Vec<(&str, &[PinInfo])> = self
+            .device_wrappers
+            .iter()
+            .map(|device| (device.name.as_str(), device.pins.as_slice()))
+            .collect();
+        let nets: Vec<DotNet> = self
+            .nets
+            .iter()
+            .map(|net| DotNet {
+                name: &net.name,
+                value: net.value,
+                pins: net
+                    .connections
+                    .iter()
+                    .map(|connection| (connection.device, connection.pin))
+                    .collect(),
+            })
+            .collect();
+        return dot::write_dot(out, options, &devices, &nets);
+    }
+
+    fn run(&mut self, until_tick: u64, max_events: u64) -> Result<RunSummary, SimError> {
+        let mut events: u64 = 0;
+        loop {
+            let next_tick = self.next_event_tick();
+            if next_tick == u64::MAX || next_tick > until_tick || events == max_events {
+                break;
+            }
+            if events == self.event_budget {
+                return Err(SimError::EventBudgetExceeded {
+                    budget: self.event_budget,
+                    tick: self.last_tick,
+                });
             }
+            self.tick(next_tick)?;
+            events += 1;
+        }
+        let quiescent = self.next_event_tick() == u64::MAX;
+        return Ok(RunSummary::new(self.last_tick, events, quiescent));
+    }
+
+    /// Gets the next tick at which something happens, `u64::MAX` if nothing
+    /// will until the circuit is given new data.
+    fn next_event_tick(&mut self) -> u64 {
+        if !self.data_wakes.borrow().is_empty() {
+            return self.last_tick + 1;
+        }
+        let wake_tick = self.next_wake_tick();
+        if wake_tick == u64::MAX {
+            return u64::MAX;
+        }
+        return wake_tick.max(self.last_tick + 1);
+    }
+
+    fn get_device_wrapper(&self, device_index: usize) -> Result<&DeviceWrapper, SimError> {
+        return self
+            .device_wrappers
+            .get(device_index)
+            .ok_or(SimError::UnknownDevice {
+                device: device_index,
+            });
+    }
+
+    fn run_tick(&mut self, tick: u64) -> Result<u64, SimError> {
+        self.apply_pending_drives(tick);
+
+        // notify due devices of next tick
+        let due_devices = self.take_due_devices(tick);
+        for device_index in &due_devices {
+            self.device_wrappers[*device_index].send(CircuitToDeviceMessage::NextTick { tick })?;
+        }
+
+        // wait for devices to send next tick reply
+        let mut replied = vec![false; self.device_wrappers.len()];
+        for device_index in &due_devices {
+            self.recv_replies(*device_index, tick)?;
+            replied[*device_index] = true;
         }
 
-        // set pins
-        for (device_index, device_set_pins) in devices_set_pins.iter().enumerate() {
-            for (set_pin_index, set_pin) in device_set_pins.iter().enumerate() {
-                self.device_wrappers[device_index]
-                    .tx
-                    .send(CircuitToDeviceMessage::SetPin {
+        // set pins, devices may drive outputs in response which are routed on the same tick
+        let mut delta_cycles = 0;
+        loop {
+            let devices_set_pins = self.resolve_nets(tick);
+            if devices_set_pins.iter().all(|set_pins| set_pins.is_empty()) {
+                break;
+            }
+            delta_cycles += 1;
+            if delta_cycles > Circuit::MAX_DELTA_CYCLES {
+                return Err(SimError::DidNotSettle { tick });
+            }
+
+            for (device_index, device_set_pins) in devices_set_pins.iter().enumerate() {
+                for (set_pin_index, set_pin) in device_set_pins.iter().enumerate() {
+                    self.device_wrappers[device_index].record_input(tick, set_pin.pin);
+                    self.device_wrappers[device_index].send(CircuitToDeviceMessage::SetPin {
                         tick,
                         pin: set_pin.pin,
                         value: set_pin.value,
                         last: (device_set_pins.len() - 1) == set_pin_index,
-                    })
-                    .unwrap();
-            }
-        }
-        for (device_index, device_set_pins) in devices_set_pins.iter().enumerate() {
-            if device_set_pins.len() > 0 {
-                match self.device_wrappers[device_index].rx.recv() {
-                    Result::Ok(message) => match message {
-                        DeviceToCircuitMessage::NextTick { tick } => {
-                            min_next_tick = min_next_tick.min(tick);
-                        }
-                        _ => {
-                            panic!("unexpected device message {:?}", message);
-                        }
-                    },
-                    Result::Err(_err) => {
-                        panic!("failed to receive from device");
-                    }
+                    })?;
+                }
+            }
+            for (device_index, device_set_pins) in devices_set_pins.iter().enumerate() {
+                if !device_set_pins.is_empty() {
+                    self.recv_replies(device_index, tick)?;
+                    replied[device_index] = true;
+                }
+            }
+        }
+
+        for (device_index, has_replied) in replied.iter().enumerate() {
+            if *has_replied {
+                let wake_tick = self.wake_ticks[device_index];
+                if wake_tick != u64::MAX {
+                    self.wake_queue.push(Reverse((wake_tick, device_index)));
                 }
             }
         }
 
+        self.check_oscillation(tick)?;
         self.last_tick = tick;
-        return min_next_tick;
+        return Ok(self.next_wake_tick());
     }
 
-    pub fn get_last_tick(&self) -> u64 {
-        return self.last_tick;
+    /// Fails if any net changed more often than the oscillation limit allows.
+    /// Nets in a loop take turns changing, so when the first of them goes over
+    /// the limit the others are only at it. Every net that changed and reached
+    /// the limit is reported along with the devices driving them.
+    fn check_oscillation(&mut self, tick: u64) -> Result<(), SimError> {
+        let (max_changes, window) = match self.oscillation_limit {
+            Some(limit) => limit,
+            None => return Ok(()),
+        };
+        let mut oscillating = false;
+        for net in &mut self.nets {
+            while let Some(change_tick) = net.changes.front() {
+                if tick - change_tick < window {
+                    break;
+                }
+                net.changes.pop_front();
+            }
+            oscillating |= net.changes.len() > max_changes;
+        }
+        if !oscillating {
+            return Ok(());
+        }
+
+        let mut nets: Vec<String> = Vec::new();
+        let mut devices: Vec<usize> = Vec::new();
+        for net in &self.nets {
+            if !net.changes.is_empty() && net.changes.len() >= max_changes {
+                nets.push(net.name.clone());
+                for (connection_index, connection) in net.connections.iter().enumerate() {
+                    if net.driving[connection_index] {
+                        devices.push(connection.device);
+                    }
+                }
+            }
+        }
+        devices.sort_unstable();
+        devices.dedup();
+        return Err(SimError::Oscillation {
+            tick,
+            nets,
+            devices: devices
+                .iter()
+                .map(|device_index| self.device_wrappers[*device_index].name.clone())
+                .collect(),
+        });
     }
 
-    pub fn send_device_data(&self, device_index: usize, data: Box<dyn DeviceData>) {
-        self.device_wrappers[device_index]
-            .tx
-            .send(CircuitToDeviceMessage::Data { data })
-            .unwrap();
+    /// Receives a device's replies up to and including its `NextTick`, recording
+    /// the values it drives on its nets.
+    fn recv_replies(&mut self, device_index: usize, tick: u64) -> Result<(), SimError> {
+        let mut drove = false;
+        loop {
+            let device = &self.device_wrappers[device_index];
+            match device.recv()? {
+                DeviceToCircuitMessage::NextTick { tick } => {
+                    self.wake_ticks[device_index] = tick;
+                    if drove {
+                        // the drives answered the input changes recorded so far
+                        self.device_wrappers[device_index].changed_inputs.clear();
+                    }
+                    return Ok(());
+                }
+
+                DeviceToCircuitMessage::SetPin {
+                    pin,
+                    value,
+                    direction,
+                } => {
+                    // outputs and released pins alike, pin 0 is a placeholder
+                    if pin == 0 || pin >= self.pin_values[device_index].len() {
+                        return Err(SimError::UnknownPin {
+                            device: device.name.clone(),
+                            pin,
+                        });
+                    }
+                    let pin_width = self.pin_values[device_index][pin].get_width();
+                    if value.get_width() != pin_width {
+                        return Err(SimError::PinWidthMismatch {
+                            device: device.name.clone(),
+                            pin: device.get_pin_name(pin),
+                            expected: pin_width,
+                            actual: value.get_width(),
+                        });
+                    }
+                    drove = true;
+                    let drive_tick = self.get_drive_tick(device_index, pin, tick, value);
+                    for slot_index in 0..self.pin_nets[device_index][pin].len() {
+                        let slot = self.pin_nets[device_index][pin][slot_index];
+                        let drive = PendingDrive {
+                            tick: drive_tick + self.nets[slot.net].delay,
+                            slot,
+                            value,
+                            direction,
+                        };
+                        self.schedule_drive(tick, drive);
+                    }
+                }
+
+                DeviceToCircuitMessage::TimingViolation { violation } => {
+                    self.events.push(SimEvent::TimingViolation {
+                        tick,
+                        device: device.name.clone(),
+                        pin: device.get_pin_name(violation.get_pin()),
+                        check: violation.get_kind(),
+                        amount: violation.get_amount(),
+                    });
+                }
+
+                message => {
+                    return Err(device.unexpected_message(message));
+                }
+            }
+        }
     }
 
-    pub fn recv_device_data(
-        &self,
-        device_index: usize,
-        data: Box<dyn DeviceData>,
-    ) -> Box<dyn DeviceData> {
-        self.send_device_data(device_index, data);
-        let results = self.device_wrappers[device_index].rx.recv();
-        match results {
-            Result::Ok(message) => match message {
-                DeviceToCircuitMessage::Data { data } => {
-                    return data;
+    /// Gets the tick at which `value`, driven on `pin` at `tick`, leaves the
+    /// device. The delay is the longest pin-to-pin delay from the inputs the
+    /// device is responding to, or else the device's delay, and counts from the
+    /// last change of those inputs. A device that takes time to respond, like
+    /// the built-in devices that drive on the tick after their inputs change,
+    /// is thus not delayed twice, but it cannot drive before it responds.
+    fn get_drive_tick(&self, device_index: usize, pin: usize, tick: u64, value: BusValue) -> u64 {
+        let device = &self.device_wrappers[device_index];
+        let mut delay: Option<u64> = None;
+        for input_pin in &device.changed_inputs {
+            if let Some(pin_delay) = self.pin_delays.get(&(device_index, *input_pin, pin)) {
+                delay = Some(delay.unwrap_or(0).max(pin_delay.for_value(value)));
+            }
+        }
+        let delay = delay.unwrap_or_else(|| self.device_delays[device_index].for_value(value));
+        if device.changed_inputs.is_empty() {
+            return tick + delay;
+        }
+        return (device.input_tick + delay).max(tick);
+    }
+
+    /// Applies a drive made at `tick` now if it has no delay, otherwise queues
+    /// it. In inertial mode a new drive replaces any still queued for the same
+    /// connection.
+    fn schedule_drive(&mut self, tick: u64, drive: PendingDrive) {
+        if self.delay_mode == DelayMode::Inertial {
+            self.pending_drives
+                .retain(|pending| pending.slot != drive.slot);
+        }
+        if drive.tick == tick {
+            self.apply_drive(drive);
+            return;
+        }
+        if self.delay_mode == DelayMode::Inertial {
+            let net = &self.nets[drive.slot.net];
+            let (driver, driving) = Circuit::get_driver(net, &drive);
+            if net.drivers[drive.slot.connection] == driver
+                && net.driving[drive.slot.connection] == driving
+            {
+                // the value in flight was cancelled and the net already has this one
+                return;
+            }
+        }
+        self.pending_drives.push(drive);
+    }
+
+    fn apply_pending_drives(&mut self, tick: u64) {
+        let mut due: Vec<PendingDrive> = Vec::new();
+        self.pending_drives.retain(|pending| {
+            if pending.tick <= tick {
+                due.push(*pending);
+                return false;
+            }
+            return true;
+        });
+        due.sort_by_key(|pending| pending.tick);
+        for drive in due {
+            self.apply_drive(drive);
+        }
+    }
+
+    /// Records the value a connection drives on its net.
+    fn apply_drive(&mut self, drive: PendingDrive) {
+        let net = &mut self.nets[drive.slot.net];
+        let (driver, driving) = Circuit::get_driver(net, &drive);
+        if !driving {
+            net.new_receivers.push(drive.slot.connection);
+        }
+        net.drivers[drive.slot.connection] = driver;
+        net.driving[drive.slot.connection] = driving;
+        self.dirty_nets.push(drive.slot.net);
+    }
+
+    /// Gets the value a drive puts on the whole net and whether the connection
+    /// is driving at all.
+    fn get_driver(net: &NetState, drive: &PendingDrive) -> (BusValue, bool) {
+        let connection = &net.connections[drive.slot.connection];
+        let mut driver = BusValue::z(net.width);
+        match drive.direction {
+            PinDirection::Output | PinDirection::InOut => {
+                driver.set_slice(
+                    connection.net_lsb,
+                    drive.value.slice(connection.pin_lsb, connection.width),
+                );
+                return (driver, true);
+            }
+            PinDirection::Input => {
+                return (driver, false);
+            }
+        }
+    }
+
+    /// Resolves the drivers of every dirty net and returns, per device, the pins
+    /// whose value changed.
+    fn resolve_nets(&mut self, tick: u64) -> Vec<Vec<SetPin>> {
+        let mut changed_pins: Vec<(usize, usize)> = std::mem::take(&mut self.default_pins);
+        let mut dirty_nets = std::mem::take(&mut self.dirty_nets);
+        dirty_nets.sort_unstable();
+        dirty_nets.dedup();
+        for net_index in dirty_nets {
+            let net = &mut self.nets[net_index];
+            if net.resolution.is_contention(net.width, &net.drivers) {
+                let mut devices: Vec<String> = Vec::new();
+                for (connection_index, connection) in net.connections.iter().enumerate() {
+                    if net.drivers[connection_index] != BusValue::z(net.width) {
+                        devices.push(self.device_wrappers[connection.device].name.clone());
+                    }
                 }
+                self.events.push(SimEvent::BusContention {
+                    tick,
+                    net: net.name.clone(),
+                    devices,
+                });
+            }
 
-                _ => {
-                    panic!("unexpected data response");
+            let value = net.resolution.resolve(net.width, &net.drivers, net.pull);
+            let new_receivers = std::mem::take(&mut net.new_receivers);
+            for (connection_index, connection) in net.connections.iter().enumerate() {
+                if net.driving[connection_index] {
+                    continue;
                 }
-            },
-            Result::Err(_err) => {
-                panic!("failed to receive from device");
+                let bits = value.slice(connection.net_lsb, connection.width);
+                if bits != net.value.slice(connection.net_lsb, connection.width)
+                    || new_receivers.contains(&connection_index)
+                {
+                    self.pin_values[connection.device][connection.pin]
+                        .set_slice(connection.pin_lsb, bits);
+                    changed_pins.push((connection.device, connection.pin));
+                }
+            }
+            if value != net.value {
+                if let Some(waveform) = &mut self.waveform {
+                    waveform.record(tick, net_index, value);
+                }
+                if self.oscillation_limit.is_some() {
+                    net.changes.push_back(tick);
+                }
+            }
+            net.value = value;
+        }
+
+        let mut devices_set_pins: Vec<Vec<SetPin>> = Vec::new();
+        for _device in &self.device_wrappers {
+            devices_set_pins.push(Vec::new());
+        }
+        changed_pins.sort_unstable();
+        changed_pins.dedup();
+        for (device_index, pin) in changed_pins {
+            devices_set_pins[device_index].push(SetPin {
+                pin,
+                value: self.pin_values[device_index][pin],
+            });
+        }
+        return devices_set_pins;
+    }
+
+    /// Removes and returns the devices that must be notified of `tick`. The
+    /// threaded engine notifies every device; the event-driven engine only
+    /// notifies devices whose wake-up is due or that received data.
+    fn take_due_devices(&mut self, tick: u64) -> Vec<usize> {
+        let mut due = vec![self.engine == Engine::Threaded; self.device_wrappers.len()];
+        while let Some(Reverse((wake_tick, device_index))) = self.wake_queue.peek().cloned() {
+            if wake_tick > tick {
+                break;
             }
+            self.wake_queue.pop();
+            if self.wake_ticks[device_index] == wake_tick {
+                due[device_index] = true;
+            }
+        }
+        for device_index in self.data_wakes.borrow_mut().drain(..) {
+            due[device_index] = true;
         }
+
+        let mut due_devices = Vec::new();
+        for (device_index, is_due) in due.iter().enumerate() {
+            if *is_due {
+                self.wake_ticks[device_index] = u64::MAX;
+                due_devices.push(device_index);
+            }
+        }
+        return due_devices;
+    }
+
+    fn next_wake_tick(&mut self) -> u64 {
+        let drive_tick = self
+            .pending_drives
+            .iter()
+            .map(|pending| pending.tick)
+            .min()
+            .unwrap_or(u64::MAX);
+        while let Some(Reverse((wake_tick, device_index))) = self.wake_queue.peek().cloned() {
+            if self.wake_ticks[device_index] == wake_tick {
+                return wake_tick.min(drive_tick);
+            }
+            self.wake_queue.pop();
+        }
+        return drive_tick;
     }
 }
 
 impl Drop for Circuit {
     fn drop(&mut self) {
+        // devices that failed have already been reported through SimError
         for device in &self.device_wrappers {
-            device.tx.send(CircuitToDeviceMessage::Terminate).unwrap();
+            let _ = device.send(CircuitToDeviceMessage::Terminate);
         }
         for device in self.device_wrappers.iter_mut() {
-            device
-                .thread
-                .take()
-                .unwrap()
-                .join()
-                .expect("failed to join");
+            if let DeviceRunner::Threaded { thread, .. } = &mut device.runner {
+                if let Some(thread) = thread.get_mut().take() {
+                    let _ = thread.join();
+                }
+            }
         }
     }
 }
 
 #[derive(Debug)]
 struct DeviceWrapper {
-    index: usize,
     name: String,
-    tx: mpsc::Sender<CircuitToDeviceMessage>,
-    rx: mpsc::Receiver<DeviceToCircuitMessage>,
-    thread: Option<JoinHandle<()>>,
+    // pins[pin_index - 1] = description of the pin
+    pins: Vec<PinInfo>,
+    runner: DeviceRunner,
+    // first error the device failed with, returned for every later call
+    failure: RefCell<Option<SimError>>,
+    // inputs that changed on `input_tick`, the last tick any input changed,
+    // since the device last drove an output
+    changed_inputs: Vec<usize>,
+    input_tick: u64,
+}
+
+impl DeviceWrapper {
+    /// Records that input `pin` changed on `tick`, earlier changes are
+    /// forgotten.
+    fn record_input(&mut self, tick: u64, pin: usize) {
+        if self.input_tick != tick {
+            self.changed_inputs.clear();
+            self.input_tick = tick;
+        }
+        if !self.changed_inputs.contains(&pin) {
+            self.changed_inputs.push(pin);
+        }
+    }
+
+    fn get_pin_name(&self, pin: usize) -> String {
+        match pin
+            .checked_sub(1)
+            .and_then(|pin_index| self.pins.get(pin_index))
+        {
+            Some(pin) => pin.get_name().to_string(),
+            None => pin.to_string(),
+        }
+    }
+
+    fn send(&self, message: CircuitToDeviceMessage) -> Result<(), SimError> {
+        self.check_failure()?;
+        match &self.runner {
+            DeviceRunner::Threaded { tx, .. } => {
+                if tx.send(message).is_err() {
+                    return Err(self.thread_failure());
+                }
+            }
+            DeviceRunner::Direct { host, replies } => {
+                let mut new_replies = Vec::new();
+                let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                    host.borrow_mut().handle_message(message, &mut new_replies);
+                }));
+                if let Result::Err(payload) = result {
+                    return Err(self.fail(SimError::device_panicked(&self.name, payload)));
+                }
+                replies.borrow_mut().extend(new_replies);
+            }
+        }
+        return Ok(());
+    }
+
+    fn recv(&self) -> Result<DeviceToCircuitMessage, SimError> {
+        self.check_failure()?;
+        match &self.runner {
+            DeviceRunner::Threaded { rx, .. } => match rx.recv() {
+                Result::Ok(message) => {
+                    return Ok(message);
+                }
+                Result::Err(_err) => {
+                    return Err(self.thread_failure());
+                }
+            },
+            DeviceRunner::Direct { replies, .. } => match replies.borrow_mut().pop_front() {
+                Some(message) => {
+                    return Ok(message);
+                }
+                None => {
+                    return Err(self.fail(SimError::DeviceDisconnected {
+                        device: self.name.clone(),
+                    }));
+                }
+            },
+        }
+    }
+
+    fn unexpected_message(&self, message: DeviceToCircuitMessage) -> SimError {
+        return self.fail(SimError::UnexpectedMessage {
+            device: self.name.clone(),
+            message: format!("{:?}", message),
+        });
+    }
+
+    fn check_failure(&self) -> Result<(), SimError> {
+        if let Some(err) = &*self.failure.borrow() {
+            return Err(err.clone());
+        }
+        return Ok(());
+    }
+
+    fn fail(&self, err: SimError) -> SimError {
+        *self.failure.borrow_mut() = Some(err.clone());
+        return err;
+    }
+
+    /// Builds the error for a device thread that hung up, joining it to find
+    /// out whether it panicked.
+    fn thread_failure(&self) -> SimError {
+        let mut err = SimError::DeviceDisconnected {
+            device: self.name.clone(),
+        };
+        if let DeviceRunner::Threaded { thread, .. } = &self.runner {
+            if let Some(thread) = thread.borrow_mut().take() {
+                if let Result::Err(payload) = thread.join() {
+                    err = SimError::device_panicked(&self.name, payload);
+                }
+            }
+        }
+        return self.fail(err);
+    }
+}
+
+#[derive(Debug)]
+enum DeviceRunner {
+    Threaded {
+        tx: mpsc::Sender<CircuitToDeviceMessage>,
+        rx: mpsc::Receiver<DeviceToCircuitMessage>,
+        thread: RefCell<Option<JoinHandle<()>>>,
+    },
+    Direct {
+        host: RefCell<DeviceHost>,
+        replies: RefCell<VecDeque<DeviceToCircuitMessage>>,
+    },
+}
+
+impl DeviceRunner {
+    fn spawn(device: RefCell<Box<dyn Device>>) -> DeviceRunner {
+        let (device_to_circuit_tx, device_to_circuit_rx): (
+            mpsc::Sender<DeviceToCircuitMessage>,
+            mpsc::Receiver<DeviceToCircuitMessage>,
+        ) = mpsc::channel();
+        let (circuit_to_device_tx, circuit_to_device_rx): (
+            mpsc::Sender<CircuitToDeviceMessage>,
+            mpsc::Receiver<CircuitToDeviceMessage>,
+        ) = mpsc::channel();
+        let device_thread = thread::spawn(move || {
+            DeviceHost::new(device.into_inner()).run(device_to_circuit_tx, circuit_to_device_rx);
+        });
+        return DeviceRunner::Threaded {
+            tx: circuit_to_device_tx,
+            rx: device_to_circuit_rx,
+            thread: RefCell::new(Some(device_thread)),
+        };
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct NetSlot {
+    net: usize,
+    connection: usize,
+}
+
+/// A value driven onto one net connection that reaches the net at `tick`.
+#[derive(Debug, Copy, Clone)]
+struct PendingDrive {
+    tick: u64,
+    slot: NetSlot,
+    value: BusValue,
+    direction: PinDirection,
+}
+
+#[derive(Debug)]
+struct NetState {
+    name: String,
+    width: usize,
+    connections: Vec<PinRef>,
+    // drivers[connection_index] = value the connection drives on the whole net, Z
+    // outside of its bits or when not driving
+    drivers: Vec<BusValue>,
+    // driving[connection_index] = true while the connection drives the net, receivers get its value
+    driving: Vec<bool>,
+    // connections that stopped driving since the net was last resolved and need its current value
+    new_receivers: Vec<usize>,
+    resolution: NetResolution,
+    pull: NetPull,
+    value: BusValue,
+    // ticks the net changed at, within the oscillation window
+    changes: VecDeque<u64>,
+    // wire delay added to every drive onto the net
+    delay: u64,
 }
 
 #[derive(Debug)]
 struct PinRef {
     device: usize,
     pin: usize,
+    pin_lsb: usize,
+    net_lsb: usize,
+    width: usize,
 }
 
 #[derive(Debug)]
 struct SetPin {
     pin: usize,
-    value: u32,
+    value: BusValue,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::device::AndGate;
+    use crate::device::Device;
+    use crate::device::DeviceResponse;
+    use crate::device::NotGate;
+    use crate::device::TestProbe;
+    use crate::Circuit;
+    use crate::CircuitBuilder;
+    use crate::Engine;
+    use crate::LogicValue;
+    use crate::PinDirection;
+    use crate::PinInfo;
+    use crate::PinValue;
+    use crate::RunSummary;
+    use crate::SimError;
+    use std::cell::RefCell;
+
+    /// Panics the second time it is woken.
+    struct PanicsOnTick {}
+
+    impl Device for PanicsOnTick {
+        fn get_name(&self) -> &str {
+            return "broken";
+        }
+
+        fn get_pin_count(&self) -> usize {
+            return 0;
+        }
+
+        fn get_pin_info(&self, pin: usize) -> PinInfo {
+            panic!("no pin {}", pin);
+        }
+
+        fn on_tick(&mut self, tick: u64) -> DeviceResponse {
+            if tick > 1 {
+                panic!("broken at tick {}", tick);
+            }
+            return DeviceResponse::wake_at(tick + 1);
+        }
+    }
+
+    #[test]
+    fn device_panic_is_an_error() {
+        for engine in [Engine::Threaded, Engine::EventDriven] {
+            let devices: Vec<RefCell<Box<dyn Device>>> =
+                vec![RefCell::new(Box::new(PanicsOnTick {}))];
+            let mut circuit = Circuit::with_engine(devices, vec![], engine).unwrap();
+            assert_eq!(Ok(2), circuit.tick(1));
+            assert_eq!(
+                Err(SimError::NonIncreasingTick {
+                    tick: 1,
+                    last_tick: 1
+                }),
+                circuit.tick(1)
+            );
+
+            let err = SimError::DevicePanicked {
+                device: "broken".to_string(),
+                message: "broken at tick 2".to_string(),
+            };
+            assert_eq!(Err(err.clone()), circuit.tick(2));
+            assert_eq!(Err(err), circuit.tick(3));
+        }
+    }
+
+    /// Drives pin 7 or releases pin 0, neither of which it has.
+    struct WrongPin {
+        release: bool,
+    }
+
+    impl Device for WrongPin {
+        fn get_name(&self) -> &str {
+            return "wrong";
+        }
+
+        fn get_pin_count(&self) -> usize {
+            return 1;
+        }
+
+        fn get_pin_info(&self, _pin: usize) -> PinInfo {
+            return PinInfo::new("Y", PinDirection::Output);
+        }
+
+        fn on_tick(&mut self, _tick: u64) -> DeviceResponse {
+            let mut response = DeviceResponse::new();
+            if self.release {
+                response.release_pin(0);
+            } else {
+                response.set_output(7, LogicValue::One);
+            }
+            return response;
+        }
+    }
+
+    #[test]
+    fn unknown_pin_is_an_error() {
+        for engine in [Engine::Threaded, Engine::EventDriven] {
+            for (release, pin) in [(false, 7), (true, 0)] {
+                let devices: Vec<RefCell<Box<dyn Device>>> =
+                    vec![RefCell::new(Box::new(WrongPin { release }))];
+                let mut circuit = Circuit::with_engine(devices, vec![], engine).unwrap();
+                let err = SimError::UnknownPin {
+                    device: "wrong".to_string(),
+                    pin,
+                };
+                assert_eq!(Err(err), circuit.tick(1));
+            }
+        }
+    }
+
+    /// Asks to be woken on every tick.
+    struct Ticker {}
+
+    impl Device for Ticker {
+        fn get_name(&self) -> &str {
+            return "ticker";
+        }
+
+        fn get_pin_count(&self) -> usize {
+            return 0;
+        }
+
+        fn get_pin_info(&self, pin: usize) -> PinInfo {
+            panic!("no pin {}", pin);
+        }
+
+        fn on_tick(&mut self, tick: u64) -> DeviceResponse {
+            return DeviceResponse::wake_at(tick + 1);
+        }
+    }
+
+    #[test]
+    fn run_until_stable() {
+        let mut builder = CircuitBuilder::new();
+        builder.set_engine(Engine::EventDriven);
+        builder.add_device(TestProbe::new("a", LogicValue::Zero, PinDirection::Output));
+        builder.add_device(AndGate::new("and"));
+        builder.add_device(TestProbe::new("y", LogicValue::Z, PinDirection::Input));
+        builder.connect("a.pin", "and.A");
+        builder.connect("a.pin", "and.B");
+        builder.connect("and.Y", "y.pin");
+        let (mut circuit, _devices) = builder.build().unwrap();
+
+        assert_eq!(
+            RunSummary::new(2, 2, true),
+            circuit.run_until_stable().unwrap()
+        );
+        TestProbe::set_output_high(&circuit, "a").unwrap();
+        assert_eq!(RunSummary::new(10, 2, true), circuit.run_until(10).unwrap());
+        assert_eq!(
+            LogicValue::One,
+            TestProbe::get_value(&circuit, "y").unwrap()
+        );
+        // reading the probe wakes it on the next tick
+        assert_eq!(RunSummary::new(11, 1, true), circuit.run_for(5).unwrap());
+        assert_eq!(RunSummary::new(11, 0, true), circuit.run_for(5).unwrap());
+    }
+
+    #[test]
+    fn event_budget() {
+        let devices: Vec<RefCell<Box<dyn Device>>> = vec![RefCell::new(Box::new(Ticker {}))];
+        let mut circuit = Circuit::with_engine(devices, vec![], Engine::EventDriven).unwrap();
+        assert_eq!(RunSummary::new(3, 3, false), circuit.run_for(3).unwrap());
+        assert_eq!(RunSummary::new(5, 2, false), circuit.run_until(5).unwrap());
+
+        circuit.set_event_budget(10);
+        assert_eq!(
+            Err(SimError::EventBudgetExceeded {
+                budget: 10,
+                tick: 15
+            }),
+            circuit.run_until_stable()
+        );
+    }
+
+    /// Inverts input `A` onto output `Y` one tick later.
+    struct DelayedInverter {
+        next: LogicValue,
+    }
+
+    impl Device for DelayedInverter {
+        fn get_name(&self) -> &str {
+            return "inv";
+        }
+
+        fn get_pin_count(&self) -> usize {
+            return 2;
+        }
+
+        fn get_pin_info(&self, pin: usize) -> PinInfo {
+            if pin == 1 {
+                return PinInfo::new("A", PinDirection::Input);
+            }
+            return PinInfo::new("Y", PinDirection::Output);
+        }
+
+        fn on_tick(&mut self, _tick: u64) -> DeviceResponse {
+            let mut response = DeviceResponse::new();
+            response.set_output(2, self.next);
+            return response;
+        }
+
+        fn on_inputs_changed(&mut self, tick: u64, pins: &[PinValue]) -> DeviceResponse {
+            self.next = !pins[0].get_logic_value();
+            return DeviceResponse::wake_at(tick + 1);
+        }
+    }
+
+    #[test]
+    fn oscillation() {
+        let mut builder = CircuitBuilder::new();
+        builder.set_engine(Engine::EventDriven);
+        builder.add_device(TestProbe::new(
+            "enable",
+            LogicValue::Zero,
+            PinDirection::Output,
+        ));
+        builder.add_device(AndGate::new("and"));
+        builder.add_device(DelayedInverter {
+            next: LogicValue::Zero,
+        });
+        builder.connect("enable.pin", "and.A");
+        builder.connect("and.Y", "inv.A");
+        builder.connect("inv.Y", "and.B");
+        let (mut circuit, _devices) = builder.build().unwrap();
+        circuit.set_oscillation_limit(4, 10);
+        assert!(circuit.run_until_stable().unwrap().is_quiescent());
+
+        TestProbe::set_output_high(&circuit, "enable").unwrap();
+        match circuit.run_until_stable() {
+            Err(SimError::Oscillation {
+                tick: _,
+                nets,
+                devices,
+            }) => {
+                assert_eq!(vec!["net1".to_string(), "net2".to_string()], nets);
+                assert_eq!(vec!["and".to_string(), "inv".to_string()], devices);
+            }
+            result => panic!("expected oscillation, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn oscillation_reports_only_changed_nets() {
+        let mut builder = CircuitBuilder::new();
+        builder.add_device(TestProbe::new("in", LogicValue::Zero, PinDirection::Output));
+        builder.add_device(NotGate::new("not"));
+        builder.add_device(TestProbe::new("out", LogicValue::Z, PinDirection::Input));
+        builder.add_device(TestProbe::new(
+            "idle",
+            LogicValue::Zero,
+            PinDirection::Output,
+        ));
+        builder.add_device(TestProbe::new("sink", LogicValue::Z, PinDirection::Input));
+        builder.connect("in.pin", "not.A");
+        builder.connect("not.Y", "out.pin");
+        builder.connect("idle.pin", "sink.pin");
+        let (mut circuit, _devices) = builder.build().unwrap();
+        assert!(circuit.run_until_stable().unwrap().is_quiescent());
+
+        // no net is oscillating, but any change is over a limit of 0
+        circuit.set_oscillation_limit(0, 100);
+        TestProbe::set_output_high(&circuit, "in").unwrap();
+        match circuit.run_until_stable() {
+            Err(SimError::Oscillation { nets, .. }) => {
+                assert_eq!(vec!["net0".to_string()], nets);
+            }
+            result => panic!("expected oscillation, got {:?}", result),
+        }
+    }
 }
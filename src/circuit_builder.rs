@@ -0,0 +1,207 @@
+use crate::device::Device;
+use crate::device::SubCircuit;
+use crate::Circuit;
+use crate::Delay;
+use crate::DelayMode;
+use crate::DeviceHandle;
+use crate::Engine;
+use crate::Net;
+use crate::NetConnection;
+use crate::SimError;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+/// Collects devices and the connections between their pins, then builds a
+/// `Circuit`.
+#[derive(Debug, Default)]
+pub struct CircuitBuilder {
+    devices: Vec<RefCell<Box<dyn Device>>>,
+    nets: Vec<Net>,
+    // groups of "device.pin" names joined by connect, each becomes a net
+    connected_pins: Vec<Vec<String>>,
+    engine: Engine,
+    device_delays: Vec<(String, Delay)>,
+    // (device, input pin, output pin, delay) by name
+    pin_delays: Vec<(String, String, String, Delay)>,
+    delay_mode: DelayMode,
+}
+
+impl CircuitBuilder {
+    pub fn new() -> CircuitBuilder {
+        return CircuitBuilder::default();
+    }
+
+    pub fn set_engine(&mut self, engine: Engine) {
+        self.engine = engine;
+    }
+
+    /// Sets the delay of every output of the device called `device`, counted
+    /// from the input change the device responds to and replacing the tick
+    /// the built-in devices take, see `Delay` and `Circuit::set_device_delay`.
+    pub fn set_device_delay(&mut self, device: &str, delay: Delay) {
+        self.device_delays.push((device.to_string(), delay));
+    }
+
+    /// Sets the delay from input pin `input` to output pin `output` of the
+    /// device called `device`, see `Circuit::set_pin_delay`.
+    pub fn set_pin_delay(&mut self, device: &str, input: &str, output: &str, delay: Delay) {
+        self.pin_delays.push((
+            device.to_string(),
+            input.to_string(),
+            output.to_string(),
+            delay,
+        ));
+    }
+
+    pub fn set_delay_mode(&mut self, delay_mode: DelayMode) {
+        self.delay_mode = delay_mode;
+    }
+
+    /// Adds a device, it is referred to by `Device::get_name` in `connect`.
+    pub fn add_device<T: Device + 'static>(&mut self, device: T) -> DeviceHandle<T> {
+        self.devices.push(RefCell::new(Box::new(device)));
+        return DeviceHandle::new(self.devices.len() - 1);
+    }
+
+    /// Adds a net built by hand, e.g. a named bus or a net with a pull.
+    pub fn add_net(&mut self, net: Net) {
+        self.nets.push(net);
+    }
+
+    /// Connects two pins by `"device.pin"` name. Pins connected to each other
+    /// directly or through other pins end up on the same net.
+    pub fn connect(&mut self, pin1: &str, pin2: &str) {
+        let group1 = self.find_group(pin1);
+        let group2 = self.find_group(pin2);
+        match (group1, group2) {
+            (Some(group1), Some(group2)) => {
+                if group1 != group2 {
+                    let pins = self.connected_pins.remove(group2);
+                    let group1 = if group2 < group1 { group1 - 1 } else { group1 };
+                    self.connected_pins[group1].extend(pins);
+                }
+            }
+            (Some(group), None) => self.connected_pins[group].push(pin2.to_string()),
+            (None, Some(group)) => self.connected_pins[group].push(pin1.to_string()),
+            (None, None) => self
+                .connected_pins
+                .push(vec![pin1.to_string(), pin2.to_string()]),
+        }
+    }
+
+    /// Builds the circuit and returns it with the index of every device by name.
+    pub fn build(self) -> Result<(Circuit, HashMap<String, usize>), SimError> {
+        let mut device_indexes: HashMap<String, usize> = HashMap::new();
+        for (device_index, device) in self.devices.iter().enumerate() {
+            device_indexes
+                .entry(device.borrow().get_name().to_string())
+                .or_insert(device_index);
+        }
+        let mut nets = self.nets;
+        for pins in self.connected_pins {
+            nets.push(Net::new(
+                pins.iter().map(|pin| NetConnection::named(pin)).collect(),
+            ));
+        }
+        let mut circuit = Circuit::with_engine(self.devices, nets, self.engine)?;
+        circuit.set_delay_mode(self.delay_mode);
+        for (device, delay) in self.device_delays {
+            let device_index = CircuitBuilder::find_device(&circuit, &device)?;
+            circuit.set_device_delay(device_index, delay)?;
+        }
+        for (device, input, output, delay) in self.pin_delays {
+            let device_index = CircuitBuilder::find_device(&circuit, &device)?;
+            let input_pin = CircuitBuilder::find_pin(&circuit, device_index, &device, &input)?;
+            let output_pin = CircuitBuilder::find_pin(&circuit, device_index, &device, &output)?;
+            circuit.set_pin_delay(device_index, input_pin, output_pin, delay)?;
+        }
+        return Ok((circuit, device_indexes));
+    }
+
+    /// Packages the devices and nets as a `SubCircuit` instead of building a
+    /// circuit, the engine and delays set on this builder are not used.
+    pub fn into_sub_circuit(self, name: &str) -> SubCircuit {
+        let mut nets = self.nets;
+        for pins in self.connected_pins {
+            nets.push(Net::new(
+                pins.iter().map(|pin| NetConnection::named(pin)).collect(),
+            ));
+        }
+        return SubCircuit::new(name, self.devices, nets);
+    }
+
+    fn find_device(circuit: &Circuit, device: &str) -> Result<usize, SimError> {
+        return circuit
+            .get_device_index(device)
+            .ok_or_else(|| SimError::UnknownDeviceName {
+                name: device.to_string(),
+            });
+    }
+
+    fn find_pin(
+        circuit: &Circuit,
+        device_index: usize,
+        device: &str,
+        pin: &str,
+    ) -> Result<usize, SimError> {
+        return circuit.get_pin_by_name(device_index, pin).ok_or_else(|| {
+            SimError::UnknownPinName {
+                device: device.to_string(),
+                pin: pin.to_string(),
+            }
+        });
+    }
+
+    fn find_group(&self, pin: &str) -> Option<usize> {
+        return self
+            .connected_pins
+            .iter()
+            .position(|pins| pins.iter().any(|other| other == pin));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::device::AndGate;
+    use crate::device::TestProbe;
+    use crate::CircuitBuilder;
+    use crate::LogicValue;
+    use crate::PinDirection;
+    use crate::SimError;
+
+    #[test]
+    fn builds_with_handles_and_names() {
+        let mut builder = CircuitBuilder::new();
+        let input = builder.add_device(TestProbe::new("in", LogicValue::One, PinDirection::Output));
+        let and_gate = builder.add_device(AndGate::new("and"));
+        let output = builder.add_device(TestProbe::new("out", LogicValue::Z, PinDirection::Input));
+        // both inputs end up on the same net as the probe
+        builder.connect("in.pin", "and.A");
+        builder.connect("and.B", "in.pin");
+        builder.connect("and.Y", "out.pin");
+        let (mut circuit, devices) = builder.build().unwrap();
+        assert_eq!(Some(&and_gate.get_index()), devices.get("and"));
+        assert_eq!(Some(&output.get_index()), devices.get("out"));
+
+        circuit.tick(1).unwrap();
+        circuit.tick(2).unwrap();
+        assert_eq!(
+            LogicValue::One,
+            TestProbe::get_value(&circuit, output).unwrap()
+        );
+
+        TestProbe::set_output_low(&circuit, input).unwrap();
+        circuit.tick(3).unwrap();
+        circuit.tick(4).unwrap();
+        assert_eq!(
+            LogicValue::Zero,
+            TestProbe::get_value(&circuit, "out").unwrap()
+        );
+        assert_eq!(
+            Err(SimError::UnknownDeviceName {
+                name: "missing".to_string()
+            }),
+            TestProbe::get_value(&circuit, "missing")
+        );
+    }
+}
@@ -0,0 +1,231 @@
+use crate::BusValue;
+use crate::LogicValue;
+
+/// Number of ticks between a device driving a value and the value reaching its
+/// nets. Bits driven to 1 take the rise delay, bits driven to 0 the fall delay
+/// and X or Z bits the longer of the two.
+///
+/// When the device is responding to an input change the delay counts from the
+/// change, so it is the whole propagation delay. The built-in devices respond
+/// on the tick after their inputs change, so for them a delay of 0 or 1 is the
+/// same as no delay and a delay of 10 makes an output change 10 ticks after
+/// the input.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct Delay {
+    rise: u64,
+    fall: u64,
+}
+
+impl Delay {
+    pub fn new(rise: u64, fall: u64) -> Delay {
+        return Delay { rise, fall };
+    }
+
+    /// Creates a delay with the same rise and fall time.
+    pub fn fixed(ticks: u64) -> Delay {
+        return Delay::new(ticks, ticks);
+    }
+
+    pub fn get_rise(&self) -> u64 {
+        return self.rise;
+    }
+
+    pub fn get_fall(&self) -> u64 {
+        return self.fall;
+    }
+
+    /// Gets the delay of driving `value`, the longest delay of any of its bits.
+    pub fn for_value(&self, value: BusValue) -> u64 {
+        let mut delay = 0;
+        for bit in 0..value.get_width() {
+            let bit_delay = match value.get_bit(bit) {
+                LogicValue::One => self.rise,
+                LogicValue::Zero => self.fall,
+                LogicValue::X | LogicValue::Z => self.rise.max(self.fall),
+            };
+            delay = delay.max(bit_delay);
+        }
+        return delay;
+    }
+}
+
+/// How delayed values that have not reached their net yet are treated when the
+/// device drives a new value.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DelayMode {
+    /// The new value replaces any value still in flight, so pulses shorter than
+    /// the delay never reach the net.
+    Inertial,
+    /// Every value reaches the net after its delay, pulses are kept.
+    Transport,
+}
+
+impl Default for DelayMode {
+    fn default() -> DelayMode {
+        return DelayMode::Inertial;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::device::AndGate;
+    use crate::device::Device;
+    use crate::device::DeviceResponse;
+    use crate::device::TestProbe;
+    use crate::Circuit;
+    use crate::CircuitBuilder;
+    use crate::Delay;
+    use crate::DelayMode;
+    use crate::Engine;
+    use crate::LogicValue;
+    use crate::Net;
+    use crate::NetConnection;
+    use crate::PinDirection;
+    use crate::PinInfo;
+    use crate::PinValue;
+
+    /// Copies input `A` to output `Y`, any delay comes from the circuit.
+    struct Buffer {}
+
+    impl Device for Buffer {
+        fn get_name(&self) -> &str {
+            return "buf";
+        }
+
+        fn get_pin_count(&self) -> usize {
+            return 2;
+        }
+
+        fn get_pin_info(&self, pin: usize) -> PinInfo {
+            if pin == 1 {
+                return PinInfo::new("A", PinDirection::Input);
+            }
+            return PinInfo::new("Y", PinDirection::Output);
+        }
+
+        fn on_inputs_changed(&mut self, _tick: u64, pins: &[PinValue]) -> DeviceResponse {
+            let mut response = DeviceResponse::new();
+            response.set_output(2, pins[0].get_value());
+            return response;
+        }
+    }
+
+    /// `in` drives a buffer with a rise delay of 2 and a fall delay of 3, whose
+    /// output reaches `out` over a wire with a delay of 1.
+    fn create_circuit(delay_mode: DelayMode) -> Circuit {
+        let mut builder = CircuitBuilder::new();
+        builder.add_device(TestProbe::new("in", LogicValue::Zero, PinDirection::Output));
+        builder.add_device(Buffer {});
+        builder.add_device(TestProbe::new("out", LogicValue::Z, PinDirection::Input));
+        builder.connect("in.pin", "buf.A");
+        let mut out = Net::named(
+            "out",
+            vec![
+                NetConnection::named("buf.Y"),
+                NetConnection::named("out.pin"),
+            ],
+        );
+        out.set_delay(1);
+        builder.add_net(out);
+        builder.set_device_delay("buf", Delay::new(2, 3));
+        builder.set_delay_mode(delay_mode);
+        let (mut circuit, _devices) = builder.build().unwrap();
+        circuit.start_waveform();
+        return circuit;
+    }
+
+    /// Drives a one tick high pulse at tick 6, shorter than the rise delay.
+    fn send_pulse(circuit: &mut Circuit) {
+        circuit.run_until(5).unwrap();
+        TestProbe::set_output_high(circuit, "in").unwrap();
+        circuit.run_until(6).unwrap();
+        TestProbe::set_output_low(circuit, "in").unwrap();
+        circuit.run_until(20).unwrap();
+    }
+
+    /// Gets the values of `out` at ticks 4, 5, 8, 9, 10, 11 and 20, e.g. `z001100`.
+    fn get_values(circuit: &Circuit) -> String {
+        let waveform = circuit.get_waveform().unwrap();
+        return [4, 5, 8, 9, 10, 11, 20]
+            .iter()
+            .map(|tick| waveform.get_value_at("out", *tick).unwrap().to_string())
+            .collect();
+    }
+
+    #[test]
+    fn inertial_delay_swallows_short_pulses() {
+        let mut circuit = create_circuit(DelayMode::Inertial);
+        send_pulse(&mut circuit);
+        assert_eq!("z000000", get_values(&circuit));
+    }
+
+    #[test]
+    fn transport_delay_keeps_pulses() {
+        let mut circuit = create_circuit(DelayMode::Transport);
+        send_pulse(&mut circuit);
+        // the pulse rises after 2 + 1 ticks and falls after 3 + 1
+        assert_eq!("z001100", get_values(&circuit));
+    }
+
+    #[test]
+    fn pin_to_pin_delay() {
+        let mut builder = CircuitBuilder::new();
+        builder.add_device(TestProbe::new("in", LogicValue::One, PinDirection::Output));
+        builder.add_device(Buffer {});
+        builder.add_device(TestProbe::new("out", LogicValue::Z, PinDirection::Input));
+        builder.connect("in.pin", "buf.A");
+        builder.connect("buf.Y", "out.pin");
+        builder.set_device_delay("buf", Delay::fixed(2));
+        builder.set_pin_delay("buf", "A", "Y", Delay::fixed(5));
+        let (mut circuit, _devices) = builder.build().unwrap();
+        circuit.run_until(5).unwrap();
+        assert_eq!(
+            LogicValue::Z,
+            TestProbe::get_value(&circuit, "out").unwrap()
+        );
+        circuit.run_until(6).unwrap();
+        assert_eq!(
+            LogicValue::One,
+            TestProbe::get_value(&circuit, "out").unwrap()
+        );
+    }
+
+    #[test]
+    fn built_in_device_delays() {
+        for engine in [Engine::Threaded, Engine::EventDriven] {
+            let mut builder = CircuitBuilder::new();
+            builder.set_engine(engine);
+            builder.add_device(TestProbe::new("a", LogicValue::One, PinDirection::Output));
+            builder.add_device(TestProbe::new("b", LogicValue::One, PinDirection::Output));
+            builder.add_device(AndGate::new("and"));
+            builder.add_device(TestProbe::new("out", LogicValue::Z, PinDirection::Input));
+            builder.connect("a.pin", "and.A");
+            builder.connect("b.pin", "and.B");
+            builder.connect("and.Y", "out.pin");
+            builder.set_device_delay("and", Delay::fixed(4));
+            builder.set_pin_delay("and", "A", "Y", Delay::fixed(10));
+            let (mut circuit, _devices) = builder.build().unwrap();
+            let get_value = |circuit: &mut Circuit, tick: u64| {
+                circuit.run_until(tick).unwrap();
+                return TestProbe::get_value(circuit, "out").unwrap();
+            };
+            // both inputs change on tick 1, A's pin delay is the longest
+            assert_eq!(LogicValue::Z, get_value(&mut circuit, 10));
+            assert_eq!(LogicValue::One, get_value(&mut circuit, 11));
+
+            // B changes on tick 21, the device delay replaces the gate's own tick
+            circuit.tick(20).unwrap();
+            TestProbe::set_output_low(&circuit, "b").unwrap();
+            assert_eq!(LogicValue::One, get_value(&mut circuit, 24));
+            assert_eq!(LogicValue::Zero, get_value(&mut circuit, 25));
+
+            TestProbe::set_output_high(&circuit, "b").unwrap();
+            assert_eq!(LogicValue::One, get_value(&mut circuit, 40));
+            // A changes on tick 51
+            circuit.tick(50).unwrap();
+            TestProbe::set_output_low(&circuit, "a").unwrap();
+            assert_eq!(LogicValue::One, get_value(&mut circuit, 60));
+            assert_eq!(LogicValue::Zero, get_value(&mut circuit, 61));
+        }
+    }
+}
@@ -1,161 +1,199 @@
 use crate::device::Device;
-use crate::CircuitToDeviceMessage;
-use crate::DeviceToCircuitMessage;
-use crate::PinDirection;
-use std::sync::mpsc;
+use crate::device::DeviceResponse;
+use crate::device::LogicGate;
+use crate::LogicValue;
+use crate::PinInfo;
+use crate::PinValue;
 
+/// Drives 1 when every input is 1 and 0 when any input is 0.
 #[derive(Debug)]
 pub struct AndGate {
-    name: String,
-    input1: bool,
-    input2: bool,
-    last_result: bool,
-    next_result: bool,
+    gate: LogicGate,
 }
 
 impl AndGate {
     pub const PIN_INPUT1: usize = 1;
     pub const PIN_INPUT2: usize = 2;
+    /// Output of a two-input gate, see `get_output_pin` for wider ones.
     pub const PIN_OUTPUT: usize = 3;
 
     pub fn new(name: &str) -> AndGate {
-        AndGate {
-            name: name.to_string(),
-            input1: false,
-            input2: false,
-            last_result: false,
-            next_result: false,
-        }
+        return AndGate::with_inputs(name, 2);
     }
-}
 
-impl Device for AndGate {
-    fn run(
-        &mut self,
-        tx: mpsc::Sender<DeviceToCircuitMessage>,
-        rx: mpsc::Receiver<CircuitToDeviceMessage>,
-    ) {
-        let mut run = true;
-        while run {
-            match rx.recv() {
-                Result::Ok(message) => match message {
-                    CircuitToDeviceMessage::NextTick { tick: _ } => {
-                        if self.next_result != self.last_result {
-                            tx.send(DeviceToCircuitMessage::SetPin {
-                                pin: AndGate::PIN_OUTPUT,
-                                value: u32::MAX,
-                                direction: PinDirection::Output,
-                            })
-                            .unwrap();
-                            self.last_result = self.next_result;
-                        }
-                        tx.send(DeviceToCircuitMessage::NextTick { tick: u64::MAX })
-                            .unwrap();
-                    }
-                    CircuitToDeviceMessage::Terminate => {
-                        run = false;
-                    }
-                    CircuitToDeviceMessage::SetPin {
-                        tick,
-                        pin,
-                        value,
-                        last,
-                    } => {
-                        if pin == AndGate::PIN_INPUT1 {
-                            self.input1 = value > 0;
-                        } else if pin == AndGate::PIN_INPUT2 {
-                            self.input2 = value > 0;
-                        } else {
-                            panic!("cannot set pin {} on and gate", pin);
-                        }
-                        if last {
-                            let new_result = self.input1 && self.input2;
-                            if self.last_result != new_result {
-                                self.next_result = new_result;
-                                tx.send(DeviceToCircuitMessage::NextTick { tick: tick + 1 })
-                                    .unwrap();
-                            } else {
-                                tx.send(DeviceToCircuitMessage::NextTick { tick: u64::MAX })
-                                    .unwrap();
-                            }
-                        }
-                    }
-                    CircuitToDeviceMessage::Data { data: _ } => {
-                        panic!("not expecting data");
-                    }
-                },
-                Result::Err(_err) => {
-                    run = false;
-                }
-            }
-        }
+    /// A gate with inputs `A`, `B`, `C`, ... on pins 1 to `input_count`.
+    pub fn with_inputs(name: &str, input_count: usize) -> AndGate {
+        return AndGate {
+            gate: LogicGate::new(name, input_count, AndGate::evaluate),
+        };
+    }
+
+    pub fn get_output_pin(&self) -> usize {
+        return self.gate.get_output_pin();
     }
 
+    pub(crate) fn evaluate(inputs: &[LogicValue]) -> LogicValue {
+        return inputs
+            .iter()
+            .fold(LogicValue::One, |result, input| result & *input);
+    }
+}
+
+impl Device for AndGate {
     fn get_name(&self) -> &str {
-        return &self.name;
+        return self.gate.get_name();
     }
 
     fn get_pin_count(&self) -> usize {
-        return 3;
+        return self.gate.get_pin_count();
+    }
+
+    fn get_pin_info(&self, pin: usize) -> PinInfo {
+        return self.gate.get_pin_info(pin);
+    }
+
+    fn on_tick(&mut self, tick: u64) -> DeviceResponse {
+        return self.gate.on_tick(tick);
+    }
+
+    fn on_inputs_changed(&mut self, tick: u64, pins: &[PinValue]) -> DeviceResponse {
+        return self.gate.on_inputs_changed(tick, pins);
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::device::logic_gate::tests::check_gate;
     use crate::device::AndGate;
-    use crate::device::Device;
     use crate::device::TestProbe;
     use crate::Circuit;
-    use crate::Net;
-    use crate::NetConnection;
+    use crate::CircuitBuilder;
+    use crate::Engine;
+    use crate::LogicValue;
     use crate::PinDirection;
-    use std::cell::RefCell;
+
+    const DEVICE_INPUT1: &str = "input1_tp";
+    const DEVICE_INPUT2: &str = "input2_tp";
+    const DEVICE_OUTPUT: &str = "output_tp";
+
+    fn create_circuit(engine: Engine) -> Circuit {
+        let mut builder = CircuitBuilder::new();
+        builder.set_engine(engine);
+        builder.add_device(AndGate::new("and"));
+        builder.add_device(TestProbe::new(
+            DEVICE_INPUT1,
+            LogicValue::Zero,
+            PinDirection::Output,
+        ));
+        builder.add_device(TestProbe::new(
+            DEVICE_INPUT2,
+            LogicValue::Zero,
+            PinDirection::Output,
+        ));
+        builder.add_device(TestProbe::new(
+            DEVICE_OUTPUT,
+            LogicValue::Z,
+            PinDirection::Input,
+        ));
+        builder.connect("input1_tp.pin", "and.A");
+        builder.connect("input2_tp.pin", "and.B");
+        builder.connect("and.Y", "output_tp.pin");
+        let (circuit, _devices) = builder.build().unwrap();
+        return circuit;
+    }
 
     #[test]
     fn it_works() {
-        const DEVICE_AND_GATE: usize = 0;
-        const DEVICE_INPUT1: usize = 1;
-        const DEVICE_INPUT2: usize = 2;
-        const DEVICE_OUTPUT: usize = 3;
-        let and_gate = AndGate::new("and");
-        let input1_tp = TestProbe::new("input1_tp", 0, PinDirection::Output);
-        let input2_tp = TestProbe::new("input2_tp", 0, PinDirection::Output);
-        let output_tp = TestProbe::new("output_tp", 0, PinDirection::Input);
-        let devices: Vec<RefCell<Box<dyn Device>>> = vec![
-            RefCell::new(Box::new(and_gate)),
-            RefCell::new(Box::new(input1_tp)),
-            RefCell::new(Box::new(input2_tp)),
-            RefCell::new(Box::new(output_tp)),
-        ];
-        let net0 = Net::new(vec![
-            NetConnection::new(DEVICE_AND_GATE, AndGate::PIN_INPUT1),
-            NetConnection::new(DEVICE_INPUT1, TestProbe::PIN),
-        ]);
-        let net1 = Net::new(vec![
-            NetConnection::new(DEVICE_AND_GATE, AndGate::PIN_INPUT2),
-            NetConnection::new(DEVICE_INPUT2, TestProbe::PIN),
-        ]);
-        let net2 = Net::new(vec![
-            NetConnection::new(DEVICE_AND_GATE, AndGate::PIN_OUTPUT),
-            NetConnection::new(DEVICE_OUTPUT, TestProbe::PIN),
-        ]);
-        let nets = vec![net0, net1, net2];
-        let mut circuit = Circuit::new(devices, nets);
-        let mut next_tick = circuit.tick(1);
-        assert_eq!(u64::MAX, next_tick);
+        check_and_gate(Engine::Threaded);
+    }
+
+    #[test]
+    fn it_works_event_driven() {
+        check_and_gate(Engine::EventDriven);
+    }
 
-        TestProbe::set_output_high(&circuit, DEVICE_INPUT1);
-        next_tick = circuit.tick(2);
+    fn check_and_gate(engine: Engine) {
+        let mut circuit = create_circuit(engine);
+        let mut next_tick = circuit.tick(1).unwrap();
+        assert_eq!(2, next_tick); // and gate drives its first result on the next tick
+        assert_eq!(
+            LogicValue::X,
+            TestProbe::get_value(&circuit, DEVICE_OUTPUT).unwrap()
+        );
+
+        TestProbe::set_output_high(&circuit, DEVICE_INPUT1).unwrap();
+        next_tick = circuit.tick(2).unwrap();
         assert_eq!(u64::MAX, next_tick);
-        assert_eq!(0, TestProbe::get_value(&circuit, DEVICE_OUTPUT));
+        assert_eq!(
+            LogicValue::Zero,
+            TestProbe::get_value(&circuit, DEVICE_OUTPUT).unwrap()
+        );
 
-        TestProbe::set_output_high(&circuit, DEVICE_INPUT2);
-        next_tick = circuit.tick(3);
+        TestProbe::set_output_high(&circuit, DEVICE_INPUT2).unwrap();
+        next_tick = circuit.tick(3).unwrap();
         assert_eq!(4, next_tick); // and gate will set next tick + 1
-        assert_eq!(0, TestProbe::get_value(&circuit, DEVICE_OUTPUT));
+        assert_eq!(
+            LogicValue::Zero,
+            TestProbe::get_value(&circuit, DEVICE_OUTPUT).unwrap()
+        );
 
-        next_tick = circuit.tick(4);
+        next_tick = circuit.tick(4).unwrap();
         assert_eq!(u64::MAX, next_tick);
-        assert_eq!(u32::MAX, TestProbe::get_value(&circuit, DEVICE_OUTPUT));
+        assert_eq!(
+            LogicValue::One,
+            TestProbe::get_value(&circuit, DEVICE_OUTPUT).unwrap()
+        );
+    }
+
+    #[test]
+    fn unknown_inputs() {
+        let mut circuit = create_circuit(Engine::EventDriven);
+        TestProbe::set_output(&circuit, DEVICE_INPUT2, LogicValue::X).unwrap();
+        circuit.tick(1).unwrap();
+        circuit.tick(2).unwrap();
+        // a known 0 decides the result regardless of the other input
+        assert_eq!(
+            LogicValue::Zero,
+            TestProbe::get_value(&circuit, DEVICE_OUTPUT).unwrap()
+        );
+
+        TestProbe::set_output_high(&circuit, DEVICE_INPUT1).unwrap();
+        circuit.tick(3).unwrap();
+        circuit.tick(4).unwrap();
+        assert_eq!(
+            LogicValue::X,
+            TestProbe::get_value(&circuit, DEVICE_OUTPUT).unwrap()
+        );
+
+        // a floating input reads as unknown
+        TestProbe::set_output(&circuit, DEVICE_INPUT2, LogicValue::Z).unwrap();
+        circuit.tick(5).unwrap();
+        assert_eq!(u64::MAX, circuit.tick(6).unwrap());
+        assert_eq!(
+            LogicValue::X,
+            TestProbe::get_value(&circuit, DEVICE_OUTPUT).unwrap()
+        );
+
+        TestProbe::set_output_high(&circuit, DEVICE_INPUT2).unwrap();
+        circuit.tick(7).unwrap();
+        circuit.tick(8).unwrap();
+        assert_eq!(
+            LogicValue::One,
+            TestProbe::get_value(&circuit, DEVICE_OUTPUT).unwrap()
+        );
+    }
+
+    #[test]
+    fn three_inputs() {
+        use LogicValue::*;
+        check_gate(
+            AndGate::with_inputs("and3", 3),
+            &[
+                &[One, One, One, One],
+                &[One, Zero, One, Zero],
+                &[X, One, One, X],
+                &[Z, Zero, One, Zero],
+            ],
+        );
     }
 }
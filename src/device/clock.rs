@@ -0,0 +1,273 @@
+use crate::device::Device;
+use crate::device::DeviceResponse;
+use crate::LogicValue;
+use crate::PinDirection;
+use crate::PinInfo;
+
+/// Square wave generator that wakes itself at each edge, so `Circuit::run_until`
+/// can drive a synchronous design without test code per edge.
+///
+/// The output is low before the phase tick, rises at the phase tick and then
+/// every period, staying high for the duty cycle of each period. From the stop
+/// tick on, if there is one, it stays low.
+#[derive(Debug)]
+pub struct Clock {
+    name: String,
+    period: u64,
+    high_ticks: u64,
+    phase: u64,
+    stop_tick: Option<u64>,
+    driven: Option<LogicValue>,
+}
+
+impl Clock {
+    pub const PIN_OUTPUT: usize = 1;
+
+    /// A clock with a 50% duty cycle rising at tick 0, `period` is at least 2.
+    pub fn new(name: &str, period: u64) -> Clock {
+        assert!(period >= 2, "clock {} needs a period of at least 2", name);
+        return Clock {
+            name: name.to_string(),
+            period,
+            high_ticks: period / 2,
+            phase: 0,
+            stop_tick: None,
+            driven: None,
+        };
+    }
+
+    pub fn get_period(&self) -> u64 {
+        return self.period;
+    }
+
+    /// Gets the number of ticks the output is high in each period.
+    pub fn get_high_ticks(&self) -> u64 {
+        return self.high_ticks;
+    }
+
+    /// Sets the share of each period the output is high, rounded down to whole
+    /// ticks, which must leave at least one tick high and one tick low.
+    pub fn set_duty_cycle(&mut self, percent: u64) {
+        let high_ticks = self.period * percent / 100;
+        assert!(
+            high_ticks >= 1 && high_ticks < self.period,
+            "a {}% duty cycle of clock {} does not fit a period of {}",
+            percent,
+            self.name,
+            self.period
+        );
+        self.high_ticks = high_ticks;
+    }
+
+    pub fn get_phase(&self) -> u64 {
+        return self.phase;
+    }
+
+    /// Sets the tick of the first rising edge.
+    pub fn set_phase(&mut self, phase: u64) {
+        self.phase = phase;
+    }
+
+    pub fn get_stop_tick(&self) -> Option<u64> {
+        return self.stop_tick;
+    }
+
+    pub fn set_stop_tick(&mut self, stop_tick: Option<u64>) {
+        self.stop_tick = stop_tick;
+    }
+
+    /// Gets the output level at `tick`.
+    pub fn get_level(&self, tick: u64) -> LogicValue {
+        if tick < self.phase || self.stop_tick.is_some_and(|stop_tick| tick >= stop_tick) {
+            return LogicValue::Zero;
+        }
+        return LogicValue::from_bool((tick - self.phase) % self.period < self.high_ticks);
+    }
+
+    /// Gets the first tick after `tick` at which the output changes,
+    /// `u64::MAX` if it never does again.
+    fn get_next_edge(&self, tick: u64) -> u64 {
+        let edge = if tick < self.phase {
+            self.phase
+        } else {
+            let start = tick - (tick - self.phase) % self.period;
+            if tick - start < self.high_ticks {
+                start + self.high_ticks
+            } else {
+                start + self.period
+            }
+        };
+        if let Some(stop_tick) = self.stop_tick {
+            if edge >= stop_tick {
+                if tick < stop_tick && self.get_level(tick) == LogicValue::One {
+                    return stop_tick;
+                }
+                return u64::MAX;
+            }
+        }
+        return edge;
+    }
+}
+
+impl Device for Clock {
+    fn get_name(&self) -> &str {
+        return &self.name;
+    }
+
+    fn get_pin_count(&self) -> usize {
+        return 1;
+    }
+
+    fn get_pin_info(&self, pin: usize) -> PinInfo {
+        match pin {
+            Clock::PIN_OUTPUT => PinInfo::new("CLK", PinDirection::Output),
+            _ => panic!("clock {} has no pin {}", self.name, pin),
+        }
+    }
+
+    fn on_tick(&mut self, tick: u64) -> DeviceResponse {
+        let mut response = DeviceResponse::new();
+        let level = self.get_level(tick);
+        if self.driven != Some(level) {
+            response.set_output(Clock::PIN_OUTPUT, level);
+            self.driven = Some(level);
+        }
+        response.set_next_tick(self.get_next_edge(tick));
+        return response;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::device::Clock;
+    use crate::device::TFlipFlop;
+    use crate::device::TestProbe;
+    use crate::CircuitBuilder;
+    use crate::Engine;
+    use crate::LogicValue;
+    use crate::Net;
+    use crate::NetConnection;
+    use crate::PinDirection;
+
+    #[test]
+    fn levels_and_edges() {
+        let mut clock = Clock::new("clk", 10);
+        clock.set_duty_cycle(30);
+        clock.set_phase(5);
+        clock.set_stop_tick(Some(37));
+        assert_eq!(3, clock.get_high_ticks());
+        assert_eq!(LogicValue::Zero, clock.get_level(4));
+        assert_eq!(LogicValue::One, clock.get_level(5));
+        assert_eq!(LogicValue::One, clock.get_level(7));
+        assert_eq!(LogicValue::Zero, clock.get_level(8));
+        assert_eq!(LogicValue::One, clock.get_level(35));
+        assert_eq!(LogicValue::Zero, clock.get_level(37));
+        assert_eq!(5, clock.get_next_edge(0));
+        assert_eq!(8, clock.get_next_edge(5));
+        assert_eq!(15, clock.get_next_edge(8));
+        assert_eq!(37, clock.get_next_edge(35));
+        assert_eq!(u64::MAX, clock.get_next_edge(37));
+    }
+
+    #[test]
+    fn drives_a_divider() {
+        check_divider(Engine::Threaded);
+        check_divider(Engine::EventDriven);
+    }
+
+    fn check_divider(engine: Engine) {
+        let mut builder = CircuitBuilder::new();
+        builder.set_engine(engine);
+        let mut clock = Clock::new("clk", 4);
+        clock.set_stop_tick(Some(40));
+        builder.add_device(clock);
+        builder.add_device(TFlipFlop::new("ff"));
+        builder.add_device(TestProbe::new("t", LogicValue::One, PinDirection::Output));
+        builder.add_device(TestProbe::new("rst", LogicValue::One, PinDirection::Output));
+        builder.connect("t.pin", "ff.T");
+        builder.connect("rst.pin", "ff.RST");
+        builder.add_net(Net::named(
+            "clk",
+            vec![
+                NetConnection::named("clk.CLK"),
+                NetConnection::named("ff.CLK"),
+            ],
+        ));
+        builder.add_net(Net::named("q", vec![NetConnection::named("ff.Q")]));
+        let (mut circuit, _devices) = builder.build().unwrap();
+        circuit.start_waveform();
+        circuit.run_until(2).unwrap();
+        TestProbe::set_output_low(&circuit, "rst").unwrap();
+        let summary = circuit.run_until(100).unwrap();
+        assert!(summary.is_quiescent());
+
+        let waveform = circuit.get_waveform().unwrap();
+        let value_at = |name: &str, tick: u64| -> LogicValue {
+            return waveform.get_value_at(name, tick).unwrap().get_bit(0);
+        };
+        // rising edges at 4, 8, 12, ..., q follows one tick later
+        assert_eq!(LogicValue::One, value_at("clk", 4));
+        assert_eq!(LogicValue::Zero, value_at("clk", 6));
+        assert_eq!(LogicValue::Zero, value_at("q", 4));
+        assert_eq!(LogicValue::One, value_at("q", 5));
+        assert_eq!(LogicValue::One, value_at("q", 8));
+        assert_eq!(LogicValue::Zero, value_at("q", 9));
+        assert_eq!(LogicValue::One, value_at("q", 13));
+        // the clock stops low after its last edge at 36
+        assert_eq!(LogicValue::One, value_at("clk", 37));
+        assert_eq!(LogicValue::Zero, value_at("clk", 40));
+        assert_eq!(LogicValue::Zero, value_at("clk", 100));
+        assert_eq!(LogicValue::One, value_at("q", 100));
+    }
+
+    #[test]
+    fn two_clock_domains() {
+        let mut builder = CircuitBuilder::new();
+        let mut fast = Clock::new("fast", 2);
+        fast.set_stop_tick(Some(30));
+        builder.add_device(fast);
+        let mut slow = Clock::new("slow", 6);
+        slow.set_phase(1);
+        slow.set_stop_tick(Some(30));
+        builder.add_device(slow);
+        builder.add_device(TestProbe::new(
+            "fast_in",
+            LogicValue::Z,
+            PinDirection::Input,
+        ));
+        builder.add_device(TestProbe::new(
+            "slow_in",
+            LogicValue::Z,
+            PinDirection::Input,
+        ));
+        builder.add_net(Net::named(
+            "fast",
+            vec![
+                NetConnection::named("fast.CLK"),
+                NetConnection::named("fast_in.pin"),
+            ],
+        ));
+        builder.add_net(Net::named(
+            "slow",
+            vec![
+                NetConnection::named("slow.CLK"),
+                NetConnection::named("slow_in.pin"),
+            ],
+        ));
+        let (mut circuit, _devices) = builder.build().unwrap();
+        circuit.start_waveform();
+        circuit.run_until(24).unwrap();
+
+        let waveform = circuit.get_waveform().unwrap();
+        let edges = |name: &str| -> Vec<u64> {
+            return (2..=24)
+                .filter(|tick| {
+                    waveform.get_value_at(name, *tick) != waveform.get_value_at(name, tick - 1)
+                })
+                .collect();
+        };
+        // the fast clock changes on every tick, the slow one every third
+        assert_eq!((2..=24).collect::<Vec<u64>>(), edges("fast"));
+        assert_eq!(vec![4, 7, 10, 13, 16, 19, 22], edges("slow"));
+    }
+}
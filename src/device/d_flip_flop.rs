@@ -0,0 +1,182 @@
+use crate::device::Device;
+use crate::device::DeviceResponse;
+use crate::device::FlipFlop;
+use crate::LogicValue;
+use crate::PinInfo;
+use crate::PinValue;
+use crate::TimingCheck;
+
+/// Rising-edge D flip-flop with clock enable and asynchronous set and reset,
+/// `EN`, `SET` and `RST` may be left unconnected.
+#[derive(Debug)]
+pub struct DFlipFlop {
+    flip_flop: FlipFlop,
+}
+
+impl DFlipFlop {
+    pub const PIN_D: usize = 1;
+    pub const PIN_CLOCK: usize = 2;
+    pub const PIN_ENABLE: usize = 3;
+    pub const PIN_SET: usize = 4;
+    pub const PIN_RESET: usize = 5;
+    pub const PIN_Q: usize = 6;
+    pub const PIN_QN: usize = 7;
+
+    pub fn new(name: &str) -> DFlipFlop {
+        return DFlipFlop {
+            flip_flop: FlipFlop::new(name, &["D"], DFlipFlop::next_state),
+        };
+    }
+
+    /// Sets the state at power-on, X by default.
+    pub fn set_initial_state(&mut self, state: LogicValue) {
+        self.flip_flop.set_initial_state(state);
+    }
+
+    /// Declares a setup, hold, recovery, removal or pulse width check on the
+    /// pins of this flip-flop, e.g. `TimingCheck::setup(PIN_D, PIN_CLOCK, 2)`.
+    pub fn add_timing_check(&mut self, check: TimingCheck) {
+        self.flip_flop.add_timing_check(check);
+    }
+
+    /// Makes a failed timing check set the state to X, it is only reported
+    /// otherwise.
+    pub fn set_x_on_violation(&mut self, x_on_violation: bool) {
+        self.flip_flop.set_x_on_violation(x_on_violation);
+    }
+
+    fn next_state(data: &[LogicValue], _state: LogicValue) -> LogicValue {
+        return data[0];
+    }
+}
+
+impl Device for DFlipFlop {
+    fn get_name(&self) -> &str {
+        return self.flip_flop.get_name();
+    }
+
+    fn get_pin_count(&self) -> usize {
+        return self.flip_flop.get_pin_count();
+    }
+
+    fn get_pin_info(&self, pin: usize) -> PinInfo {
+        return self.flip_flop.get_pin_info(pin);
+    }
+
+    fn on_tick(&mut self, tick: u64) -> DeviceResponse {
+        return self.flip_flop.on_tick(tick);
+    }
+
+    fn on_inputs_changed(&mut self, tick: u64, pins: &[PinValue]) -> DeviceResponse {
+        return self.flip_flop.on_inputs_changed(tick, pins);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::device::flip_flop::tests::clock;
+    use crate::device::flip_flop::tests::create_circuit;
+    use crate::device::flip_flop::tests::drive;
+    use crate::device::flip_flop::tests::get_q;
+    use crate::device::DFlipFlop;
+    use crate::LogicValue;
+    use crate::SimEvent;
+    use crate::TimingCheck;
+    use crate::TimingCheckKind;
+
+    #[test]
+    fn samples_on_rising_edge() {
+        let mut circuit = create_circuit(DFlipFlop::new("ff"), &["D", "CLK"]);
+        let mut tick = drive(&mut circuit, 1, "d", LogicValue::One);
+        // nothing is known until the first edge
+        assert_eq!(LogicValue::X, get_q(&circuit));
+
+        tick = drive(&mut circuit, tick, "clk", LogicValue::One);
+        assert_eq!(LogicValue::One, get_q(&circuit));
+
+        tick = drive(&mut circuit, tick, "d", LogicValue::Zero);
+        tick = drive(&mut circuit, tick, "clk", LogicValue::Zero);
+        assert_eq!(LogicValue::One, get_q(&circuit));
+
+        tick = clock(&mut circuit, tick);
+        assert_eq!(LogicValue::Zero, get_q(&circuit));
+
+        // an edge from an unknown clock may have sampled the new value
+        tick = drive(&mut circuit, tick, "d", LogicValue::One);
+        tick = drive(&mut circuit, tick, "clk", LogicValue::X);
+        drive(&mut circuit, tick, "clk", LogicValue::One);
+        assert_eq!(LogicValue::X, get_q(&circuit));
+    }
+
+    #[test]
+    fn enable_set_and_reset() {
+        let mut circuit = create_circuit(DFlipFlop::new("ff"), &["D", "CLK", "EN", "SET", "RST"]);
+        let mut tick = drive(&mut circuit, 1, "rst", LogicValue::One);
+        assert_eq!(LogicValue::Zero, get_q(&circuit));
+
+        // reset wins over the clock while it is held
+        tick = drive(&mut circuit, tick, "d", LogicValue::One);
+        tick = drive(&mut circuit, tick, "en", LogicValue::One);
+        tick = clock(&mut circuit, tick);
+        assert_eq!(LogicValue::Zero, get_q(&circuit));
+
+        tick = drive(&mut circuit, tick, "rst", LogicValue::Zero);
+        tick = drive(&mut circuit, tick, "en", LogicValue::Zero);
+        tick = clock(&mut circuit, tick);
+        assert_eq!(LogicValue::Zero, get_q(&circuit));
+
+        tick = drive(&mut circuit, tick, "en", LogicValue::One);
+        tick = clock(&mut circuit, tick);
+        assert_eq!(LogicValue::One, get_q(&circuit));
+
+        tick = drive(&mut circuit, tick, "d", LogicValue::Zero);
+        tick = drive(&mut circuit, tick, "set", LogicValue::One);
+        assert_eq!(LogicValue::One, get_q(&circuit));
+
+        tick = drive(&mut circuit, tick, "rst", LogicValue::One);
+        assert_eq!(LogicValue::X, get_q(&circuit));
+
+        tick = drive(&mut circuit, tick, "set", LogicValue::Zero);
+        assert_eq!(LogicValue::Zero, get_q(&circuit));
+
+        tick = drive(&mut circuit, tick, "rst", LogicValue::X);
+        assert_eq!(LogicValue::X, get_q(&circuit));
+        drive(&mut circuit, tick, "rst", LogicValue::Zero);
+        assert_eq!(LogicValue::X, get_q(&circuit));
+    }
+
+    #[test]
+    fn setup_violation() {
+        let mut flip_flop = DFlipFlop::new("ff");
+        flip_flop.add_timing_check(TimingCheck::setup(
+            DFlipFlop::PIN_D,
+            DFlipFlop::PIN_CLOCK,
+            3,
+        ));
+        flip_flop.set_x_on_violation(true);
+        let mut circuit = create_circuit(flip_flop, &["D", "CLK"]);
+        let mut tick = drive(&mut circuit, 1, "d", LogicValue::Zero);
+        tick = clock(&mut circuit, tick);
+        assert_eq!(LogicValue::Zero, get_q(&circuit));
+        assert!(circuit.take_events().is_empty());
+
+        tick = drive(&mut circuit, tick, "d", LogicValue::One);
+        tick = drive(&mut circuit, tick, "clk", LogicValue::One);
+        assert_eq!(LogicValue::X, get_q(&circuit));
+        assert_eq!(
+            vec![SimEvent::TimingViolation {
+                tick: 9,
+                device: "ff".to_string(),
+                pin: "D".to_string(),
+                check: TimingCheckKind::Setup,
+                amount: 1,
+            }],
+            circuit.take_events()
+        );
+
+        tick = drive(&mut circuit, tick, "clk", LogicValue::Zero);
+        clock(&mut circuit, tick);
+        assert_eq!(LogicValue::One, get_q(&circuit));
+        assert!(circuit.take_events().is_empty());
+    }
+}
@@ -0,0 +1,89 @@
+use crate::device::Device;
+use crate::device::DeviceResponse;
+use crate::device::Latch;
+use crate::LogicValue;
+use crate::PinInfo;
+use crate::PinValue;
+
+/// Transparent latch, Q follows D while `EN` is high and holds while it is low.
+#[derive(Debug)]
+pub struct DLatch {
+    latch: Latch,
+}
+
+impl DLatch {
+    pub const PIN_D: usize = 1;
+    pub const PIN_ENABLE: usize = 2;
+    pub const PIN_Q: usize = 3;
+    pub const PIN_QN: usize = 4;
+
+    pub fn new(name: &str) -> DLatch {
+        return DLatch {
+            latch: Latch::new(name, &["D", "EN"], DLatch::next_state),
+        };
+    }
+
+    /// Sets the state at power-on, X by default.
+    pub fn set_initial_state(&mut self, state: LogicValue) {
+        self.latch.set_initial_state(state);
+    }
+
+    fn next_state(inputs: &[LogicValue], state: LogicValue) -> LogicValue {
+        match inputs[1].to_bool() {
+            Some(true) => inputs[0],
+            Some(false) => state,
+            None if inputs[0] == state => state,
+            None => LogicValue::X,
+        }
+    }
+}
+
+impl Device for DLatch {
+    fn get_name(&self) -> &str {
+        return self.latch.get_name();
+    }
+
+    fn get_pin_count(&self) -> usize {
+        return self.latch.get_pin_count();
+    }
+
+    fn get_pin_info(&self, pin: usize) -> PinInfo {
+        return self.latch.get_pin_info(pin);
+    }
+
+    fn on_tick(&mut self, tick: u64) -> DeviceResponse {
+        return self.latch.on_tick(tick);
+    }
+
+    fn on_inputs_changed(&mut self, tick: u64, pins: &[PinValue]) -> DeviceResponse {
+        return self.latch.on_inputs_changed(tick, pins);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::device::flip_flop::tests::create_circuit;
+    use crate::device::flip_flop::tests::drive;
+    use crate::device::flip_flop::tests::get_q;
+    use crate::device::DLatch;
+    use crate::LogicValue;
+
+    #[test]
+    fn transparent_while_enabled() {
+        let mut circuit = create_circuit(DLatch::new("latch"), &["D", "EN"]);
+        let mut tick = drive(&mut circuit, 1, "d", LogicValue::One);
+        assert_eq!(LogicValue::X, get_q(&circuit));
+
+        tick = drive(&mut circuit, tick, "en", LogicValue::One);
+        assert_eq!(LogicValue::One, get_q(&circuit));
+        tick = drive(&mut circuit, tick, "d", LogicValue::Zero);
+        assert_eq!(LogicValue::Zero, get_q(&circuit));
+
+        tick = drive(&mut circuit, tick, "en", LogicValue::Zero);
+        tick = drive(&mut circuit, tick, "d", LogicValue::One);
+        assert_eq!(LogicValue::Zero, get_q(&circuit));
+
+        drive(&mut circuit, tick, "en", LogicValue::X);
+        assert_eq!(LogicValue::X, get_q(&circuit));
+    }
+}
@@ -1,19 +1,68 @@
-use crate::CircuitToDeviceMessage;
-use crate::DeviceToCircuitMessage;
+use crate::device::DeviceResponse;
+use crate::device::SubCircuit;
+use crate::DeviceData;
+use crate::PinInfo;
+use crate::PinValue;
 use core::fmt::Debug;
-use std::sync::mpsc;
 
+/// A simulated part. The circuit calls the hooks below and routes the returned
+/// outputs, so devices never deal with the message protocol directly.
 pub trait Device: Send {
-    fn run(
-        &mut self,
-        tx: mpsc::Sender<DeviceToCircuitMessage>,
-        rx: mpsc::Receiver<CircuitToDeviceMessage>,
-    );
-
     fn get_name(&self) -> &str;
 
-    /// Gets the number of pins
+    /// Gets the number of pins, pins are numbered from 1 to the count.
     fn get_pin_count(&self) -> usize;
+
+    /// Describes `pin`, which is between 1 and `get_pin_count`.
+    fn get_pin_info(&self, pin: usize) -> PinInfo;
+
+    /// Finds the number of the pin called `name`.
+    fn get_pin_by_name(&self, name: &str) -> Option<usize> {
+        return (1..=self.get_pin_count()).find(|pin| self.get_pin_info(*pin).get_name() == name);
+    }
+
+    /// Gets the `(input, output)` pin pairs where a change on the input can
+    /// change the output on the same tick, i.e. outputs returned from
+    /// `on_inputs_changed`. Used to find combinational loops.
+    fn get_zero_delay_paths(&self) -> Vec<(usize, usize)> {
+        return Vec::new();
+    }
+
+    /// Only overridden by `SubCircuit`, whose contents replace it when the
+    /// circuit is built.
+    fn as_sub_circuit(&mut self) -> Option<&mut SubCircuit> {
+        return None;
+    }
+
+    /// Called when the device is woken at `tick`. With `Engine::EventDriven`
+    /// that is only when it asked to be through `DeviceResponse::set_next_tick`
+    /// or received data. With `Engine::Threaded` it is called on every tick the
+    /// circuit runs, so a device must check whether its own wake-up is due
+    /// rather than assume it is.
+    fn on_tick(&mut self, _tick: u64) -> DeviceResponse {
+        return DeviceResponse::new();
+    }
+
+    /// Called once per tick with every input pin that changed on that tick.
+    /// Outputs returned here are routed on the same tick.
+    fn on_inputs_changed(&mut self, _tick: u64, pins: &[PinValue]) -> DeviceResponse {
+        panic!(
+            "device {} does not accept input on pin {}",
+            self.get_name(),
+            pins[0].get_pin()
+        );
+    }
+
+    /// Called with data sent by `Circuit::send_device_data`.
+    fn on_data(&mut self, _data: Box<dyn DeviceData>) {
+        panic!("device {} does not accept data", self.get_name());
+    }
+
+    /// Called with data sent by `Circuit::recv_device_data`, the returned data
+    /// is handed back to the caller.
+    fn on_data_request(&mut self, _data: Box<dyn DeviceData>) -> Box<dyn DeviceData> {
+        panic!("device {} does not accept data requests", self.get_name());
+    }
 }
 
 impl Debug for dyn Device {
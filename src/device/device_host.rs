@@ -0,0 +1,115 @@
+use crate::device::Device;
+use crate::device::DeviceResponse;
+use crate::BusValue;
+use crate::CircuitToDeviceMessage;
+use crate::DeviceToCircuitMessage;
+use crate::PinDirection;
+use crate::PinValue;
+use std::sync::mpsc;
+
+/// Translates the circuit's message protocol into `Device` callbacks. Both
+/// engines talk to devices through a host, the threaded engine runs it on the
+/// device's thread while the event-driven engine calls it directly.
+#[derive(Debug)]
+pub(crate) struct DeviceHost {
+    device: Box<dyn Device>,
+    pending_pins: Vec<PinValue>,
+}
+
+impl DeviceHost {
+    pub fn new(device: Box<dyn Device>) -> DeviceHost {
+        return DeviceHost {
+            device,
+            pending_pins: Vec::new(),
+        };
+    }
+
+    /// Handles a single message from the circuit, pushing any replies onto `replies`.
+    /// Returns `false` once the device should stop running.
+    pub fn handle_message(
+        &mut self,
+        message: CircuitToDeviceMessage,
+        replies: &mut Vec<DeviceToCircuitMessage>,
+    ) -> bool {
+        match message {
+            CircuitToDeviceMessage::NextTick { tick } => {
+                let response = self.device.on_tick(tick);
+                self.push_response(response, replies);
+            }
+            CircuitToDeviceMessage::SetPin {
+                tick,
+                pin,
+                value,
+                last,
+            } => {
+                self.pending_pins.push(PinValue::new(pin, value));
+                if last {
+                    let response = self.device.on_inputs_changed(tick, &self.pending_pins);
+                    self.pending_pins.clear();
+                    self.push_response(response, replies);
+                }
+            }
+            CircuitToDeviceMessage::Data { data } => {
+                self.device.on_data(data);
+            }
+            CircuitToDeviceMessage::DataRequest { data } => {
+                let data = self.device.on_data_request(data);
+                replies.push(DeviceToCircuitMessage::Data { data });
+            }
+            CircuitToDeviceMessage::Terminate => {
+                return false;
+            }
+        }
+        return true;
+    }
+
+    /// Runs the device until it is terminated or the circuit hangs up.
+    pub fn run(
+        &mut self,
+        tx: mpsc::Sender<DeviceToCircuitMessage>,
+        rx: mpsc::Receiver<CircuitToDeviceMessage>,
+    ) {
+        let mut replies: Vec<DeviceToCircuitMessage> = Vec::new();
+        let mut run = true;
+        while run {
+            match rx.recv() {
+                Result::Ok(message) => {
+                    run = self.handle_message(message, &mut replies);
+                    for reply in replies.drain(..) {
+                        if tx.send(reply).is_err() {
+                            run = false;
+                        }
+                    }
+                }
+                Result::Err(_err) => {
+                    run = false;
+                }
+            }
+        }
+    }
+
+    fn push_response(&self, response: DeviceResponse, replies: &mut Vec<DeviceToCircuitMessage>) {
+        for output in response.get_outputs() {
+            replies.push(DeviceToCircuitMessage::SetPin {
+                pin: output.get_pin(),
+                value: output.get_value(),
+                direction: PinDirection::Output,
+            });
+        }
+        for pin in response.get_released_pins() {
+            replies.push(DeviceToCircuitMessage::SetPin {
+                pin: *pin,
+                value: BusValue::z(self.device.get_pin_info(*pin).get_width()),
+                direction: PinDirection::Input,
+            });
+        }
+        for violation in response.get_timing_violations() {
+            replies.push(DeviceToCircuitMessage::TimingViolation {
+                violation: *violation,
+            });
+        }
+        replies.push(DeviceToCircuitMessage::NextTick {
+            tick: response.get_next_tick(),
+        });
+    }
+}
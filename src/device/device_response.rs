@@ -0,0 +1,74 @@
+use crate::BusValue;
+use crate::PinValue;
+use crate::TimingViolation;
+
+/// What a device wants the circuit to do after one of its callbacks: the
+/// outputs to drive and the tick it next wants to be woken at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceResponse {
+    outputs: Vec<PinValue>,
+    released: Vec<usize>,
+    violations: Vec<TimingViolation>,
+    next_tick: u64,
+}
+
+impl DeviceResponse {
+    /// A response that drives nothing and does not ask to be woken.
+    pub fn new() -> DeviceResponse {
+        return DeviceResponse {
+            outputs: Vec::new(),
+            released: Vec::new(),
+            violations: Vec::new(),
+            next_tick: u64::MAX,
+        };
+    }
+
+    /// A response that only asks to be woken at `tick`.
+    pub fn wake_at(tick: u64) -> DeviceResponse {
+        let mut response = DeviceResponse::new();
+        response.set_next_tick(tick);
+        return response;
+    }
+
+    /// Drives `value` on `pin`, a `LogicValue` can be passed for single-bit pins.
+    pub fn set_output<V: Into<BusValue>>(&mut self, pin: usize, value: V) {
+        self.outputs.push(PinValue::new(pin, value.into()));
+    }
+
+    /// Stops driving `pin` so it receives the value of its net instead.
+    pub fn release_pin(&mut self, pin: usize) {
+        self.released.push(pin);
+    }
+
+    /// Reports a failed timing check, see `TimingChecker`.
+    pub fn add_timing_violation(&mut self, violation: TimingViolation) {
+        self.violations.push(violation);
+    }
+
+    /// Asks to be woken at `tick`, `u64::MAX` means never.
+    pub fn set_next_tick(&mut self, tick: u64) {
+        self.next_tick = tick;
+    }
+
+    pub fn get_outputs(&self) -> &[PinValue] {
+        return &self.outputs;
+    }
+
+    pub fn get_released_pins(&self) -> &[usize] {
+        return &self.released;
+    }
+
+    pub fn get_timing_violations(&self) -> &[TimingViolation] {
+        return &self.violations;
+    }
+
+    pub fn get_next_tick(&self) -> u64 {
+        return self.next_tick;
+    }
+}
+
+impl Default for DeviceResponse {
+    fn default() -> DeviceResponse {
+        return DeviceResponse::new();
+    }
+}
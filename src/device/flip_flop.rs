@@ -0,0 +1,219 @@
+use crate::device::DeviceResponse;
+use crate::LogicValue;
+use crate::PinDirection;
+use crate::PinInfo;
+use crate::PinValue;
+use crate::TimingCheck;
+use crate::TimingChecker;
+
+/// State shared by the edge-triggered flip-flops: the data inputs on pins 1 to
+/// the data pin count, followed by `CLK`, `EN`, `SET`, `RST`, `Q` and `QN`.
+///
+/// The state is X at power-on unless set otherwise. On a rising clock edge
+/// with `EN` high the state becomes `next_state(data, state)`; an edge from or
+/// to an unknown clock, or an unknown enable, gives X unless the state would
+/// not change. `SET` and `RST` are asynchronous and active high, both active
+/// or either unknown gives X. `EN`, `SET` and `RST` may be left unconnected,
+/// they then read 1, 0 and 0. Outputs follow one tick after the inputs change.
+///
+/// Failed timing checks are reported to the circuit and, if enabled, make the
+/// state X like a Verilog notifier would.
+#[derive(Debug)]
+pub(crate) struct FlipFlop {
+    name: String,
+    data_pins: &'static [&'static str],
+    // indexed by pin - 1, only the input pins
+    inputs: Vec<LogicValue>,
+    next_state: fn(&[LogicValue], LogicValue) -> LogicValue,
+    state: LogicValue,
+    driven: LogicValue,
+    checker: TimingChecker,
+    x_on_violation: bool,
+}
+
+impl FlipFlop {
+    pub(crate) fn new(
+        name: &str,
+        data_pins: &'static [&'static str],
+        next_state: fn(&[LogicValue], LogicValue) -> LogicValue,
+    ) -> FlipFlop {
+        return FlipFlop {
+            name: name.to_string(),
+            data_pins,
+            inputs: vec![LogicValue::X; data_pins.len() + 4],
+            next_state,
+            state: LogicValue::X,
+            driven: LogicValue::Z,
+            checker: TimingChecker::new(),
+            x_on_violation: false,
+        };
+    }
+
+    pub(crate) fn get_name(&self) -> &str {
+        return &self.name;
+    }
+
+    pub(crate) fn get_pin_count(&self) -> usize {
+        return self.data_pins.len() + 6;
+    }
+
+    pub(crate) fn set_initial_state(&mut self, state: LogicValue) {
+        self.state = state;
+    }
+
+    pub(crate) fn add_timing_check(&mut self, check: TimingCheck) {
+        self.checker.add_check(check);
+    }
+
+    pub(crate) fn set_x_on_violation(&mut self, x_on_violation: bool) {
+        self.x_on_violation = x_on_violation;
+    }
+
+    fn get_clock_pin(&self) -> usize {
+        return self.data_pins.len() + 1;
+    }
+
+    pub(crate) fn get_pin_info(&self, pin: usize) -> PinInfo {
+        if pin == 0 || pin > self.get_pin_count() {
+            panic!("flip-flop {} has no pin {}", self.name, pin);
+        }
+        let data_count = self.data_pins.len();
+        if pin <= data_count {
+            return PinInfo::new(self.data_pins[pin - 1], PinDirection::Input);
+        }
+        let (name, direction, default_value) = match pin - data_count {
+            1 => ("CLK", PinDirection::Input, None),
+            2 => ("EN", PinDirection::Input, Some(LogicValue::One)),
+            3 => ("SET", PinDirection::Input, Some(LogicValue::Zero)),
+            4 => ("RST", PinDirection::Input, Some(LogicValue::Zero)),
+            5 => ("Q", PinDirection::Output, None),
+            _ => ("QN", PinDirection::Output, None),
+        };
+        let mut pin_info = PinInfo::new(name, direction);
+        if let Some(default_value) = default_value {
+            pin_info.set_default_value(default_value);
+        }
+        return pin_info;
+    }
+
+    pub(crate) fn on_tick(&mut self, _tick: u64) -> DeviceResponse {
+        let mut response = DeviceResponse::new();
+        if self.state != self.driven {
+            response.set_output(self.get_clock_pin() + 4, self.state);
+            response.set_output(self.get_clock_pin() + 5, !self.state);
+            self.driven = self.state;
+        }
+        return response;
+    }
+
+    pub(crate) fn on_inputs_changed(&mut self, tick: u64, pins: &[PinValue]) -> DeviceResponse {
+        let clock_index = self.data_pins.len();
+        let last_clock = self.inputs[clock_index];
+        for pin in pins {
+            if pin.get_pin() == 0 || pin.get_pin() > self.inputs.len() {
+                panic!(
+                    "cannot set pin {} on flip-flop {}",
+                    pin.get_pin(),
+                    self.name
+                );
+            }
+            self.inputs[pin.get_pin() - 1] = pin.get_logic_value().as_input();
+        }
+        let violations = self.checker.on_inputs_changed(tick, pins);
+        let clock = self.inputs[clock_index];
+        let enable = self.inputs[clock_index + 1];
+        if clock != last_clock
+            && clock != LogicValue::Zero
+            && last_clock != LogicValue::One
+            && enable != LogicValue::Zero
+        {
+            let next_state = (self.next_state)(&self.inputs[..clock_index], self.state);
+            let rising = last_clock == LogicValue::Zero && clock == LogicValue::One;
+            if rising && enable == LogicValue::One {
+                self.state = next_state;
+            } else if next_state != self.state {
+                self.state = LogicValue::X;
+            }
+        }
+        if self.x_on_violation && !violations.is_empty() {
+            self.state = LogicValue::X;
+        }
+        let set = self.inputs[clock_index + 2];
+        let reset = self.inputs[clock_index + 3];
+        match (set.to_bool(), reset.to_bool()) {
+            (Some(false), Some(false)) => {}
+            (Some(true), Some(false)) => self.state = LogicValue::One,
+            (Some(false), Some(true)) => self.state = LogicValue::Zero,
+            _ => self.state = LogicValue::X,
+        }
+        let mut response = DeviceResponse::new();
+        for violation in violations {
+            response.add_timing_violation(violation);
+        }
+        if self.state != self.driven {
+            response.set_next_tick(tick + 1);
+        }
+        return response;
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use crate::device::Device;
+    use crate::device::TestProbe;
+    use crate::Circuit;
+    use crate::CircuitBuilder;
+    use crate::LogicValue;
+    use crate::PinDirection;
+
+    /// Connects a probe named like the pin, in lower case, to each of `inputs`
+    /// and input probes `q` and `qn` to the outputs.
+    pub(crate) fn create_circuit<T: Device + 'static>(device: T, inputs: &[&str]) -> Circuit {
+        let device_name = device.get_name().to_string();
+        let mut builder = CircuitBuilder::new();
+        builder.add_device(device);
+        for input in inputs {
+            let probe = input.to_lowercase();
+            builder.add_device(TestProbe::new(
+                &probe,
+                LogicValue::Zero,
+                PinDirection::Output,
+            ));
+            builder.connect(
+                &format!("{}.pin", probe),
+                &format!("{}.{}", device_name, input),
+            );
+        }
+        for output in ["Q", "QN"] {
+            let probe = output.to_lowercase();
+            builder.add_device(TestProbe::new(&probe, LogicValue::Z, PinDirection::Input));
+            builder.connect(
+                &format!("{}.{}", device_name, output),
+                &format!("{}.pin", probe),
+            );
+        }
+        let (circuit, _devices) = builder.build().unwrap();
+        return circuit;
+    }
+
+    /// Sets probe `name` to `value` and runs two ticks from `tick` so outputs
+    /// settle, returns the tick after them.
+    pub(crate) fn drive(circuit: &mut Circuit, tick: u64, name: &str, value: LogicValue) -> u64 {
+        TestProbe::set_output(circuit, name, value).unwrap();
+        circuit.tick(tick).unwrap();
+        circuit.tick(tick + 1).unwrap();
+        return tick + 2;
+    }
+
+    /// Raises then lowers probe `clk`, returns the tick after the pulse.
+    pub(crate) fn clock(circuit: &mut Circuit, tick: u64) -> u64 {
+        let tick = drive(circuit, tick, "clk", LogicValue::One);
+        return drive(circuit, tick, "clk", LogicValue::Zero);
+    }
+
+    pub(crate) fn get_q(circuit: &Circuit) -> LogicValue {
+        let q = TestProbe::get_value(circuit, "q").unwrap();
+        assert_eq!(!q, TestProbe::get_value(circuit, "qn").unwrap());
+        return q;
+    }
+}
@@ -0,0 +1,120 @@
+use crate::device::Device;
+use crate::device::DeviceResponse;
+use crate::device::FlipFlop;
+use crate::LogicValue;
+use crate::PinInfo;
+use crate::PinValue;
+use crate::TimingCheck;
+
+/// Rising-edge JK flip-flop with clock enable and asynchronous set and reset:
+/// J sets, K resets and both toggle. `EN`, `SET` and `RST` may be left
+/// unconnected.
+#[derive(Debug)]
+pub struct JkFlipFlop {
+    flip_flop: FlipFlop,
+}
+
+impl JkFlipFlop {
+    pub const PIN_J: usize = 1;
+    pub const PIN_K: usize = 2;
+    pub const PIN_CLOCK: usize = 3;
+    pub const PIN_ENABLE: usize = 4;
+    pub const PIN_SET: usize = 5;
+    pub const PIN_RESET: usize = 6;
+    pub const PIN_Q: usize = 7;
+    pub const PIN_QN: usize = 8;
+
+    pub fn new(name: &str) -> JkFlipFlop {
+        return JkFlipFlop {
+            flip_flop: FlipFlop::new(name, &["J", "K"], JkFlipFlop::next_state),
+        };
+    }
+
+    /// Sets the state at power-on, X by default.
+    pub fn set_initial_state(&mut self, state: LogicValue) {
+        self.flip_flop.set_initial_state(state);
+    }
+
+    /// Declares a setup, hold, recovery, removal or pulse width check on the
+    /// pins of this flip-flop, e.g. `TimingCheck::setup(PIN_J, PIN_CLOCK, 2)`.
+    pub fn add_timing_check(&mut self, check: TimingCheck) {
+        self.flip_flop.add_timing_check(check);
+    }
+
+    /// Makes a failed timing check set the state to X, it is only reported
+    /// otherwise.
+    pub fn set_x_on_violation(&mut self, x_on_violation: bool) {
+        self.flip_flop.set_x_on_violation(x_on_violation);
+    }
+
+    fn next_state(data: &[LogicValue], state: LogicValue) -> LogicValue {
+        match (data[0].to_bool(), data[1].to_bool()) {
+            (Some(false), Some(false)) => state,
+            (Some(false), Some(true)) => LogicValue::Zero,
+            (Some(true), Some(false)) => LogicValue::One,
+            (Some(true), Some(true)) => !state,
+            _ => LogicValue::X,
+        }
+    }
+}
+
+impl Device for JkFlipFlop {
+    fn get_name(&self) -> &str {
+        return self.flip_flop.get_name();
+    }
+
+    fn get_pin_count(&self) -> usize {
+        return self.flip_flop.get_pin_count();
+    }
+
+    fn get_pin_info(&self, pin: usize) -> PinInfo {
+        return self.flip_flop.get_pin_info(pin);
+    }
+
+    fn on_tick(&mut self, tick: u64) -> DeviceResponse {
+        return self.flip_flop.on_tick(tick);
+    }
+
+    fn on_inputs_changed(&mut self, tick: u64, pins: &[PinValue]) -> DeviceResponse {
+        return self.flip_flop.on_inputs_changed(tick, pins);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::device::flip_flop::tests::clock;
+    use crate::device::flip_flop::tests::create_circuit;
+    use crate::device::flip_flop::tests::drive;
+    use crate::device::flip_flop::tests::get_q;
+    use crate::device::JkFlipFlop;
+    use crate::LogicValue;
+
+    #[test]
+    fn set_reset_hold_and_toggle() {
+        let mut circuit = create_circuit(JkFlipFlop::new("ff"), &["J", "K", "CLK"]);
+        let mut tick = drive(&mut circuit, 1, "j", LogicValue::One);
+        // toggling an unknown state stays unknown
+        tick = drive(&mut circuit, tick, "k", LogicValue::One);
+        tick = clock(&mut circuit, tick);
+        assert_eq!(LogicValue::X, get_q(&circuit));
+
+        tick = drive(&mut circuit, tick, "j", LogicValue::Zero);
+        tick = clock(&mut circuit, tick);
+        assert_eq!(LogicValue::Zero, get_q(&circuit));
+
+        tick = drive(&mut circuit, tick, "k", LogicValue::Zero);
+        tick = clock(&mut circuit, tick);
+        assert_eq!(LogicValue::Zero, get_q(&circuit));
+
+        tick = drive(&mut circuit, tick, "j", LogicValue::One);
+        tick = drive(&mut circuit, tick, "k", LogicValue::One);
+        tick = clock(&mut circuit, tick);
+        assert_eq!(LogicValue::One, get_q(&circuit));
+        tick = clock(&mut circuit, tick);
+        assert_eq!(LogicValue::Zero, get_q(&circuit));
+
+        tick = drive(&mut circuit, tick, "k", LogicValue::Zero);
+        clock(&mut circuit, tick);
+        assert_eq!(LogicValue::One, get_q(&circuit));
+    }
+}
@@ -0,0 +1,88 @@
+use crate::device::DeviceResponse;
+use crate::LogicValue;
+use crate::PinDirection;
+use crate::PinInfo;
+use crate::PinValue;
+
+/// State shared by the level-sensitive latches: the inputs on pins 1 to the
+/// input count, followed by `Q` and `QN`.
+///
+/// The state is X at power-on unless set otherwise and becomes
+/// `next_state(inputs, state)` whenever an input changes. Outputs follow one
+/// tick later.
+#[derive(Debug)]
+pub(crate) struct Latch {
+    name: String,
+    input_pins: &'static [&'static str],
+    inputs: Vec<LogicValue>,
+    next_state: fn(&[LogicValue], LogicValue) -> LogicValue,
+    state: LogicValue,
+    driven: LogicValue,
+}
+
+impl Latch {
+    pub(crate) fn new(
+        name: &str,
+        input_pins: &'static [&'static str],
+        next_state: fn(&[LogicValue], LogicValue) -> LogicValue,
+    ) -> Latch {
+        return Latch {
+            name: name.to_string(),
+            input_pins,
+            inputs: vec![LogicValue::X; input_pins.len()],
+            next_state,
+            state: LogicValue::X,
+            driven: LogicValue::Z,
+        };
+    }
+
+    pub(crate) fn set_initial_state(&mut self, state: LogicValue) {
+        self.state = state;
+    }
+
+    pub(crate) fn get_name(&self) -> &str {
+        return &self.name;
+    }
+
+    pub(crate) fn get_pin_count(&self) -> usize {
+        return self.input_pins.len() + 2;
+    }
+
+    pub(crate) fn get_pin_info(&self, pin: usize) -> PinInfo {
+        let input_count = self.input_pins.len();
+        if pin >= 1 && pin <= input_count {
+            return PinInfo::new(self.input_pins[pin - 1], PinDirection::Input);
+        }
+        if pin == input_count + 1 {
+            return PinInfo::new("Q", PinDirection::Output);
+        }
+        if pin == input_count + 2 {
+            return PinInfo::new("QN", PinDirection::Output);
+        }
+        panic!("latch {} has no pin {}", self.name, pin);
+    }
+
+    pub(crate) fn on_tick(&mut self, _tick: u64) -> DeviceResponse {
+        let mut response = DeviceResponse::new();
+        if self.state != self.driven {
+            response.set_output(self.input_pins.len() + 1, self.state);
+            response.set_output(self.input_pins.len() + 2, !self.state);
+            self.driven = self.state;
+        }
+        return response;
+    }
+
+    pub(crate) fn on_inputs_changed(&mut self, tick: u64, pins: &[PinValue]) -> DeviceResponse {
+        for pin in pins {
+            if pin.get_pin() == 0 || pin.get_pin() > self.inputs.len() {
+                panic!("cannot set pin {} on latch {}", pin.get_pin(), self.name);
+            }
+            self.inputs[pin.get_pin() - 1] = pin.get_logic_value().as_input();
+        }
+        self.state = (self.next_state)(&self.inputs, self.state);
+        if self.state != self.driven {
+            return DeviceResponse::wake_at(tick + 1);
+        }
+        return DeviceResponse::new();
+    }
+}
@@ -0,0 +1,152 @@
+use crate::device::DeviceResponse;
+use crate::LogicValue;
+use crate::PinDirection;
+use crate::PinInfo;
+use crate::PinValue;
+
+/// State shared by the single-bit gates: inputs on pins 1 to the input count,
+/// named `A`, `B`, `C`, ..., and the output `Y` on the pin after them.
+///
+/// Floating inputs read as X before `function` sees them, so every gate treats
+/// Z like X. The result is driven one tick after the inputs change.
+#[derive(Debug)]
+pub(crate) struct LogicGate {
+    name: String,
+    inputs: Vec<LogicValue>,
+    function: fn(&[LogicValue]) -> LogicValue,
+    last_result: LogicValue,
+    next_result: LogicValue,
+}
+
+impl LogicGate {
+    /// Most inputs a gate can have, the letters before `Y`.
+    pub(crate) const MAX_INPUTS: usize = 24;
+
+    pub(crate) fn new(
+        name: &str,
+        input_count: usize,
+        function: fn(&[LogicValue]) -> LogicValue,
+    ) -> LogicGate {
+        assert!(
+            (1..=LogicGate::MAX_INPUTS).contains(&input_count),
+            "gate {} cannot have {} inputs",
+            name,
+            input_count
+        );
+        return LogicGate {
+            name: name.to_string(),
+            inputs: vec![LogicValue::Z; input_count],
+            function,
+            last_result: LogicValue::Z,
+            next_result: LogicValue::X,
+        };
+    }
+
+    pub(crate) fn get_name(&self) -> &str {
+        return &self.name;
+    }
+
+    pub(crate) fn get_pin_count(&self) -> usize {
+        return self.inputs.len() + 1;
+    }
+
+    pub(crate) fn get_output_pin(&self) -> usize {
+        return self.inputs.len() + 1;
+    }
+
+    pub(crate) fn get_pin_info(&self, pin: usize) -> PinInfo {
+        if pin == self.get_output_pin() {
+            return PinInfo::new("Y", PinDirection::Output);
+        }
+        if pin == 0 || pin > self.inputs.len() {
+            panic!("gate {} has no pin {}", self.name, pin);
+        }
+        let name = ((b'A' + (pin - 1) as u8) as char).to_string();
+        return PinInfo::new(&name, PinDirection::Input);
+    }
+
+    pub(crate) fn on_tick(&mut self, _tick: u64) -> DeviceResponse {
+        let mut response = DeviceResponse::new();
+        if self.next_result != self.last_result {
+            response.set_output(self.get_output_pin(), self.next_result);
+            self.last_result = self.next_result;
+        }
+        return response;
+    }
+
+    pub(crate) fn on_inputs_changed(&mut self, tick: u64, pins: &[PinValue]) -> DeviceResponse {
+        for pin in pins {
+            if pin.get_pin() == 0 || pin.get_pin() > self.inputs.len() {
+                panic!("cannot set pin {} on gate {}", pin.get_pin(), self.name);
+            }
+            self.inputs[pin.get_pin() - 1] = pin.get_logic_value().as_input();
+        }
+        self.next_result = (self.function)(&self.inputs);
+        if self.next_result != self.last_result {
+            return DeviceResponse::wake_at(tick + 1);
+        }
+        return DeviceResponse::new();
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use crate::device::AndGate;
+    use crate::device::Device;
+    use crate::device::TestProbe;
+    use crate::CircuitBuilder;
+    use crate::LogicValue;
+    use crate::PinDirection;
+
+    /// Drives each row of `cases` into the inputs of `gate` and checks the
+    /// output against the last value of the row.
+    pub(crate) fn check_gate<T: Device + 'static>(gate: T, cases: &[&[LogicValue]]) {
+        let gate_name = gate.get_name().to_string();
+        let input_count = gate.get_pin_count() - 1;
+        let mut builder = CircuitBuilder::new();
+        builder.add_device(gate);
+        for input in 1..=input_count {
+            let probe = format!("in{}", input);
+            builder.add_device(TestProbe::new(&probe, LogicValue::Z, PinDirection::Output));
+            let pin = ((b'A' + (input - 1) as u8) as char).to_string();
+            builder.connect(&format!("{}.pin", probe), &format!("{}.{}", gate_name, pin));
+        }
+        builder.add_device(TestProbe::new("out", LogicValue::Z, PinDirection::Input));
+        builder.connect(&format!("{}.Y", gate_name), "out.pin");
+        let (mut circuit, _devices) = builder.build().unwrap();
+
+        let mut tick = 1;
+        for case in cases {
+            let (expected, inputs) = case.split_last().unwrap();
+            for (input, value) in inputs.iter().enumerate() {
+                TestProbe::set_output(&circuit, format!("in{}", input + 1).as_str(), *value)
+                    .unwrap();
+            }
+            circuit.tick(tick).unwrap();
+            circuit.tick(tick + 1).unwrap();
+            tick += 2;
+            assert_eq!(
+                *expected,
+                TestProbe::get_value(&circuit, "out").unwrap(),
+                "{} with inputs {:?}",
+                gate_name,
+                inputs
+            );
+        }
+    }
+
+    #[test]
+    fn pin_names() {
+        let gate = AndGate::with_inputs("and", 3);
+        assert_eq!(4, gate.get_pin_count());
+        assert_eq!(Some(3), gate.get_pin_by_name("C"));
+        assert_eq!(Some(4), gate.get_pin_by_name("Y"));
+        assert_eq!(PinDirection::Output, gate.get_pin_info(4).get_direction());
+    }
+
+    #[test]
+    #[should_panic]
+    fn too_many_inputs() {
+        AndGate::with_inputs("and", 25);
+    }
+}
@@ -0,0 +1,216 @@
+use crate::device::Device;
+use crate::device::DeviceResponse;
+use crate::LogicValue;
+use crate::PinDirection;
+use crate::PinInfo;
+use crate::PinValue;
+
+/// Look-up table with inputs `A`, `B`, `C`, ... and output `Y`, like the
+/// gates. It drives entry `i` of its table, where bit 0 of `i` is input `A`,
+/// bit 1 is `B` and so on.
+///
+/// An unknown or floating input gives X unless every value it could have
+/// selects the same entry value, so a multiplexer with an unknown select but
+/// equal inputs still drives them. The result is driven one tick after the
+/// inputs change, a table without inputs drives its only entry from the first
+/// tick.
+#[derive(Debug)]
+pub struct Lut {
+    name: String,
+    table: Vec<bool>,
+    inputs: Vec<LogicValue>,
+    last_result: LogicValue,
+    next_result: LogicValue,
+}
+
+impl Lut {
+    /// Most inputs a table can have.
+    pub const MAX_INPUTS: usize = 16;
+
+    /// A table of `2^input_count` entries.
+    pub fn new(name: &str, input_count: usize, table: Vec<bool>) -> Lut {
+        assert!(
+            input_count <= Lut::MAX_INPUTS,
+            "look-up table {} cannot have {} inputs",
+            name,
+            input_count
+        );
+        assert_eq!(
+            1 << input_count,
+            table.len(),
+            "look-up table {} with {} inputs needs {} entries",
+            name,
+            input_count,
+            1 << input_count
+        );
+        let next_result = if input_count == 0 {
+            LogicValue::from_bool(table[0])
+        } else {
+            LogicValue::X
+        };
+        return Lut {
+            name: name.to_string(),
+            table,
+            inputs: vec![LogicValue::Z; input_count],
+            last_result: LogicValue::Z,
+            next_result,
+        };
+    }
+
+    /// Creates a table from a function of the input values, `A` first.
+    pub fn from_function(name: &str, input_count: usize, function: fn(&[bool]) -> bool) -> Lut {
+        let table = (0..1usize << input_count)
+            .map(|index| {
+                let inputs: Vec<bool> = (0..input_count)
+                    .map(|bit| (index >> bit) & 1 == 1)
+                    .collect();
+                return function(&inputs);
+            })
+            .collect();
+        return Lut::new(name, input_count, table);
+    }
+
+    pub fn get_table(&self) -> &[bool] {
+        return &self.table;
+    }
+
+    pub fn get_input_count(&self) -> usize {
+        return self.inputs.len();
+    }
+
+    pub fn get_output_pin(&self) -> usize {
+        return self.inputs.len() + 1;
+    }
+
+    /// Gets the name of input `index`, `A` for the first.
+    pub fn get_input_name(index: usize) -> String {
+        return ((b'A' + index as u8) as char).to_string();
+    }
+
+    fn evaluate(&self) -> LogicValue {
+        let mut known = 0;
+        let mut unknown_bits: Vec<usize> = Vec::new();
+        for (bit, input) in self.inputs.iter().enumerate() {
+            match input.to_bool() {
+                Some(true) => known |= 1 << bit,
+                Some(false) => {}
+                None => unknown_bits.push(bit),
+            }
+        }
+        let result = self.table[known];
+        for combination in 1..1usize << unknown_bits.len() {
+            let mut index = known;
+            for (i, bit) in unknown_bits.iter().enumerate() {
+                if (combination >> i) & 1 == 1 {
+                    index |= 1 << bit;
+                }
+            }
+            if self.table[index] != result {
+                return LogicValue::X;
+            }
+        }
+        return LogicValue::from_bool(result);
+    }
+}
+
+impl Device for Lut {
+    fn get_name(&self) -> &str {
+        return &self.name;
+    }
+
+    fn get_pin_count(&self) -> usize {
+        return self.inputs.len() + 1;
+    }
+
+    fn get_pin_info(&self, pin: usize) -> PinInfo {
+        if pin == self.get_output_pin() {
+            return PinInfo::new("Y", PinDirection::Output);
+        }
+        if pin == 0 || pin > self.inputs.len() {
+            panic!("look-up table {} has no pin {}", self.name, pin);
+        }
+        return PinInfo::new(&Lut::get_input_name(pin - 1), PinDirection::Input);
+    }
+
+    fn on_tick(&mut self, _tick: u64) -> DeviceResponse {
+        let mut response = DeviceResponse::new();
+        if self.next_result != self.last_result {
+            response.set_output(self.get_output_pin(), self.next_result);
+            self.last_result = self.next_result;
+        }
+        return response;
+    }
+
+    fn on_inputs_changed(&mut self, tick: u64, pins: &[PinValue]) -> DeviceResponse {
+        for pin in pins {
+            if pin.get_pin() == 0 || pin.get_pin() > self.inputs.len() {
+                panic!(
+                    "cannot set pin {} on look-up table {}",
+                    pin.get_pin(),
+                    self.name
+                );
+            }
+            self.inputs[pin.get_pin() - 1] = pin.get_logic_value();
+        }
+        self.next_result = self.evaluate();
+        if self.next_result != self.last_result {
+            return DeviceResponse::wake_at(tick + 1);
+        }
+        return DeviceResponse::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::device::logic_gate::tests::check_gate;
+    use crate::device::Lut;
+    use crate::device::TestProbe;
+    use crate::CircuitBuilder;
+    use crate::LogicValue;
+    use crate::LogicValue::*;
+    use crate::PinDirection;
+
+    #[test]
+    fn multiplexer() {
+        // Y = C ? B : A
+        let mux = Lut::from_function("mux", 3, |inputs| {
+            return if inputs[2] { inputs[1] } else { inputs[0] };
+        });
+        assert_eq!(
+            vec![false, true, false, true, false, false, true, true],
+            mux.get_table()
+        );
+        check_gate(
+            mux,
+            &[
+                &[One, Zero, Zero, One],
+                &[One, Zero, One, Zero],
+                &[Zero, One, One, One],
+                &[One, One, X, One],
+                &[One, Zero, X, X],
+                &[Z, Zero, One, Zero],
+                &[Z, Zero, Zero, X],
+            ],
+        );
+    }
+
+    #[test]
+    fn constant() {
+        let mut builder = CircuitBuilder::new();
+        builder.add_device(Lut::new("one", 0, vec![true]));
+        builder.add_device(TestProbe::new("out", LogicValue::Z, PinDirection::Input));
+        builder.connect("one.Y", "out.pin");
+        let (mut circuit, _devices) = builder.build().unwrap();
+        circuit.tick(1).unwrap();
+        assert_eq!(
+            LogicValue::One,
+            TestProbe::get_value(&circuit, "out").unwrap()
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn wrong_table_size() {
+        Lut::new("lut", 2, vec![true; 3]);
+    }
+}
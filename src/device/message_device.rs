@@ -0,0 +1,254 @@
+use crate::device::Device;
+use crate::device::DeviceResponse;
+use crate::CircuitToDeviceMessage;
+use crate::DeviceData;
+use crate::DeviceToCircuitMessage;
+use crate::PinDirection;
+use crate::PinInfo;
+use crate::PinValue;
+use std::sync::mpsc;
+use std::thread;
+use std::thread::JoinHandle;
+
+/// A device written as a hand-rolled loop over `CircuitToDeviceMessage`. After
+/// a `NextTick`, or after the `SetPin` marked `last`, it must send any outputs
+/// followed by exactly one `NextTick` reply. Wrap it in a `MessageDeviceAdapter`
+/// to use it in a circuit.
+pub trait MessageDevice: Send {
+    fn run(
+        &mut self,
+        tx: mpsc::Sender<DeviceToCircuitMessage>,
+        rx: mpsc::Receiver<CircuitToDeviceMessage>,
+    );
+
+    fn get_name(&self) -> &str;
+
+    /// Gets the number of pins, pins are numbered from 1 to the count.
+    fn get_pin_count(&self) -> usize;
+
+    /// Describes `pin`, which is between 1 and `get_pin_count`.
+    fn get_pin_info(&self, pin: usize) -> PinInfo;
+}
+
+/// Runs a `MessageDevice` on its own thread and exposes it as a `Device`.
+#[derive(Debug)]
+pub struct MessageDeviceAdapter {
+    name: String,
+    // pins[pin_index - 1] = description of the pin
+    pins: Vec<PinInfo>,
+    tx: mpsc::Sender<CircuitToDeviceMessage>,
+    rx: mpsc::Receiver<DeviceToCircuitMessage>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl MessageDeviceAdapter {
+    pub fn new(mut device: Box<dyn MessageDevice>) -> MessageDeviceAdapter {
+        let name = device.get_name().to_string();
+        let pins = (1..=device.get_pin_count())
+            .map(|pin| device.get_pin_info(pin))
+            .collect();
+        let (device_to_circuit_tx, device_to_circuit_rx) = mpsc::channel();
+        let (circuit_to_device_tx, circuit_to_device_rx) = mpsc::channel();
+        let thread = thread::spawn(move || {
+            device.run(device_to_circuit_tx, circuit_to_device_rx);
+        });
+        return MessageDeviceAdapter {
+            name,
+            pins,
+            tx: circuit_to_device_tx,
+            rx: device_to_circuit_rx,
+            thread: Some(thread),
+        };
+    }
+
+    fn send(&self, message: CircuitToDeviceMessage) {
+        self.tx
+            .send(message)
+            .unwrap_or_else(|_err| panic!("device {} has stopped", self.name));
+    }
+
+    fn recv(&self) -> DeviceToCircuitMessage {
+        match self.rx.recv() {
+            Result::Ok(message) => {
+                return message;
+            }
+            Result::Err(_err) => {
+                panic!("failed to receive from device {}", self.name);
+            }
+        }
+    }
+
+    fn recv_response(&self) -> DeviceResponse {
+        let mut response = DeviceResponse::new();
+        loop {
+            match self.recv() {
+                DeviceToCircuitMessage::NextTick { tick } => {
+                    response.set_next_tick(tick);
+                    return response;
+                }
+                DeviceToCircuitMessage::SetPin {
+                    pin,
+                    value,
+                    direction,
+                } => match direction {
+                    PinDirection::Output | PinDirection::InOut => response.set_output(pin, value),
+                    PinDirection::Input => response.release_pin(pin),
+                },
+                DeviceToCircuitMessage::TimingViolation { violation } => {
+                    response.add_timing_violation(violation);
+                }
+                DeviceToCircuitMessage::Data { data: _ } => {
+                    panic!("unexpected data from device {}", self.name);
+                }
+            }
+        }
+    }
+}
+
+impl Device for MessageDeviceAdapter {
+    fn get_name(&self) -> &str {
+        return &self.name;
+    }
+
+    fn get_pin_count(&self) -> usize {
+        return self.pins.len();
+    }
+
+    fn get_pin_info(&self, pin: usize) -> PinInfo {
+        return self.pins[pin - 1].clone();
+    }
+
+    fn on_tick(&mut self, tick: u64) -> DeviceResponse {
+        self.send(CircuitToDeviceMessage::NextTick { tick });
+        return self.recv_response();
+    }
+
+    fn on_inputs_changed(&mut self, tick: u64, pins: &[PinValue]) -> DeviceResponse {
+        for (i, pin) in pins.iter().enumerate() {
+            self.send(CircuitToDeviceMessage::SetPin {
+                tick,
+                pin: pin.get_pin(),
+                value: pin.get_value(),
+                last: i == pins.len() - 1,
+            });
+        }
+        return self.recv_response();
+    }
+
+    fn on_data(&mut self, data: Box<dyn DeviceData>) {
+        self.send(CircuitToDeviceMessage::Data { data });
+    }
+
+    fn on_data_request(&mut self, data: Box<dyn DeviceData>) -> Box<dyn DeviceData> {
+        self.send(CircuitToDeviceMessage::Data { data });
+        match self.recv() {
+            DeviceToCircuitMessage::Data { data } => {
+                return data;
+            }
+            message => {
+                panic!(
+                    "unexpected message from device {}: {:?}",
+                    self.name, message
+                );
+            }
+        }
+    }
+}
+
+impl Drop for MessageDeviceAdapter {
+    fn drop(&mut self) {
+        // the device may already have stopped, in which case there is nobody to tell
+        let _ = self.tx.send(CircuitToDeviceMessage::Terminate);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::device::Device;
+    use crate::device::MessageDevice;
+    use crate::device::MessageDeviceAdapter;
+    use crate::device::TestProbe;
+    use crate::Circuit;
+    use crate::CircuitToDeviceMessage;
+    use crate::DeviceToCircuitMessage;
+    use crate::Engine;
+    use crate::LogicValue;
+    use crate::Net;
+    use crate::NetConnection;
+    use crate::PinDirection;
+    use crate::PinInfo;
+    use std::cell::RefCell;
+    use std::sync::mpsc;
+
+    /// Drives its only pin high one tick after it is first woken.
+    struct DelayedHigh {
+        driven: bool,
+    }
+
+    impl MessageDevice for DelayedHigh {
+        fn run(
+            &mut self,
+            tx: mpsc::Sender<DeviceToCircuitMessage>,
+            rx: mpsc::Receiver<CircuitToDeviceMessage>,
+        ) {
+            while let Result::Ok(message) = rx.recv() {
+                match message {
+                    CircuitToDeviceMessage::NextTick { tick } => {
+                        let mut next_tick = u64::MAX;
+                        if tick == 1 {
+                            next_tick = 2;
+                        } else if !self.driven {
+                            tx.send(DeviceToCircuitMessage::SetPin {
+                                pin: 1,
+                                value: LogicValue::One.into(),
+                                direction: PinDirection::Output,
+                            })
+                            .unwrap();
+                            self.driven = true;
+                        }
+                        tx.send(DeviceToCircuitMessage::NextTick { tick: next_tick })
+                            .unwrap();
+                    }
+                    CircuitToDeviceMessage::Terminate => break,
+                    _ => panic!("unexpected message"),
+                }
+            }
+        }
+
+        fn get_name(&self) -> &str {
+            return "delayed_high";
+        }
+
+        fn get_pin_count(&self) -> usize {
+            return 1;
+        }
+
+        fn get_pin_info(&self, _pin: usize) -> PinInfo {
+            return PinInfo::new("Q", PinDirection::Output);
+        }
+    }
+
+    #[test]
+    fn adapts_message_loop_device() {
+        for engine in [Engine::Threaded, Engine::EventDriven] {
+            let device = MessageDeviceAdapter::new(Box::new(DelayedHigh { driven: false }));
+            let output_tp = TestProbe::new("output_tp", LogicValue::Z, PinDirection::Input);
+            let devices: Vec<RefCell<Box<dyn Device>>> = vec![
+                RefCell::new(Box::new(device)),
+                RefCell::new(Box::new(output_tp)),
+            ];
+            let nets = vec![Net::new(vec![
+                NetConnection::new(0, 1),
+                NetConnection::new(1, TestProbe::PIN),
+            ])];
+            let mut circuit = Circuit::with_engine(devices, nets, engine).unwrap();
+            assert_eq!(2, circuit.tick(1).unwrap());
+            assert_eq!(LogicValue::Z, TestProbe::get_value(&circuit, 1).unwrap());
+            assert_eq!(u64::MAX, circuit.tick(2).unwrap());
+            assert_eq!(LogicValue::One, TestProbe::get_value(&circuit, 1).unwrap());
+        }
+    }
+}
@@ -1,8 +1,77 @@
+#[allow(clippy::module_inception)]
 mod device;
 pub use device::Device;
 
+mod device_response;
+pub use device_response::DeviceResponse;
+
+mod device_host;
+pub(crate) use device_host::DeviceHost;
+
+mod message_device;
+pub use message_device::MessageDevice;
+pub use message_device::MessageDeviceAdapter;
+
+mod logic_gate;
+pub(crate) use logic_gate::LogicGate;
+
 mod and_gate;
 pub use and_gate::AndGate;
 
+mod or_gate;
+pub use or_gate::OrGate;
+
+mod not_gate;
+pub use not_gate::NotGate;
+
+mod nand_gate;
+pub use nand_gate::NandGate;
+
+mod nor_gate;
+pub use nor_gate::NorGate;
+
+mod xor_gate;
+pub use xor_gate::XorGate;
+
+mod xnor_gate;
+pub use xnor_gate::XnorGate;
+
+mod buf_gate;
+pub use buf_gate::BufGate;
+
+mod lut;
+pub use lut::Lut;
+
+mod flip_flop;
+pub(crate) use flip_flop::FlipFlop;
+
+mod d_flip_flop;
+pub use d_flip_flop::DFlipFlop;
+
+mod jk_flip_flop;
+pub use jk_flip_flop::JkFlipFlop;
+
+mod t_flip_flop;
+pub use t_flip_flop::TFlipFlop;
+
+mod latch;
+pub(crate) use latch::Latch;
+
+mod sr_latch;
+pub use sr_latch::SrLatch;
+
+mod d_latch;
+pub use d_latch::DLatch;
+
+mod sub_circuit;
+pub(crate) use sub_circuit::flatten_sub_circuits;
+pub use sub_circuit::SubCircuit;
+
+mod clock;
+pub use clock::Clock;
+
+mod tri_state_buffer;
+pub use tri_state_buffer::TriStateBuffer;
+
 mod test_probe;
 pub use test_probe::TestProbe;
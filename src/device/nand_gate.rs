@@ -0,0 +1,94 @@
+use crate::device::AndGate;
+use crate::device::Device;
+use crate::device::DeviceResponse;
+use crate::device::LogicGate;
+use crate::LogicValue;
+use crate::PinInfo;
+use crate::PinValue;
+
+/// Drives 0 when every input is 1 and 1 when any input is 0.
+#[derive(Debug)]
+pub struct NandGate {
+    gate: LogicGate,
+}
+
+impl NandGate {
+    pub const PIN_INPUT1: usize = 1;
+    pub const PIN_INPUT2: usize = 2;
+    /// Output of a two-input gate, see `get_output_pin` for wider ones.
+    pub const PIN_OUTPUT: usize = 3;
+
+    pub fn new(name: &str) -> NandGate {
+        return NandGate::with_inputs(name, 2);
+    }
+
+    /// A gate with inputs `A`, `B`, `C`, ... on pins 1 to `input_count`.
+    pub fn with_inputs(name: &str, input_count: usize) -> NandGate {
+        return NandGate {
+            gate: LogicGate::new(name, input_count, NandGate::evaluate),
+        };
+    }
+
+    pub fn get_output_pin(&self) -> usize {
+        return self.gate.get_output_pin();
+    }
+
+    pub(crate) fn evaluate(inputs: &[LogicValue]) -> LogicValue {
+        return !AndGate::evaluate(inputs);
+    }
+}
+
+impl Device for NandGate {
+    fn get_name(&self) -> &str {
+        return self.gate.get_name();
+    }
+
+    fn get_pin_count(&self) -> usize {
+        return self.gate.get_pin_count();
+    }
+
+    fn get_pin_info(&self, pin: usize) -> PinInfo {
+        return self.gate.get_pin_info(pin);
+    }
+
+    fn on_tick(&mut self, tick: u64) -> DeviceResponse {
+        return self.gate.on_tick(tick);
+    }
+
+    fn on_inputs_changed(&mut self, tick: u64, pins: &[PinValue]) -> DeviceResponse {
+        return self.gate.on_inputs_changed(tick, pins);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::device::logic_gate::tests::check_gate;
+    use crate::device::NandGate;
+    use crate::LogicValue::*;
+
+    #[test]
+    fn truth_table() {
+        check_gate(
+            NandGate::new("nand"),
+            &[
+                &[One, One, Zero],
+                &[One, Zero, One],
+                &[Zero, X, One],
+                &[One, X, X],
+                &[One, Z, X],
+            ],
+        );
+    }
+
+    #[test]
+    fn three_inputs() {
+        check_gate(
+            NandGate::with_inputs("nand3", 3),
+            &[
+                &[One, One, One, Zero],
+                &[One, Zero, One, One],
+                &[One, One, X, X],
+            ],
+        );
+    }
+}
@@ -0,0 +1,64 @@
+use crate::device::Device;
+use crate::device::DeviceResponse;
+use crate::device::LogicGate;
+use crate::LogicValue;
+use crate::PinInfo;
+use crate::PinValue;
+
+/// Drives the inverse of its input.
+#[derive(Debug)]
+pub struct NotGate {
+    gate: LogicGate,
+}
+
+impl NotGate {
+    pub const PIN_INPUT: usize = 1;
+    pub const PIN_OUTPUT: usize = 2;
+
+    pub fn new(name: &str) -> NotGate {
+        return NotGate {
+            gate: LogicGate::new(name, 1, NotGate::evaluate),
+        };
+    }
+
+    fn evaluate(inputs: &[LogicValue]) -> LogicValue {
+        return !inputs[0];
+    }
+}
+
+impl Device for NotGate {
+    fn get_name(&self) -> &str {
+        return self.gate.get_name();
+    }
+
+    fn get_pin_count(&self) -> usize {
+        return self.gate.get_pin_count();
+    }
+
+    fn get_pin_info(&self, pin: usize) -> PinInfo {
+        return self.gate.get_pin_info(pin);
+    }
+
+    fn on_tick(&mut self, tick: u64) -> DeviceResponse {
+        return self.gate.on_tick(tick);
+    }
+
+    fn on_inputs_changed(&mut self, tick: u64, pins: &[PinValue]) -> DeviceResponse {
+        return self.gate.on_inputs_changed(tick, pins);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::device::logic_gate::tests::check_gate;
+    use crate::device::NotGate;
+    use crate::LogicValue::*;
+
+    #[test]
+    fn truth_table() {
+        check_gate(
+            NotGate::new("not"),
+            &[&[Zero, One], &[One, Zero], &[X, X], &[Z, X]],
+        );
+    }
+}
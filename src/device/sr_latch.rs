@@ -0,0 +1,89 @@
+use crate::device::Device;
+use crate::device::DeviceResponse;
+use crate::device::Latch;
+use crate::LogicValue;
+use crate::PinInfo;
+use crate::PinValue;
+
+/// Set-reset latch, both inputs high is invalid and gives X.
+#[derive(Debug)]
+pub struct SrLatch {
+    latch: Latch,
+}
+
+impl SrLatch {
+    pub const PIN_SET: usize = 1;
+    pub const PIN_RESET: usize = 2;
+    pub const PIN_Q: usize = 3;
+    pub const PIN_QN: usize = 4;
+
+    pub fn new(name: &str) -> SrLatch {
+        return SrLatch {
+            latch: Latch::new(name, &["S", "R"], SrLatch::next_state),
+        };
+    }
+
+    /// Sets the state at power-on, X by default.
+    pub fn set_initial_state(&mut self, state: LogicValue) {
+        self.latch.set_initial_state(state);
+    }
+
+    fn next_state(inputs: &[LogicValue], state: LogicValue) -> LogicValue {
+        match (inputs[0].to_bool(), inputs[1].to_bool()) {
+            (Some(false), Some(false)) => state,
+            (Some(true), Some(false)) => LogicValue::One,
+            (Some(false), Some(true)) => LogicValue::Zero,
+            _ => LogicValue::X,
+        }
+    }
+}
+
+impl Device for SrLatch {
+    fn get_name(&self) -> &str {
+        return self.latch.get_name();
+    }
+
+    fn get_pin_count(&self) -> usize {
+        return self.latch.get_pin_count();
+    }
+
+    fn get_pin_info(&self, pin: usize) -> PinInfo {
+        return self.latch.get_pin_info(pin);
+    }
+
+    fn on_tick(&mut self, tick: u64) -> DeviceResponse {
+        return self.latch.on_tick(tick);
+    }
+
+    fn on_inputs_changed(&mut self, tick: u64, pins: &[PinValue]) -> DeviceResponse {
+        return self.latch.on_inputs_changed(tick, pins);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::device::flip_flop::tests::create_circuit;
+    use crate::device::flip_flop::tests::drive;
+    use crate::device::flip_flop::tests::get_q;
+    use crate::device::SrLatch;
+    use crate::LogicValue;
+
+    #[test]
+    fn set_hold_reset() {
+        let mut circuit = create_circuit(SrLatch::new("latch"), &["S", "R"]);
+        let mut tick = drive(&mut circuit, 1, "s", LogicValue::Zero);
+        assert_eq!(LogicValue::X, get_q(&circuit));
+
+        tick = drive(&mut circuit, tick, "s", LogicValue::One);
+        assert_eq!(LogicValue::One, get_q(&circuit));
+        tick = drive(&mut circuit, tick, "s", LogicValue::Zero);
+        assert_eq!(LogicValue::One, get_q(&circuit));
+
+        tick = drive(&mut circuit, tick, "r", LogicValue::One);
+        assert_eq!(LogicValue::Zero, get_q(&circuit));
+        tick = drive(&mut circuit, tick, "s", LogicValue::One);
+        assert_eq!(LogicValue::X, get_q(&circuit));
+        drive(&mut circuit, tick, "s", LogicValue::Zero);
+        assert_eq!(LogicValue::Zero, get_q(&circuit));
+    }
+}
@@ -0,0 +1,652 @@
+use crate::device::Device;
+use crate::device::DeviceResponse;
+use crate::DeviceData;
+use crate::Net;
+use crate::NetConnection;
+use crate::NetPull;
+use crate::NetResolution;
+use crate::NetlistError;
+use crate::PinInfo;
+use crate::PinValue;
+use crate::SimError;
+use std::cell::RefCell;
+
+/// A reusable part made of inner devices and the nets between them, with
+/// chosen inner pins exposed as its own pins by `add_port`. Sub circuits can
+/// contain sub circuits.
+///
+/// When the circuit is built the contents replace the sub circuit: inner
+/// devices and nets are added to the circuit under hierarchical names, e.g.
+/// net `sum` of sub circuit `adder3` inside `alu` becomes `alu.adder3.sum`,
+/// and a net connected to a port is merged with the inner net of the port.
+/// The sub circuit itself stays behind as a device without pins.
+#[derive(Debug)]
+pub struct SubCircuit {
+    name: String,
+    devices: Vec<RefCell<Box<dyn Device>>>,
+    nets: Vec<Net>,
+    // ports[pin - 1] = (the pin as seen from outside, inner device name, inner pin name)
+    ports: Vec<(PinInfo, String, String)>,
+}
+
+impl SubCircuit {
+    /// Wraps `devices` and the `nets` between them, connections by name refer
+    /// to the inner device names.
+    pub fn new(name: &str, devices: Vec<RefCell<Box<dyn Device>>>, nets: Vec<Net>) -> SubCircuit {
+        return SubCircuit {
+            name: name.to_string(),
+            devices,
+            nets,
+            ports: Vec::new(),
+        };
+    }
+
+    /// Exposes the inner pin `inner_pin`, e.g. `"xor1.A"`, as pin `name` with
+    /// the same direction and width and returns its number. The inner pin must
+    /// be connected to at most one inner net and to the whole of it.
+    pub fn add_port(&mut self, name: &str, inner_pin: &str) -> Result<usize, SimError> {
+        let unknown_device = |device_name: &str| SimError::UnknownDeviceName {
+            name: format!("{}.{}", self.name, device_name),
+        };
+        let (device_name, pin_name) = inner_pin
+            .rsplit_once('.')
+            .ok_or_else(|| unknown_device(inner_pin))?;
+        let device = self
+            .devices
+            .iter()
+            .find(|device| device.borrow().get_name() == device_name)
+            .ok_or_else(|| unknown_device(device_name))?
+            .borrow();
+        let pin = device
+            .get_pin_by_name(pin_name)
+            .ok_or_else(|| SimError::UnknownPinName {
+                device: format!("{}.{}", self.name, device_name),
+                pin: pin_name.to_string(),
+            })?;
+        let inner_info = device.get_pin_info(pin);
+        let mut info = PinInfo::bus(name, inner_info.get_direction(), inner_info.get_width());
+        if let Some(default_value) = inner_info.get_default_value() {
+            info.set_default_value(default_value);
+        }
+        drop(device);
+        self.ports
+            .push((info, device_name.to_string(), pin_name.to_string()));
+        return Ok(self.ports.len());
+    }
+}
+
+impl Device for SubCircuit {
+    fn get_name(&self) -> &str {
+        return &self.name;
+    }
+
+    fn get_pin_count(&self) -> usize {
+        return self.ports.len();
+    }
+
+    fn get_pin_info(&self, pin: usize) -> PinInfo {
+        match pin.checked_sub(1).and_then(|index| self.ports.get(index)) {
+            Some((info, _device, _pin)) => return info.clone(),
+            None => panic!("sub circuit {} has no pin {}", self.name, pin),
+        }
+    }
+
+    fn as_sub_circuit(&mut self) -> Option<&mut SubCircuit> {
+        return Some(self);
+    }
+}
+
+/// An inner device of a sub circuit, renamed to its hierarchical name.
+#[derive(Debug)]
+struct PrefixedDevice {
+    name: String,
+    device: Box<dyn Device>,
+}
+
+impl Device for PrefixedDevice {
+    fn get_name(&self) -> &str {
+        return &self.name;
+    }
+
+    fn get_pin_count(&self) -> usize {
+        return self.device.get_pin_count();
+    }
+
+    fn get_pin_info(&self, pin: usize) -> PinInfo {
+        return self.device.get_pin_info(pin);
+    }
+
+    fn get_pin_by_name(&self, name: &str) -> Option<usize> {
+        return self.device.get_pin_by_name(name);
+    }
+
+    fn get_zero_delay_paths(&self) -> Vec<(usize, usize)> {
+        return self.device.get_zero_delay_paths();
+    }
+
+    fn on_tick(&mut self, tick: u64) -> DeviceResponse {
+        return self.device.on_tick(tick);
+    }
+
+    fn on_inputs_changed(&mut self, tick: u64, pins: &[PinValue]) -> DeviceResponse {
+        return self.device.on_inputs_changed(tick, pins);
+    }
+
+    fn on_data(&mut self, data: Box<dyn DeviceData>) {
+        self.device.on_data(data);
+    }
+
+    fn on_data_request(&mut self, data: Box<dyn DeviceData>) -> Box<dyn DeviceData> {
+        return self.device.on_data_request(data);
+    }
+}
+
+/// What the bits of a port are joined to once its sub circuit is flattened.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum PortTarget {
+    /// Port bit `b` is bit `b + offset` of net `net`.
+    Net { net: usize, offset: usize },
+    /// The inner pin is not on any inner net.
+    Pin { device: usize, pin: usize },
+    /// The port could not be resolved, the error has been reported.
+    Invalid,
+}
+
+/// The flattened contents of one level of the hierarchy.
+struct Level {
+    devices: Vec<RefCell<Box<dyn Device>>>,
+    nets: Vec<Net>,
+    // ports[pin - 1] = where the port of the sub circuit ends up
+    ports: Vec<PortTarget>,
+}
+
+type Flattened = (Vec<RefCell<Box<dyn Device>>>, Vec<Net>, Vec<NetlistError>);
+
+/// Replaces every sub circuit among `devices`, at any depth, with its contents.
+/// The devices and nets given keep their indexes, the contents are added after
+/// them. Returns the problems found with ports.
+pub(crate) fn flatten_sub_circuits(
+    mut devices: Vec<RefCell<Box<dyn Device>>>,
+    nets: Vec<Net>,
+) -> Flattened {
+    if !devices
+        .iter_mut()
+        .any(|device| device.get_mut().as_sub_circuit().is_some())
+    {
+        return (devices, nets, Vec::new());
+    }
+    let mut errors: Vec<NetlistError> = Vec::new();
+    let level = flatten_level(devices, nets, "", Vec::new(), &mut errors);
+    return (level.devices, level.nets, errors);
+}
+
+/// Flattens the devices and nets of a sub circuit whose hierarchical name
+/// followed by `.` is `prefix`, or of the circuit if `prefix` is empty.
+/// `ports` are the `(description, inner device, inner pin)` of its ports.
+fn flatten_level(
+    devices: Vec<RefCell<Box<dyn Device>>>,
+    mut nets: Vec<Net>,
+    prefix: &str,
+    ports: Vec<(PinInfo, String, String)>,
+    errors: &mut Vec<NetlistError>,
+) -> Level {
+    let local_count = devices.len();
+    let names: Vec<String> = devices
+        .iter()
+        .map(|device| device.borrow().get_name().to_string())
+        .collect();
+
+    // resolve names while sub circuits still have their ports, names that are
+    // not local are left to the netlist check
+    for net in nets.iter_mut() {
+        for connection in net.connections_iter_mut() {
+            let pin_name = match connection.get_pin_name() {
+                Some(pin_name) if !connection.is_resolved() => pin_name.to_string(),
+                _ => continue,
+            };
+            match find_local_pin(&devices, &names, &pin_name) {
+                Some((device, pin)) => connection.resolve(device, pin),
+                None => {
+                    if !prefix.is_empty() {
+                        connection.set_pin_name(format!("{}{}", prefix, pin_name));
+                    }
+                }
+            }
+        }
+    }
+    let port_pins: Vec<Option<(usize, usize)>> = ports
+        .iter()
+        .map(|(_info, device, pin)| {
+            find_local_pin(&devices, &names, &format!("{}.{}", device, pin))
+        })
+        .collect();
+
+    // local devices keep their indexes, sub circuits keep an empty shell
+    let mut out_devices: Vec<RefCell<Box<dyn Device>>> = Vec::new();
+    let mut subs: Vec<(usize, SubCircuit)> = Vec::new();
+    for (device_index, mut device) in devices.into_iter().enumerate() {
+        if let Some(sub) = device.get_mut().as_sub_circuit() {
+            let contents = SubCircuit {
+                name: sub.name.clone(),
+                devices: std::mem::take(&mut sub.devices),
+                nets: std::mem::take(&mut sub.nets),
+                ports: std::mem::take(&mut sub.ports),
+            };
+            subs.push((device_index, contents));
+        }
+        if prefix.is_empty() {
+            out_devices.push(device);
+        } else {
+            out_devices.push(RefCell::new(Box::new(PrefixedDevice {
+                name: format!("{}{}", prefix, names[device_index]),
+                device: device.into_inner(),
+            })));
+        }
+    }
+
+    let mut lower_nets: Vec<Net> = Vec::new();
+    // sub_ports[device_index] = targets and descriptions of the ports of a sub circuit
+    let mut sub_ports: Vec<Option<Vec<(PortTarget, PinInfo)>>> =
+        (0..local_count).map(|_| None).collect();
+    for (device_index, sub) in subs {
+        let infos: Vec<PinInfo> = sub.ports.iter().map(|(info, _, _)| info.clone()).collect();
+        let sub_prefix = format!("{}{}.", prefix, sub.name);
+        let level = flatten_level(sub.devices, sub.nets, &sub_prefix, sub.ports, errors);
+        let device_offset = out_devices.len();
+        let net_offset = lower_nets.len();
+        out_devices.extend(level.devices);
+        for mut net in level.nets {
+            for connection in net.connections_iter_mut() {
+                if connection.is_resolved() {
+                    connection.resolve(
+                        connection.get_device() + device_offset,
+                        connection.get_pin(),
+                    );
+                }
+            }
+            lower_nets.push(net);
+        }
+        let targets = level
+            .ports
+            .into_iter()
+            .map(|target| match target {
+                PortTarget::Net { net, offset } => PortTarget::Net {
+                    net: net + net_offset,
+                    offset,
+                },
+                PortTarget::Pin { device, pin } => PortTarget::Pin {
+                    device: device + device_offset,
+                    pin,
+                },
+                PortTarget::Invalid => PortTarget::Invalid,
+            })
+            .zip(infos)
+            .collect();
+        sub_ports[device_index] = Some(targets);
+    }
+
+    // connections to ports are replaced by what the port leads to
+    let out_count = out_devices.len();
+    // merged[lower net] = (net it was merged into, bits it was shifted up by)
+    let mut merged: Vec<Option<(usize, usize)>> = vec![None; lower_nets.len()];
+    for (net_index, net) in nets.iter_mut().enumerate() {
+        let net_name = format!("{}{}", prefix, net.get_display_name(net_index));
+        for mut connection in net.take_connections() {
+            let device = connection.get_device();
+            if !connection.is_resolved() {
+                net.add_connection(connection);
+                continue;
+            }
+            if device >= local_count {
+                // keep unknown devices unknown for the netlist check
+                connection.resolve(device - local_count + out_count, connection.get_pin());
+                net.add_connection(connection);
+                continue;
+            }
+            let targets = match &sub_ports[device] {
+                Some(targets) => targets,
+                None => {
+                    net.add_connection(connection);
+                    continue;
+                }
+            };
+            let pin = connection.get_pin();
+            let sub_name = format!("{}{}", prefix, names[device]);
+            let (target, info) = match pin.checked_sub(1).and_then(|index| targets.get(index)) {
+                Some((target, info)) => (*target, info),
+                None => {
+                    errors.push(NetlistError::UnknownPin {
+                        net: net_name.clone(),
+                        device: sub_name,
+                        pin,
+                    });
+                    continue;
+                }
+            };
+            let port_error = NetlistError::InvalidPortConnection {
+                net: net_name.clone(),
+                device: sub_name,
+                pin: info.get_name().to_string(),
+            };
+            if connection.get_pin_lsb() != 0
+                || connection
+                    .get_width()
+                    .is_some_and(|connection_width| connection_width != info.get_width())
+            {
+                errors.push(port_error);
+                continue;
+            }
+            match target {
+                PortTarget::Pin { device, pin } => {
+                    net.add_connection(NetConnection::at(device, pin, connection.get_net_lsb()));
+                }
+                PortTarget::Net { net: lower, offset } => {
+                    if connection.get_net_lsb() < offset {
+                        errors.push(port_error);
+                        continue;
+                    }
+                    let shift = connection.get_net_lsb() - offset;
+                    match merged[lower] {
+                        None => {
+                            merged[lower] = Some((net_index, shift));
+                            for mut inner in lower_nets[lower].take_connections() {
+                                inner.shift(shift);
+                                net.add_connection(inner);
+                            }
+                            merge_settings(net, &lower_nets[lower]);
+                        }
+                        Some(merge) if merge == (net_index, shift) => {}
+                        Some(_) => errors.push(port_error),
+                    }
+                }
+                PortTarget::Invalid => {}
+            }
+        }
+    }
+
+    // lower nets that were not merged follow the nets of this level
+    let mut lower_indexes: Vec<usize> = Vec::new();
+    let mut next_index = nets.len();
+    for merge in &merged {
+        lower_indexes.push(next_index);
+        if merge.is_none() {
+            next_index += 1;
+        }
+    }
+
+    let mut targets: Vec<PortTarget> = Vec::new();
+    for ((info, _device, _pin), port_pin) in ports.iter().zip(port_pins) {
+        let (device, pin) = match port_pin {
+            Some(port_pin) => port_pin,
+            None => {
+                targets.push(PortTarget::Invalid);
+                continue;
+            }
+        };
+        let target = match &sub_ports[device] {
+            Some(sub_targets) => match sub_targets[pin - 1].0 {
+                PortTarget::Net { net, offset } => match merged[net] {
+                    Some((merged_net, shift)) => PortTarget::Net {
+                        net: merged_net,
+                        offset: offset + shift,
+                    },
+                    None => PortTarget::Net {
+                        net: lower_indexes[net],
+                        offset,
+                    },
+                },
+                target => target,
+            },
+            None => {
+                find_port_net(&nets, device, pin, info.get_width()).unwrap_or_else(|net_index| {
+                    errors.push(NetlistError::InvalidPortConnection {
+                        net: format!("{}{}", prefix, nets[net_index].get_display_name(net_index)),
+                        device: prefix.trim_end_matches('.').to_string(),
+                        pin: info.get_name().to_string(),
+                    });
+                    PortTarget::Invalid
+                })
+            }
+        };
+        targets.push(target);
+    }
+
+    if !prefix.is_empty() {
+        for (net_index, net) in nets.iter_mut().enumerate() {
+            let name = format!("{}{}", prefix, net.get_display_name(net_index));
+            net.set_name(&name);
+        }
+    }
+    nets.extend(
+        lower_nets
+            .into_iter()
+            .zip(merged)
+            .filter(|(_net, merge)| merge.is_none())
+            .map(|(net, _merge)| net),
+    );
+    return Level {
+        devices: out_devices,
+        nets,
+        ports: targets,
+    };
+}
+
+/// Finds what inner pin `pin` of `device`, `width` bits wide, is joined to.
+/// Fails with the index of a net that connects to it in part or that is not
+/// its only net.
+fn find_port_net(
+    nets: &[Net],
+    device: usize,
+    pin: usize,
+    width: usize,
+) -> Result<PortTarget, usize> {
+    let mut target = PortTarget::Pin { device, pin };
+    for (net_index, net) in nets.iter().enumerate() {
+        for connection in net.connections_iter() {
+            if !connection.is_resolved()
+                || connection.get_device() != device
+                || connection.get_pin() != pin
+            {
+                continue;
+            }
+            let whole = connection.get_pin_lsb() == 0
+                && connection
+                    .get_width()
+                    .is_none_or(|connection_width| connection_width == width);
+            if !whole || target != (PortTarget::Pin { device, pin }) {
+                return Err(net_index);
+            }
+            target = PortTarget::Net {
+                net: net_index,
+                offset: connection.get_net_lsb(),
+            };
+        }
+    }
+    return Ok(target);
+}
+
+/// A net merged with the inner net of a port takes the inner net's pull,
+/// resolution and delay where its own are left at their defaults.
+fn merge_settings(net: &mut Net, inner: &Net) {
+    if net.get_pull() == NetPull::default() {
+        net.set_pull(inner.get_pull());
+    }
+    if net.get_resolution() == NetResolution::default() {
+        net.set_resolution(inner.get_resolution());
+    }
+    net.set_delay(net.get_delay().max(inner.get_delay()));
+}
+
+fn find_local_pin(
+    devices: &[RefCell<Box<dyn Device>>],
+    names: &[String],
+    pin_name: &str,
+) -> Option<(usize, usize)> {
+    let (device_name, pin_name) = pin_name.rsplit_once('.')?;
+    let device_index = names.iter().position(|name| name == device_name)?;
+    let pin = devices[device_index].borrow().get_pin_by_name(pin_name)?;
+    return Some((device_index, pin));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::device::AndGate;
+    use crate::device::OrGate;
+    use crate::device::SubCircuit;
+    use crate::device::TestProbe;
+    use crate::device::XorGate;
+    use crate::BusValue;
+    use crate::CircuitBuilder;
+    use crate::LogicValue;
+    use crate::Net;
+    use crate::NetConnection;
+    use crate::NetlistError;
+    use crate::PinDirection;
+    use crate::SimError;
+
+    fn full_adder(name: &str) -> SubCircuit {
+        let mut builder = CircuitBuilder::new();
+        builder.add_device(XorGate::new("xor1"));
+        builder.add_device(XorGate::new("xor2"));
+        builder.add_device(AndGate::new("and1"));
+        builder.add_device(AndGate::new("and2"));
+        builder.add_device(OrGate::new("or"));
+        builder.add_device(TestProbe::new("p_tp", LogicValue::Z, PinDirection::Input));
+        let nets = [
+            ("a", vec!["xor1.A", "and1.A"]),
+            ("b", vec!["xor1.B", "and1.B"]),
+            ("p", vec!["xor1.Y", "xor2.A", "and2.A", "p_tp.pin"]),
+            ("cin", vec!["xor2.B", "and2.B"]),
+            ("g", vec!["and1.Y", "or.A"]),
+            ("t", vec!["and2.Y", "or.B"]),
+            ("sum", vec!["xor2.Y"]),
+            ("cout", vec!["or.Y"]),
+        ];
+        for (net, pins) in nets {
+            builder.add_net(Net::named(
+                net,
+                pins.into_iter().map(NetConnection::named).collect(),
+            ));
+        }
+        let mut adder = builder.into_sub_circuit(name);
+        adder.add_port("a", "xor1.A").unwrap();
+        adder.add_port("b", "xor1.B").unwrap();
+        adder.add_port("cin", "xor2.B").unwrap();
+        adder.add_port("sum", "xor2.Y").unwrap();
+        adder.add_port("cout", "or.Y").unwrap();
+        return adder;
+    }
+
+    #[test]
+    fn nested_adders() {
+        let mut alu_builder = CircuitBuilder::new();
+        alu_builder.add_device(full_adder("adder0"));
+        alu_builder.add_device(full_adder("adder1"));
+        alu_builder.add_net(Net::named(
+            "c1",
+            vec![
+                NetConnection::named("adder0.cout"),
+                NetConnection::named("adder1.cin"),
+            ],
+        ));
+        let mut alu = alu_builder.into_sub_circuit("alu");
+        for (port, inner_pin) in [
+            ("a0", "adder0.a"),
+            ("b0", "adder0.b"),
+            ("a1", "adder1.a"),
+            ("b1", "adder1.b"),
+            ("cin", "adder0.cin"),
+            ("s0", "adder0.sum"),
+            ("s1", "adder1.sum"),
+            ("cout", "adder1.cout"),
+        ] {
+            alu.add_port(port, inner_pin).unwrap();
+        }
+        assert_eq!(
+            Err(SimError::UnknownPinName {
+                device: "alu.adder0".to_string(),
+                pin: "x".to_string(),
+            }),
+            alu.add_port("x", "adder0.x")
+        );
+
+        let mut builder = CircuitBuilder::new();
+        builder.add_device(alu);
+        for input in ["a0", "b0", "a1", "b1", "cin"] {
+            builder.add_device(TestProbe::new(
+                input,
+                LogicValue::Zero,
+                PinDirection::Output,
+            ));
+            builder.connect(&format!("{}.pin", input), &format!("alu.{}", input));
+        }
+        for output in ["s0", "s1", "cout"] {
+            builder.add_device(TestProbe::new(output, LogicValue::Z, PinDirection::Input));
+            builder.connect(&format!("alu.{}", output), &format!("{}.pin", output));
+        }
+        let (mut circuit, _devices) = builder.build().unwrap();
+        circuit.start_waveform();
+        // 3 + 1
+        TestProbe::set_output_high(&circuit, "a0").unwrap();
+        TestProbe::set_output_high(&circuit, "a1").unwrap();
+        TestProbe::set_output_high(&circuit, "b0").unwrap();
+        circuit.run_until(10).unwrap();
+        let get_value = |name: &str| TestProbe::get_value(&circuit, name).unwrap();
+        assert_eq!(LogicValue::Zero, get_value("s0"));
+        assert_eq!(LogicValue::Zero, get_value("s1"));
+        assert_eq!(LogicValue::One, get_value("cout"));
+        // probes inside sub circuits are reached by their hierarchical names
+        assert_eq!(LogicValue::Zero, get_value("alu.adder0.p_tp"));
+        assert_eq!(LogicValue::One, get_value("alu.adder1.p_tp"));
+
+        TestProbe::set_output_high(&circuit, "cin").unwrap();
+        circuit.run_until(20).unwrap();
+        let get_value = |name: &str| TestProbe::get_value(&circuit, name).unwrap();
+        assert_eq!(LogicValue::One, get_value("s0"));
+        assert_eq!(LogicValue::Zero, get_value("s1"));
+        assert_eq!(LogicValue::One, get_value("cout"));
+
+        let waveform = circuit.get_waveform().unwrap();
+        assert_eq!(
+            Some(BusValue::from(LogicValue::One)),
+            waveform.get_value_at("alu.adder1.p", 20)
+        );
+        assert_eq!(
+            Some(BusValue::from(LogicValue::One)),
+            waveform.get_value_at("alu.c1", 20)
+        );
+    }
+
+    #[test]
+    fn port_connected_in_part() {
+        let mut inner = CircuitBuilder::new();
+        inner.add_device(TestProbe::new_bus(
+            "tp",
+            BusValue::z(2),
+            PinDirection::Output,
+        ));
+        let mut sub = inner.into_sub_circuit("sub");
+        sub.add_port("d", "tp.pin").unwrap();
+
+        let mut builder = CircuitBuilder::new();
+        builder.add_device(sub);
+        builder.add_device(TestProbe::new("in", LogicValue::One, PinDirection::Output));
+        builder.add_net(Net::named(
+            "half",
+            vec![
+                NetConnection::slice(0, 1, 1, 0, 1),
+                NetConnection::named("in.pin"),
+            ],
+        ));
+        assert_eq!(
+            SimError::InvalidNetlist {
+                errors: vec![NetlistError::InvalidPortConnection {
+                    net: "half".to_string(),
+                    device: "sub".to_string(),
+                    pin: "d".to_string(),
+                }]
+            },
+            builder.build().unwrap_err()
+        );
+    }
+}
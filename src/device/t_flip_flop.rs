@@ -0,0 +1,105 @@
+use crate::device::Device;
+use crate::device::DeviceResponse;
+use crate::device::FlipFlop;
+use crate::LogicValue;
+use crate::PinInfo;
+use crate::PinValue;
+use crate::TimingCheck;
+
+/// Rising-edge T flip-flop with clock enable and asynchronous set and reset,
+/// the state toggles on an edge while T is 1. `EN`, `SET` and `RST` may be
+/// left unconnected.
+#[derive(Debug)]
+pub struct TFlipFlop {
+    flip_flop: FlipFlop,
+}
+
+impl TFlipFlop {
+    pub const PIN_T: usize = 1;
+    pub const PIN_CLOCK: usize = 2;
+    pub const PIN_ENABLE: usize = 3;
+    pub const PIN_SET: usize = 4;
+    pub const PIN_RESET: usize = 5;
+    pub const PIN_Q: usize = 6;
+    pub const PIN_QN: usize = 7;
+
+    pub fn new(name: &str) -> TFlipFlop {
+        return TFlipFlop {
+            flip_flop: FlipFlop::new(name, &["T"], TFlipFlop::next_state),
+        };
+    }
+
+    /// Sets the state at power-on, X by default.
+    pub fn set_initial_state(&mut self, state: LogicValue) {
+        self.flip_flop.set_initial_state(state);
+    }
+
+    /// Declares a setup, hold, recovery, removal or pulse width check on the
+    /// pins of this flip-flop, e.g. `TimingCheck::setup(PIN_T, PIN_CLOCK, 2)`.
+    pub fn add_timing_check(&mut self, check: TimingCheck) {
+        self.flip_flop.add_timing_check(check);
+    }
+
+    /// Makes a failed timing check set the state to X, it is only reported
+    /// otherwise.
+    pub fn set_x_on_violation(&mut self, x_on_violation: bool) {
+        self.flip_flop.set_x_on_violation(x_on_violation);
+    }
+
+    fn next_state(data: &[LogicValue], state: LogicValue) -> LogicValue {
+        return data[0] ^ state;
+    }
+}
+
+impl Device for TFlipFlop {
+    fn get_name(&self) -> &str {
+        return self.flip_flop.get_name();
+    }
+
+    fn get_pin_count(&self) -> usize {
+        return self.flip_flop.get_pin_count();
+    }
+
+    fn get_pin_info(&self, pin: usize) -> PinInfo {
+        return self.flip_flop.get_pin_info(pin);
+    }
+
+    fn on_tick(&mut self, tick: u64) -> DeviceResponse {
+        return self.flip_flop.on_tick(tick);
+    }
+
+    fn on_inputs_changed(&mut self, tick: u64, pins: &[PinValue]) -> DeviceResponse {
+        return self.flip_flop.on_inputs_changed(tick, pins);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::device::flip_flop::tests::clock;
+    use crate::device::flip_flop::tests::create_circuit;
+    use crate::device::flip_flop::tests::drive;
+    use crate::device::flip_flop::tests::get_q;
+    use crate::device::TFlipFlop;
+    use crate::LogicValue;
+
+    #[test]
+    fn toggles_after_reset() {
+        let mut circuit = create_circuit(TFlipFlop::new("ff"), &["T", "CLK", "RST"]);
+        let mut tick = drive(&mut circuit, 1, "t", LogicValue::One);
+        tick = clock(&mut circuit, tick);
+        assert_eq!(LogicValue::X, get_q(&circuit));
+
+        tick = drive(&mut circuit, tick, "rst", LogicValue::One);
+        tick = drive(&mut circuit, tick, "rst", LogicValue::Zero);
+        assert_eq!(LogicValue::Zero, get_q(&circuit));
+
+        tick = clock(&mut circuit, tick);
+        assert_eq!(LogicValue::One, get_q(&circuit));
+        tick = clock(&mut circuit, tick);
+        assert_eq!(LogicValue::Zero, get_q(&circuit));
+
+        tick = drive(&mut circuit, tick, "t", LogicValue::Zero);
+        clock(&mut circuit, tick);
+        assert_eq!(LogicValue::Zero, get_q(&circuit));
+    }
+}
@@ -1,125 +1,147 @@
 use crate::device::Device;
+use crate::device::DeviceResponse;
+use crate::BusValue;
 use crate::Circuit;
-use crate::CircuitToDeviceMessage;
 use crate::DeviceData;
-use crate::DeviceToCircuitMessage;
+use crate::DeviceRef;
+use crate::LogicValue;
 use crate::PinDirection;
+use crate::PinInfo;
+use crate::PinValue;
+use crate::SimError;
 use std::any::Any;
-use std::sync::mpsc;
 
 #[derive(Debug)]
 pub struct TestProbe {
     name: String,
-    value: u32,
+    value: BusValue,
     direction: PinDirection,
+    // an in/out probe only drives its net while its value is not all Z
+    driving: bool,
     dirty: bool,
 }
 
 impl TestProbe {
     pub const PIN: usize = 1;
 
-    pub fn new(name: &str, value: u32, direction: PinDirection) -> TestProbe {
+    /// Creates a probe driving `value`, or for an input probe, reporting `value`
+    /// until the first tick delivers the value of its net.
+    pub fn new(name: &str, value: LogicValue, direction: PinDirection) -> TestProbe {
+        return TestProbe::new_bus(name, value.into(), direction);
+    }
+
+    /// Creates a probe as wide as `value`, which drives or samples a whole bus.
+    pub fn new_bus(name: &str, value: BusValue, direction: PinDirection) -> TestProbe {
         TestProbe {
             name: name.to_string(),
             value,
             direction,
+            driving: TestProbe::is_driving(direction, value),
             dirty: true,
         }
     }
 
-    pub fn set_output_high(circuit: &Circuit, device: usize) {
-        circuit.send_device_data(device, Box::new(TestProbeSetData::output_high()));
+    fn is_driving(direction: PinDirection, value: BusValue) -> bool {
+        match direction {
+            PinDirection::Input => false,
+            PinDirection::Output => true,
+            PinDirection::InOut => value != BusValue::z(value.get_width()),
+        }
     }
 
-    pub fn set_output_low(circuit: &Circuit, device: usize) {
-        circuit.send_device_data(device, Box::new(TestProbeSetData::output_low()));
+    pub fn set_output_high<D: DeviceRef<TestProbe>>(
+        circuit: &Circuit,
+        device: D,
+    ) -> Result<(), SimError> {
+        return TestProbe::set_data(circuit, device, TestProbeSetData::output_high());
     }
 
-    pub fn set_input(circuit: &Circuit, device: usize) {
-        circuit.send_device_data(device, Box::new(TestProbeSetData::input()));
+    pub fn set_output_low<D: DeviceRef<TestProbe>>(
+        circuit: &Circuit,
+        device: D,
+    ) -> Result<(), SimError> {
+        return TestProbe::set_data(circuit, device, TestProbeSetData::output_low());
     }
 
-    pub fn get_value(circuit: &Circuit, device: usize) -> u32 {
-        let results = circuit.recv_device_data(device, Box::new(TestProbeGetDataRequest::new()));
-        let data = results
-            .as_any()
-            .downcast_ref::<TestProbeGetDataResponse>()
-            .unwrap();
-        return data.get_value();
+    pub fn set_output<D: DeviceRef<TestProbe>>(
+        circuit: &Circuit,
+        device: D,
+        value: LogicValue,
+    ) -> Result<(), SimError> {
+        return TestProbe::set_output_bus(circuit, device, value.into());
     }
-}
 
-impl Device for TestProbe {
-    fn run(
-        &mut self,
-        tx: mpsc::Sender<DeviceToCircuitMessage>,
-        rx: mpsc::Receiver<CircuitToDeviceMessage>,
-    ) {
-        let mut run = true;
-        while run {
-            match rx.recv() {
-                Result::Ok(message) => match message {
-                    CircuitToDeviceMessage::NextTick { tick: _ } => {
-                        if self.dirty {
-                            tx.send(DeviceToCircuitMessage::SetPin {
-                                pin: TestProbe::PIN,
-                                value: self.value,
-                                direction: self.direction,
-                            })
-                            .unwrap();
-                            self.dirty = false;
-                        }
-                        tx.send(DeviceToCircuitMessage::NextTick { tick: u64::MAX })
-                            .unwrap();
-                    }
-                    CircuitToDeviceMessage::SetPin {
-                        tick: _,
-                        pin,
-                        value,
-                        last,
-                    } => match self.direction {
-                        PinDirection::Input => {
-                            if pin == TestProbe::PIN {
-                                self.value = value;
-                            } else {
-                                panic!("cannot set pin {} on test probe", pin);
-                            }
-                            if last {
-                                tx.send(DeviceToCircuitMessage::NextTick { tick: u64::MAX })
-                                    .unwrap();
-                            }
-                        }
-                        PinDirection::Output => {
-                            panic!("invalid set pin");
-                        }
-                    },
-                    CircuitToDeviceMessage::Terminate => {
-                        run = false;
-                    }
-                    CircuitToDeviceMessage::Data { data } => {
-                        if let Some(set_data) = data.as_any().downcast_ref::<TestProbeSetData>() {
-                            self.value = set_data.get_value();
-                            self.direction = set_data.get_direction();
-                            self.dirty = true;
-                        } else if let Some(_get_data) =
-                            data.as_any().downcast_ref::<TestProbeGetDataRequest>()
-                        {
-                            tx.send(DeviceToCircuitMessage::Data {
-                                data: Box::new(TestProbeGetDataResponse::new(self.value)),
-                            })
-                            .unwrap();
-                        } else {
-                            panic!("unexpected data");
-                        }
-                    }
-                },
-                Result::Err(_err) => {
-                    run = false;
-                }
+    pub fn set_output_bus<D: DeviceRef<TestProbe>>(
+        circuit: &Circuit,
+        device: D,
+        value: BusValue,
+    ) -> Result<(), SimError> {
+        return TestProbe::set_data(
+            circuit,
+            device,
+            TestProbeSetData::new(value, PinDirection::Output),
+        );
+    }
+
+    pub fn set_input<D: DeviceRef<TestProbe>>(
+        circuit: &Circuit,
+        device: D,
+    ) -> Result<(), SimError> {
+        return TestProbe::set_data(circuit, device, TestProbeSetData::input());
+    }
+
+    /// Gets the value of a single-bit probe.
+    pub fn get_value<D: DeviceRef<TestProbe>>(
+        circuit: &Circuit,
+        device: D,
+    ) -> Result<LogicValue, SimError> {
+        return Ok(TestProbe::get_bus_value(circuit, device)?.get_bit(0));
+    }
+
+    pub fn get_bus_value<D: DeviceRef<TestProbe>>(
+        circuit: &Circuit,
+        device: D,
+    ) -> Result<BusValue, SimError> {
+        let device = device.resolve(circuit)?;
+        let results = circuit.recv_device_data(device, Box::new(TestProbeGetDataRequest::new()))?;
+        match results.as_any().downcast_ref::<TestProbeGetDataResponse>() {
+            Some(data) => {
+                return Ok(data.get_value());
+            }
+            None => {
+                return Err(SimError::UnexpectedMessage {
+                    device: circuit.get_device_name(device).unwrap_or("").to_string(),
+                    message: format!("{:?}", results),
+                });
             }
         }
     }
 
+    /// Sends `data` to the probe, unless it drives a value of the wrong width.
+    fn set_data<D: DeviceRef<TestProbe>>(
+        circuit: &Circuit,
+        device: D,
+        data: TestProbeSetData,
+    ) -> Result<(), SimError> {
+        let device = device.resolve(circuit)?;
+        let expected = circuit
+            .get_pin_info(device, TestProbe::PIN)
+            .map(|pin| pin.get_width())
+            .unwrap_or(1);
+        let actual = data.get_value().get_width();
+        if data.get_direction() != PinDirection::Input && actual != expected {
+            return Err(SimError::PinWidthMismatch {
+                device: circuit.get_device_name(device).unwrap_or("").to_string(),
+                pin: "pin".to_string(),
+                expected,
+                actual,
+            });
+        }
+        return circuit.send_device_data(device, Box::new(data));
+    }
+}
+
+impl Device for TestProbe {
     fn get_name(&self) -> &str {
         return &self.name;
     }
@@ -127,32 +149,95 @@ impl Device for TestProbe {
     fn get_pin_count(&self) -> usize {
         return 1;
     }
+
+    /// Describes the pin with the direction the probe was created with, it can
+    /// be changed at run time.
+    fn get_pin_info(&self, _pin: usize) -> PinInfo {
+        return PinInfo::bus("pin", self.direction, self.value.get_width());
+    }
+
+    fn on_tick(&mut self, _tick: u64) -> DeviceResponse {
+        let mut response = DeviceResponse::new();
+        if self.dirty {
+            if self.driving {
+                response.set_output(TestProbe::PIN, self.value);
+            } else {
+                response.release_pin(TestProbe::PIN);
+            }
+            self.dirty = false;
+        }
+        return response;
+    }
+
+    fn on_inputs_changed(&mut self, _tick: u64, pins: &[PinValue]) -> DeviceResponse {
+        for pin in pins {
+            if pin.get_pin() != TestProbe::PIN {
+                panic!("cannot set pin {} on test probe", pin.get_pin());
+            }
+            if self.driving {
+                panic!("invalid set pin");
+            }
+            self.value = pin.get_value();
+        }
+        return DeviceResponse::new();
+    }
+
+    fn on_data(&mut self, data: Box<dyn DeviceData>) {
+        if let Some(set_data) = data.as_any().downcast_ref::<TestProbeSetData>() {
+            let width = self.value.get_width();
+            let direction = set_data.get_direction();
+            let value = match direction {
+                PinDirection::Output | PinDirection::InOut => set_data.get_value(),
+                PinDirection::Input => BusValue::z(width),
+            };
+            if value.get_width() != width {
+                panic!(
+                    "test probe {} is {} bits wide, cannot drive {:?}",
+                    self.name, width, value
+                );
+            }
+            self.direction = direction;
+            self.value = value;
+            self.driving = TestProbe::is_driving(direction, value);
+            self.dirty = true;
+        } else {
+            panic!("unexpected data");
+        }
+    }
+
+    fn on_data_request(&mut self, data: Box<dyn DeviceData>) -> Box<dyn DeviceData> {
+        if let Some(_get_data) = data.as_any().downcast_ref::<TestProbeGetDataRequest>() {
+            return Box::new(TestProbeGetDataResponse::new(self.value));
+        } else {
+            panic!("unexpected data");
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct TestProbeSetData {
-    value: u32,
+    value: BusValue,
     direction: PinDirection,
 }
 
 impl TestProbeSetData {
-    pub fn new(value: u32, direction: PinDirection) -> TestProbeSetData {
+    pub fn new(value: BusValue, direction: PinDirection) -> TestProbeSetData {
         return TestProbeSetData { value, direction };
     }
 
     pub fn output_high() -> TestProbeSetData {
-        return TestProbeSetData::new(u32::MAX, PinDirection::Output);
+        return TestProbeSetData::new(LogicValue::One.into(), PinDirection::Output);
     }
 
     pub fn output_low() -> TestProbeSetData {
-        return TestProbeSetData::new(0, PinDirection::Output);
+        return TestProbeSetData::new(LogicValue::Zero.into(), PinDirection::Output);
     }
 
     pub fn input() -> TestProbeSetData {
-        return TestProbeSetData::new(0, PinDirection::Input);
+        return TestProbeSetData::new(LogicValue::Z.into(), PinDirection::Input);
     }
 
-    pub fn get_value(&self) -> u32 {
+    pub fn get_value(&self) -> BusValue {
         return self.value;
     }
 
@@ -184,15 +269,15 @@ impl DeviceData for TestProbeGetDataRequest {
 
 #[derive(Debug)]
 pub struct TestProbeGetDataResponse {
-    value: u32,
+    value: BusValue,
 }
 
 impl TestProbeGetDataResponse {
-    pub fn new(value: u32) -> TestProbeGetDataResponse {
+    pub fn new(value: BusValue) -> TestProbeGetDataResponse {
         return TestProbeGetDataResponse { value };
     }
 
-    pub fn get_value(&self) -> u32 {
+    pub fn get_value(&self) -> BusValue {
         return self.value;
     }
 }
@@ -0,0 +1,221 @@
+use crate::device::Device;
+use crate::device::DeviceResponse;
+use crate::BusValue;
+use crate::LogicValue;
+use crate::PinDirection;
+use crate::PinInfo;
+use crate::PinValue;
+
+/// Drives its input onto its output while enable is 1 and releases the output
+/// (drives Z) while enable is 0, so several buffers can share a bus. An
+/// unknown or floating enable drives X.
+#[derive(Debug)]
+pub struct TriStateBuffer {
+    name: String,
+    input: BusValue,
+    enable: LogicValue,
+    last_result: BusValue,
+    next_result: BusValue,
+}
+
+impl TriStateBuffer {
+    pub const PIN_INPUT: usize = 1;
+    pub const PIN_ENABLE: usize = 2;
+    pub const PIN_OUTPUT: usize = 3;
+
+    pub fn new(name: &str) -> TriStateBuffer {
+        return TriStateBuffer::new_bus(name, 1);
+    }
+
+    /// A buffer whose input and output are `width` bits wide.
+    pub fn new_bus(name: &str, width: usize) -> TriStateBuffer {
+        return TriStateBuffer {
+            name: name.to_string(),
+            input: BusValue::z(width),
+            enable: LogicValue::Z,
+            last_result: BusValue::x(width),
+            next_result: BusValue::z(width),
+        };
+    }
+
+    fn evaluate(&self) -> BusValue {
+        let width = self.input.get_width();
+        match self.enable.to_bool() {
+            Some(true) => {
+                let mut result = self.input;
+                for bit in 0..width {
+                    result.set_bit(bit, self.input.get_bit(bit).as_input());
+                }
+                return result;
+            }
+            Some(false) => return BusValue::z(width),
+            None => return BusValue::x(width),
+        }
+    }
+}
+
+impl Device for TriStateBuffer {
+    fn get_name(&self) -> &str {
+        return &self.name;
+    }
+
+    fn get_pin_count(&self) -> usize {
+        return 3;
+    }
+
+    fn get_pin_info(&self, pin: usize) -> PinInfo {
+        let width = self.input.get_width();
+        match pin {
+            TriStateBuffer::PIN_INPUT => PinInfo::bus("A", PinDirection::Input, width),
+            TriStateBuffer::PIN_ENABLE => PinInfo::new("EN", PinDirection::Input),
+            TriStateBuffer::PIN_OUTPUT => PinInfo::bus("Y", PinDirection::Output, width),
+            _ => panic!("tri-state buffer {} has no pin {}", self.name, pin),
+        }
+    }
+
+    fn on_tick(&mut self, _tick: u64) -> DeviceResponse {
+        let mut response = DeviceResponse::new();
+        if self.next_result != self.last_result {
+            response.set_output(TriStateBuffer::PIN_OUTPUT, self.next_result);
+            self.last_result = self.next_result;
+        }
+        return response;
+    }
+
+    fn on_inputs_changed(&mut self, tick: u64, pins: &[PinValue]) -> DeviceResponse {
+        for pin in pins {
+            match pin.get_pin() {
+                TriStateBuffer::PIN_INPUT => self.input = pin.get_value(),
+                TriStateBuffer::PIN_ENABLE => self.enable = pin.get_logic_value(),
+                _ => panic!("cannot set pin {} on tri-state buffer", pin.get_pin()),
+            }
+        }
+        self.next_result = self.evaluate();
+        if self.next_result != self.last_result {
+            return DeviceResponse::wake_at(tick + 1);
+        }
+        return DeviceResponse::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::device::TestProbe;
+    use crate::device::TriStateBuffer;
+    use crate::BusValue;
+    use crate::CircuitBuilder;
+    use crate::LogicValue;
+    use crate::Net;
+    use crate::NetConnection;
+    use crate::PinDirection;
+    use crate::SimEvent;
+
+    #[test]
+    fn enable() {
+        let mut builder = CircuitBuilder::new();
+        builder.add_device(TriStateBuffer::new("buf"));
+        builder.add_device(TestProbe::new("a", LogicValue::One, PinDirection::Output));
+        builder.add_device(TestProbe::new("en", LogicValue::Zero, PinDirection::Output));
+        builder.add_device(TestProbe::new("y", LogicValue::X, PinDirection::Input));
+        builder.connect("a.pin", "buf.A");
+        builder.connect("en.pin", "buf.EN");
+        builder.connect("buf.Y", "y.pin");
+        let (mut circuit, _devices) = builder.build().unwrap();
+        circuit.tick(1).unwrap();
+        circuit.tick(2).unwrap();
+        assert_eq!(LogicValue::Z, TestProbe::get_value(&circuit, "y").unwrap());
+
+        TestProbe::set_output_high(&circuit, "en").unwrap();
+        circuit.tick(3).unwrap();
+        circuit.tick(4).unwrap();
+        assert_eq!(
+            LogicValue::One,
+            TestProbe::get_value(&circuit, "y").unwrap()
+        );
+
+        TestProbe::set_output(&circuit, "en", LogicValue::Z).unwrap();
+        circuit.tick(5).unwrap();
+        circuit.tick(6).unwrap();
+        assert_eq!(LogicValue::X, TestProbe::get_value(&circuit, "y").unwrap());
+    }
+
+    #[test]
+    fn shared_bus() {
+        let mut builder = CircuitBuilder::new();
+        for driver in ["buf1", "buf2"] {
+            builder.add_device(TriStateBuffer::new_bus(driver, 8));
+            builder.add_device(TestProbe::new_bus(
+                &format!("{}_data", driver),
+                BusValue::z(8),
+                PinDirection::Output,
+            ));
+            builder.add_device(TestProbe::new(
+                &format!("{}_en", driver),
+                LogicValue::Zero,
+                PinDirection::Output,
+            ));
+            builder.connect(&format!("{}_data.pin", driver), &format!("{}.A", driver));
+            builder.connect(&format!("{}_en.pin", driver), &format!("{}.EN", driver));
+        }
+        // the cpu port reads the bus while it is released and drives it otherwise
+        builder.add_device(TestProbe::new_bus(
+            "cpu",
+            BusValue::z(8),
+            PinDirection::InOut,
+        ));
+        builder.add_net(Net::named(
+            "data",
+            vec![
+                NetConnection::named("buf1.Y"),
+                NetConnection::named("buf2.Y"),
+                NetConnection::named("cpu.pin"),
+            ],
+        ));
+        let (mut circuit, _devices) = builder.build().unwrap();
+        TestProbe::set_output_bus(&circuit, "buf1_data", BusValue::from_u64(8, 0x12)).unwrap();
+        TestProbe::set_output_bus(&circuit, "buf2_data", BusValue::from_u64(8, 0x34)).unwrap();
+        circuit.tick(1).unwrap();
+        circuit.tick(2).unwrap();
+        assert_eq!(
+            BusValue::z(8),
+            TestProbe::get_bus_value(&circuit, "cpu").unwrap()
+        );
+
+        TestProbe::set_output_high(&circuit, "buf1_en").unwrap();
+        circuit.tick(3).unwrap();
+        circuit.tick(4).unwrap();
+        assert_eq!(
+            Some(0x12),
+            TestProbe::get_bus_value(&circuit, "cpu").unwrap().to_u64()
+        );
+
+        // handing the bus over on the same tick is not contention
+        TestProbe::set_output_low(&circuit, "buf1_en").unwrap();
+        TestProbe::set_output_high(&circuit, "buf2_en").unwrap();
+        circuit.tick(5).unwrap();
+        circuit.tick(6).unwrap();
+        assert_eq!(
+            Some(0x34),
+            TestProbe::get_bus_value(&circuit, "cpu").unwrap().to_u64()
+        );
+        assert!(circuit.take_events().is_empty());
+
+        TestProbe::set_output_low(&circuit, "buf2_en").unwrap();
+        circuit.tick(7).unwrap();
+        TestProbe::set_output_bus(&circuit, "cpu", BusValue::from_u64(8, 0x56)).unwrap();
+        circuit.tick(8).unwrap();
+        assert!(circuit.take_events().is_empty());
+
+        TestProbe::set_output_high(&circuit, "buf1_en").unwrap();
+        circuit.tick(9).unwrap();
+        circuit.tick(10).unwrap();
+        assert_eq!(
+            vec![SimEvent::BusContention {
+                tick: 10,
+                net: "data".to_string(),
+                devices: vec!["buf1".to_string(), "cpu".to_string()],
+            }],
+            circuit.take_events()
+        );
+    }
+}
@@ -0,0 +1,73 @@
+use crate::Circuit;
+use crate::SimError;
+use std::fmt;
+use std::marker::PhantomData;
+
+/// Index of a device of type `T` added to a `CircuitBuilder`.
+pub struct DeviceHandle<T> {
+    index: usize,
+    device_type: PhantomData<fn() -> T>,
+}
+
+impl<T> DeviceHandle<T> {
+    pub(crate) fn new(index: usize) -> DeviceHandle<T> {
+        return DeviceHandle {
+            index,
+            device_type: PhantomData,
+        };
+    }
+
+    pub fn get_index(&self) -> usize {
+        return self.index;
+    }
+}
+
+impl<T> Clone for DeviceHandle<T> {
+    fn clone(&self) -> DeviceHandle<T> {
+        *self
+    }
+}
+
+impl<T> Copy for DeviceHandle<T> {}
+
+impl<T> PartialEq for DeviceHandle<T> {
+    fn eq(&self, other: &DeviceHandle<T>) -> bool {
+        return self.index == other.index;
+    }
+}
+
+impl<T> Eq for DeviceHandle<T> {}
+
+impl<T> fmt::Debug for DeviceHandle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "DeviceHandle({})", self.index)
+    }
+}
+
+/// Anything that identifies a device of type `T` in a circuit: its index, its
+/// name or a `DeviceHandle<T>`.
+pub trait DeviceRef<T> {
+    fn resolve(&self, circuit: &Circuit) -> Result<usize, SimError>;
+}
+
+impl<T> DeviceRef<T> for usize {
+    fn resolve(&self, _circuit: &Circuit) -> Result<usize, SimError> {
+        return Ok(*self);
+    }
+}
+
+impl<T> DeviceRef<T> for &str {
+    fn resolve(&self, circuit: &Circuit) -> Result<usize, SimError> {
+        return circuit
+            .get_device_index(self)
+            .ok_or_else(|| SimError::UnknownDeviceName {
+                name: self.to_string(),
+            });
+    }
+}
+
+impl<T> DeviceRef<T> for DeviceHandle<T> {
+    fn resolve(&self, _circuit: &Circuit) -> Result<usize, SimError> {
+        return Ok(self.index);
+    }
+}
@@ -0,0 +1,442 @@
+use crate::device::AndGate;
+use crate::device::BufGate;
+use crate::device::Clock;
+use crate::device::DFlipFlop;
+use crate::device::DLatch;
+use crate::device::Device;
+use crate::device::JkFlipFlop;
+use crate::device::LogicGate;
+use crate::device::Lut;
+use crate::device::NandGate;
+use crate::device::NorGate;
+use crate::device::NotGate;
+use crate::device::OrGate;
+use crate::device::SrLatch;
+use crate::device::TFlipFlop;
+use crate::device::TestProbe;
+use crate::device::TriStateBuffer;
+use crate::device::XnorGate;
+use crate::device::XorGate;
+use crate::BusValue;
+use crate::LogicValue;
+use crate::PinDirection;
+use std::collections::HashMap;
+
+/// The `name=value` parameters of a device in a netlist file, in the order
+/// they were given.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeviceParams {
+    params: Vec<(String, String)>,
+}
+
+impl DeviceParams {
+    pub fn new() -> DeviceParams {
+        return DeviceParams::default();
+    }
+
+    /// Sets parameter `name`, replacing any value it already had.
+    pub fn set(&mut self, name: &str, value: &str) {
+        match self.params.iter_mut().find(|(other, _)| other == name) {
+            Some(param) => param.1 = value.to_string(),
+            None => self.params.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        return self
+            .params
+            .iter()
+            .find(|(other, _)| other == name)
+            .map(|(_, value)| value.as_str());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.params.is_empty();
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        return self
+            .params
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()));
+    }
+
+    /// Gets parameter `name` as a number, `default` if it is not set.
+    pub fn get_u64(&self, name: &str, default: u64) -> Result<u64, String> {
+        match self.get(name) {
+            Some(value) => {
+                return value
+                    .parse()
+                    .map_err(|_| format!("parameter {} must be a number, got {}", name, value))
+            }
+            None => return Ok(default),
+        }
+    }
+
+    /// Fails if a parameter other than `names` is set, constructors call this
+    /// first so misspelt parameters are not silently ignored.
+    pub fn check_names(&self, names: &[&str]) -> Result<(), String> {
+        match self
+            .params
+            .iter()
+            .find(|(name, _)| !names.contains(&name.as_str()))
+        {
+            Some((name, _)) => return Err(format!("unknown parameter {}", name)),
+            None => return Ok(()),
+        }
+    }
+}
+
+/// Creates a device called `name` from its parameters, or describes why the
+/// parameters are wrong.
+pub type DeviceConstructor = fn(&str, &DeviceParams) -> Result<Box<dyn Device>, String>;
+
+/// Maps the device type names used in netlist files to constructors.
+///
+/// `new` registers the built-in devices below, parameters are optional unless
+/// marked required and their defaults are in brackets.
+///
+/// | type | device | parameters |
+/// |------|--------|------------|
+/// | `and`, `or`, `nand`, `nor`, `xor`, `xnor` | the gate | `inputs` (2) |
+/// | `not`, `buf` | the gate | |
+/// | `tri_state_buffer` | `TriStateBuffer` | `width` (1) |
+/// | `d_flip_flop`, `jk_flip_flop`, `t_flip_flop` | the flip-flop | `init` state `0`, `1` or `x` (x) |
+/// | `sr_latch`, `d_latch` | the latch | `init` state `0`, `1` or `x` (x) |
+/// | `lut` | `Lut` | `table` (required), the entries from the last to the first, e.g. `1000` for a two input and |
+/// | `clock` | `Clock` | `period` (required), `duty` in percent (50), `phase` (0), `stop` |
+/// | `test_probe` | `TestProbe` | `direction` `input`, `output` or `inout` (input), `width` (1), `value` e.g. `01z` (all Z, all 0 for outputs) |
+#[derive(Debug, Clone)]
+pub struct DeviceRegistry {
+    constructors: HashMap<String, DeviceConstructor>,
+}
+
+impl DeviceRegistry {
+    /// Creates a registry of the built-in devices.
+    pub fn new() -> DeviceRegistry {
+        let mut registry = DeviceRegistry::empty();
+        registry.register("and", |name, params| {
+            return Ok(Box::new(AndGate::with_inputs(
+                name,
+                get_input_count(params)?,
+            )));
+        });
+        registry.register("or", |name, params| {
+            return Ok(Box::new(OrGate::with_inputs(
+                name,
+                get_input_count(params)?,
+            )));
+        });
+        registry.register("nand", |name, params| {
+            return Ok(Box::new(NandGate::with_inputs(
+                name,
+                get_input_count(params)?,
+            )));
+        });
+        registry.register("nor", |name, params| {
+            return Ok(Box::new(NorGate::with_inputs(
+                name,
+                get_input_count(params)?,
+            )));
+        });
+        registry.register("xor", |name, params| {
+            return Ok(Box::new(XorGate::with_inputs(
+                name,
+                get_input_count(params)?,
+            )));
+        });
+        registry.register("xnor", |name, params| {
+            return Ok(Box::new(XnorGate::with_inputs(
+                name,
+                get_input_count(params)?,
+            )));
+        });
+        registry.register("not", |name, params| {
+            params.check_names(&[])?;
+            return Ok(Box::new(NotGate::new(name)));
+        });
+        registry.register("buf", |name, params| {
+            params.check_names(&[])?;
+            return Ok(Box::new(BufGate::new(name)));
+        });
+        registry.register("tri_state_buffer", |name, params| {
+            params.check_names(&["width"])?;
+            let width = get_width(params, 1)?;
+            return Ok(Box::new(TriStateBuffer::new_bus(name, width)));
+        });
+        registry.register("d_flip_flop", |name, params| {
+            params.check_names(&["init"])?;
+            let mut device = DFlipFlop::new(name);
+            device.set_initial_state(get_initial_state(params)?);
+            return Ok(Box::new(device));
+        });
+        registry.register("jk_flip_flop", |name, params| {
+            params.check_names(&["init"])?;
+            let mut device = JkFlipFlop::new(name);
+            device.set_initial_state(get_initial_state(params)?);
+            return Ok(Box::new(device));
+        });
+        registry.register("t_flip_flop", |name, params| {
+            params.check_names(&["init"])?;
+            let mut device = TFlipFlop::new(name);
+            device.set_initial_state(get_initial_state(params)?);
+            return Ok(Box::new(device));
+        });
+        registry.register("sr_latch", |name, params| {
+            params.check_names(&["init"])?;
+            let mut device = SrLatch::new(name);
+            device.set_initial_state(get_initial_state(params)?);
+            return Ok(Box::new(device));
+        });
+        registry.register("d_latch", |name, params| {
+            params.check_names(&["init"])?;
+            let mut device = DLatch::new(name);
+            device.set_initial_state(get_initial_state(params)?);
+            return Ok(Box::new(device));
+        });
+        registry.register("lut", create_lut);
+        registry.register("clock", create_clock);
+        registry.register("test_probe", create_test_probe);
+        return registry;
+    }
+
+    /// Creates a registry without any devices.
+    pub fn empty() -> DeviceRegistry {
+        return DeviceRegistry {
+            constructors: HashMap::new(),
+        };
+    }
+
+    /// Registers `constructor` for `type_name`, replacing any constructor the
+    /// type already had.
+    pub fn register(&mut self, type_name: &str, constructor: DeviceConstructor) {
+        self.constructors.insert(type_name.to_string(), constructor);
+    }
+
+    pub fn contains(&self, type_name: &str) -> bool {
+        return self.constructors.contains_key(type_name);
+    }
+
+    /// Gets the registered type names in alphabetical order.
+    pub fn get_type_names(&self) -> Vec<&str> {
+        let mut type_names: Vec<&str> =
+            self.constructors.keys().map(|name| name.as_str()).collect();
+        type_names.sort();
+        return type_names;
+    }
+
+    /// Creates a device of type `type_name` called `name`.
+    pub fn create(
+        &self,
+        type_name: &str,
+        name: &str,
+        params: &DeviceParams,
+    ) -> Result<Box<dyn Device>, String> {
+        match self.constructors.get(type_name) {
+            Some(constructor) => {
+                return constructor(name, params)
+                    .map_err(|message| format!("device {}: {}", name, message))
+            }
+            None => return Err(format!("unknown device type {}", type_name)),
+        }
+    }
+}
+
+impl Default for DeviceRegistry {
+    fn default() -> DeviceRegistry {
+        return DeviceRegistry::new();
+    }
+}
+
+fn get_input_count(params: &DeviceParams) -> Result<usize, String> {
+    params.check_names(&["inputs"])?;
+    let inputs = params.get_u64("inputs", 2)?;
+    if inputs == 0 || inputs > LogicGate::MAX_INPUTS as u64 {
+        return Err(format!(
+            "a gate takes 1 to {} inputs, got {}",
+            LogicGate::MAX_INPUTS,
+            inputs
+        ));
+    }
+    return Ok(inputs as usize);
+}
+
+fn get_initial_state(params: &DeviceParams) -> Result<LogicValue, String> {
+    match params.get("init") {
+        None | Some("x") => return Ok(LogicValue::X),
+        Some("0") => return Ok(LogicValue::Zero),
+        Some("1") => return Ok(LogicValue::One),
+        Some(init) => return Err(format!("init must be 0, 1 or x, got {}", init)),
+    }
+}
+
+fn get_width(params: &DeviceParams, default: u64) -> Result<usize, String> {
+    let width = params.get_u64("width", default)?;
+    if width == 0 || width > BusValue::MAX_WIDTH as u64 {
+        return Err(format!(
+            "width must be 1 to {}, got {}",
+            BusValue::MAX_WIDTH,
+            width
+        ));
+    }
+    return Ok(width as usize);
+}
+
+fn create_lut(name: &str, params: &DeviceParams) -> Result<Box<dyn Device>, String> {
+    params.check_names(&["table"])?;
+    let text = params
+        .get("table")
+        .ok_or_else(|| "parameter table is required".to_string())?;
+    let table = text
+        .chars()
+        .rev()
+        .map(|entry| match entry {
+            '0' => Some(false),
+            '1' => Some(true),
+            _ => None,
+        })
+        .collect::<Option<Vec<bool>>>()
+        .ok_or_else(|| format!("table must be made of 0 and 1, got {}", text))?;
+    let input_count = table.len().trailing_zeros() as usize;
+    if !table.len().is_power_of_two() || input_count > Lut::MAX_INPUTS {
+        return Err(format!(
+            "a table has 2^n entries for up to {} inputs, got {}",
+            Lut::MAX_INPUTS,
+            table.len()
+        ));
+    }
+    return Ok(Box::new(Lut::new(name, input_count, table)));
+}
+
+fn create_clock(name: &str, params: &DeviceParams) -> Result<Box<dyn Device>, String> {
+    params.check_names(&["period", "duty", "phase", "stop"])?;
+    if params.get("period").is_none() {
+        return Err("parameter period is required".to_string());
+    }
+    let period = params.get_u64("period", 0)?;
+    if period < 2 {
+        return Err(format!("period must be at least 2, got {}", period));
+    }
+    let mut clock = Clock::new(name, period);
+    if params.get("duty").is_some() {
+        let duty = params.get_u64("duty", 50)?;
+        let high_ticks = period.saturating_mul(duty) / 100;
+        if high_ticks == 0 || high_ticks >= period {
+            return Err(format!(
+                "a {}% duty cycle does not fit a period of {}",
+                duty, period
+            ));
+        }
+        clock.set_duty_cycle(duty);
+    }
+    clock.set_phase(params.get_u64("phase", 0)?);
+    if params.get("stop").is_some() {
+        clock.set_stop_tick(Some(params.get_u64("stop", 0)?));
+    }
+    return Ok(Box::new(clock));
+}
+
+fn create_test_probe(name: &str, params: &DeviceParams) -> Result<Box<dyn Device>, String> {
+    params.check_names(&["direction", "width", "value"])?;
+    let direction = match params.get("direction") {
+        None | Some("input") => PinDirection::Input,
+        Some("output") => PinDirection::Output,
+        Some("inout") => PinDirection::InOut,
+        Some(direction) => {
+            return Err(format!(
+                "direction must be input, output or inout, got {}",
+                direction
+            ))
+        }
+    };
+    let value = match params.get("value") {
+        Some(text) => {
+            let value = BusValue::parse(text)
+                .ok_or_else(|| format!("value must be made of 0, 1, x and z, got {}", text))?;
+            if params.get("width").is_some() && get_width(params, 1)? != value.get_width() {
+                return Err(format!("value {} does not have the width given", text));
+            }
+            value
+        }
+        None => {
+            let fill = if direction == PinDirection::Output {
+                LogicValue::Zero
+            } else {
+                LogicValue::Z
+            };
+            BusValue::filled(get_width(params, 1)?, fill)
+        }
+    };
+    return Ok(Box::new(TestProbe::new_bus(name, value, direction)));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::device::OrGate;
+    use crate::DeviceParams;
+    use crate::DeviceRegistry;
+
+    #[test]
+    fn creates_built_in_devices() {
+        let registry = DeviceRegistry::new();
+        let mut params = DeviceParams::new();
+        params.set("inputs", "3");
+        let gate = registry.create("and", "and1", &params).unwrap();
+        assert_eq!("and1", gate.get_name());
+        assert_eq!(4, gate.get_pin_count());
+
+        params.set("inputs", "25");
+        assert_eq!(
+            Err("device and1: a gate takes 1 to 24 inputs, got 25".to_string()),
+            registry.create("and", "and1", &params).map(|_| ())
+        );
+        assert_eq!(
+            Err("device not1: unknown parameter inputs".to_string()),
+            registry.create("not", "not1", &params).map(|_| ())
+        );
+        assert_eq!(
+            Err("unknown device type adder".to_string()),
+            registry.create("adder", "add1", &params).map(|_| ())
+        );
+
+        let mut params = DeviceParams::new();
+        params.set("direction", "output");
+        params.set("value", "10z");
+        let probe = registry.create("test_probe", "tp", &params).unwrap();
+        assert_eq!(3, probe.get_pin_info(1).get_width());
+        params.set("width", "2");
+        assert_eq!(
+            Err("device tp: value 10z does not have the width given".to_string()),
+            registry.create("test_probe", "tp", &params).map(|_| ())
+        );
+
+        let mut params = DeviceParams::new();
+        params.set("table", "0110");
+        let lut = registry.create("lut", "xor", &params).unwrap();
+        assert_eq!(Some(3), lut.get_pin_by_name("Y"));
+        params.set("table", "011");
+        assert_eq!(
+            Err("device xor: a table has 2^n entries for up to 16 inputs, got 3".to_string()),
+            registry.create("lut", "xor", &params).map(|_| ())
+        );
+
+        let mut params = DeviceParams::new();
+        params.set("init", "2");
+        assert_eq!(
+            Err("device ff: init must be 0, 1 or x, got 2".to_string()),
+            registry.create("d_flip_flop", "ff", &params).map(|_| ())
+        );
+    }
+
+    #[test]
+    fn registers_custom_devices() {
+        let mut registry = DeviceRegistry::empty();
+        assert!(!registry.contains("and"));
+        registry.register("or3", |name, _params| {
+            return Ok(Box::new(OrGate::with_inputs(name, 3)));
+        });
+        let gate = registry.create("or3", "g", &DeviceParams::new()).unwrap();
+        assert_eq!(4, gate.get_pin_count());
+        assert_eq!(vec!["or3"], registry.get_type_names());
+    }
+}
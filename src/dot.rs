@@ -0,0 +1,338 @@
+use crate::BusValue;
+use crate::LogicValue;
+use crate::PinDirection;
+use crate::PinInfo;
+use std::io;
+use std::io::Write;
+
+/// What `Circuit::write_dot` draws, by default nets joining two pins are edges
+/// and other nets are nodes, without clusters or values.
+#[derive(Debug, Clone, Default)]
+pub struct DotOptions {
+    net_nodes: bool,
+    clusters: bool,
+    values: bool,
+}
+
+impl DotOptions {
+    pub fn new() -> DotOptions {
+        return DotOptions::default();
+    }
+
+    pub fn get_net_nodes(&self) -> bool {
+        return self.net_nodes;
+    }
+
+    /// Draws every net as a node joined to its pins.
+    pub fn set_net_nodes(&mut self, net_nodes: bool) {
+        self.net_nodes = net_nodes;
+    }
+
+    pub fn get_clusters(&self) -> bool {
+        return self.clusters;
+    }
+
+    /// Groups devices and nets by the parts of their names before the last
+    /// `.`, so each sub circuit instance is a cluster.
+    pub fn set_clusters(&mut self, clusters: bool) {
+        self.clusters = clusters;
+    }
+
+    pub fn get_values(&self) -> bool {
+        return self.values;
+    }
+
+    /// Labels nets with their current value and colors them: 0 dark green,
+    /// 1 green, Z blue and X red, buses are red if a bit is X, blue if all
+    /// bits are Z and black otherwise.
+    pub fn set_values(&mut self, values: bool) {
+        self.values = values;
+    }
+}
+
+/// A net to draw, `pins` are `(device, pin)`.
+pub(crate) struct DotNet<'a> {
+    pub(crate) name: &'a str,
+    pub(crate) value: BusValue,
+    pub(crate) pins: Vec<(usize, usize)>,
+}
+
+/// Writes `devices`, their names and pins, and `nets` as a DOT graph.
+pub(crate) fn write_dot<W: Write>(
+    out: &mut W,
+    options: &DotOptions,
+    devices: &[(&str, &[PinInfo])],
+    nets: &[DotNet],
+) -> io::Result<()> {
+    let split = |name: &str| -> (Vec<String>, String) {
+        let parts: Vec<&str> = name.split('.').collect();
+        match parts.split_last() {
+            Some((last, scopes)) if options.clusters => {
+                return (
+                    scopes.iter().map(|scope| scope.to_string()).collect(),
+                    last.to_string(),
+                )
+            }
+            _ => return (Vec::new(), name.to_string()),
+        }
+    };
+    // (scopes, node statement)
+    let mut nodes: Vec<(Vec<String>, String)> = Vec::new();
+    for (device_index, (name, pins)) in devices.iter().enumerate() {
+        // flattened sub circuits stay behind without pins, the cluster shows them
+        if pins.is_empty() {
+            continue;
+        }
+        let field = |pin_index: usize, pin: &PinInfo| {
+            return format!("<p{}> {}", pin_index + 1, escape_record(pin.get_name()));
+        };
+        let inputs: Vec<String> = pins
+            .iter()
+            .enumerate()
+            .filter(|(_, pin)| pin.get_direction() == PinDirection::Input)
+            .map(|(pin_index, pin)| field(pin_index, pin))
+            .collect();
+        let outputs: Vec<String> = pins
+            .iter()
+            .enumerate()
+            .filter(|(_, pin)| pin.get_direction() != PinDirection::Input)
+            .map(|(pin_index, pin)| field(pin_index, pin))
+            .collect();
+        let (scopes, label) = split(name);
+        let mut fields: Vec<String> = Vec::new();
+        if !inputs.is_empty() {
+            fields.push(format!("{{{}}}", inputs.join("|")));
+        }
+        fields.push(escape_record(&label));
+        if !outputs.is_empty() {
+            fields.push(format!("{{{}}}", outputs.join("|")));
+        }
+        nodes.push((
+            scopes,
+            format!("d{} [label=\"{}\"];", device_index, fields.join("|")),
+        ));
+    }
+
+    let mut edges: Vec<String> = Vec::new();
+    for (net_index, net) in nets.iter().enumerate() {
+        let mut pins: Vec<(usize, usize)> = Vec::new();
+        for pin in &net.pins {
+            if !pins.contains(pin) {
+                pins.push(*pin);
+            }
+        }
+        let direction = |(device, pin): (usize, usize)| devices[device].1[pin - 1].get_direction();
+        let (scopes, name) = split(net.name);
+        let mut label = name;
+        let mut color: Vec<String> = Vec::new();
+        if options.values {
+            label = format!("{} = {}", label, net.value);
+            color.push(format!("color=\"{}\"", get_color(net.value)));
+        }
+        if pins.len() == 2 && !options.net_nodes {
+            // from the pin that drives, if any
+            let (from, to) = match direction(pins[0]) {
+                PinDirection::Input => (pins[1], pins[0]),
+                _ => (pins[0], pins[1]),
+            };
+            let dir = match (direction(from), direction(to)) {
+                (PinDirection::Input, _) => "none",
+                (_, PinDirection::Input) => "forward",
+                _ => "both",
+            };
+            let mut attributes = vec![
+                format!("label=\"{}\"", escape(&label)),
+                format!("dir={}", dir),
+            ];
+            attributes.extend(color);
+            edges.push(format!(
+                "d{}:p{} -> d{}:p{} [{}];",
+                from.0,
+                from.1,
+                to.0,
+                to.1,
+                attributes.join(", ")
+            ));
+            continue;
+        }
+        let mut attributes = vec![
+            "shape=point".to_string(),
+            format!("xlabel=\"{}\"", escape(&label)),
+        ];
+        attributes.extend(color.iter().cloned());
+        nodes.push((
+            scopes,
+            format!("n{} [{}];", net_index, attributes.join(", ")),
+        ));
+        for pin in pins {
+            let mut attributes = color.clone();
+            let edge = match direction(pin) {
+                PinDirection::Input => format!("n{} -> d{}:p{}", net_index, pin.0, pin.1),
+                PinDirection::Output => format!("d{}:p{} -> n{}", pin.0, pin.1, net_index),
+                PinDirection::InOut => {
+                    attributes.insert(0, "dir=both".to_string());
+                    format!("d{}:p{} -> n{}", pin.0, pin.1, net_index)
+                }
+            };
+            if attributes.is_empty() {
+                edges.push(format!("{};", edge));
+            } else {
+                edges.push(format!("{} [{}];", edge, attributes.join(", ")));
+            }
+        }
+    }
+
+    writeln!(out, "digraph circuit {{")?;
+    writeln!(out, "  rankdir=LR;")?;
+    writeln!(out, "  node [shape=record];")?;
+    // stable, so nodes keep their order within a scope
+    nodes.sort_by(|a, b| a.0.cmp(&b.0));
+    let mut scopes: Vec<&str> = Vec::new();
+    let mut clusters = 0;
+    for (node_scopes, node) in &nodes {
+        let common = scopes
+            .iter()
+            .zip(node_scopes.iter())
+            .take_while(|(a, b)| *a == b)
+            .count();
+        while scopes.len() > common {
+            scopes.pop();
+            writeln!(out, "{}}}", "  ".repeat(scopes.len() + 1))?;
+        }
+        for scope in &node_scopes[common..] {
+            let indent = "  ".repeat(scopes.len() + 1);
+            writeln!(out, "{}subgraph cluster{} {{", indent, clusters)?;
+            writeln!(out, "{}  label=\"{}\";", indent, escape(scope))?;
+            clusters += 1;
+            scopes.push(scope);
+        }
+        writeln!(out, "{}{}", "  ".repeat(scopes.len() + 1), node)?;
+    }
+    while !scopes.is_empty() {
+        scopes.pop();
+        writeln!(out, "{}}}", "  ".repeat(scopes.len() + 1))?;
+    }
+    for edge in edges {
+        writeln!(out, "  {}", edge)?;
+    }
+    writeln!(out, "}}")?;
+    return Ok(());
+}
+
+fn get_color(value: BusValue) -> &'static str {
+    let bits: Vec<LogicValue> = (0..value.get_width())
+        .map(|bit| value.get_bit(bit))
+        .collect();
+    match bits[..] {
+        [LogicValue::Zero] => return "darkgreen",
+        [LogicValue::One] => return "green",
+        _ if bits.contains(&LogicValue::X) => return "red",
+        _ if bits.iter().all(|bit| *bit == LogicValue::Z) => return "blue",
+        _ => return "black",
+    }
+}
+
+/// Escapes a quoted string.
+fn escape(text: &str) -> String {
+    return text.replace('\\', "\\\\").replace('"', "\\\"");
+}
+
+/// Escapes a quoted record label field.
+fn escape_record(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        if "{}|<> \"\\".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    return escaped;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Circuit;
+    use crate::DeviceRegistry;
+    use crate::DotOptions;
+    use crate::Netlist;
+
+    const NETLIST: &str = "
+module half_adder
+  device a test_probe direction=input
+  device b test_probe direction=input
+  device s test_probe direction=inout
+  device x xor
+  device c and
+  net a a.pin x.A c.A
+  net b b.pin x.B c.B
+  net s s.pin x.Y
+  port a a.pin
+  port b b.pin
+  port s s.pin
+end
+
+device in test_probe direction=output width=2 value=01
+device out test_probe
+device ha half_adder
+device inv not
+net in width=2 in.pin ha.a ha.b@1
+net sum ha.s inv.A
+net inverted inv.Y out.pin
+";
+
+    fn write(circuit: &Circuit, options: &DotOptions) -> String {
+        let mut dot: Vec<u8> = Vec::new();
+        circuit.write_dot(&mut dot, options).unwrap();
+        return String::from_utf8(dot).unwrap();
+    }
+
+    #[test]
+    fn writes_graph() {
+        let (devices, nets) = Netlist::parse(NETLIST)
+            .unwrap()
+            .build(&DeviceRegistry::new())
+            .unwrap();
+        let mut circuit = Circuit::new(devices, nets).unwrap();
+        circuit.run_until(10).unwrap();
+        assert_eq!(
+            "digraph circuit {\n\
+             \x20 rankdir=LR;\n\
+             \x20 node [shape=record];\n\
+             \x20 d0 [label=\"in|{<p1> pin}\"];\n\
+             \x20 d1 [label=\"{<p1> pin}|out\"];\n\
+             \x20 d3 [label=\"{<p1> A}|inv|{<p2> Y}\"];\n\
+             \x20 d4 [label=\"{<p1> pin}|ha.a\"];\n\
+             \x20 d5 [label=\"{<p1> pin}|ha.b\"];\n\
+             \x20 d6 [label=\"ha.s|{<p1> pin}\"];\n\
+             \x20 d7 [label=\"{<p1> A|<p2> B}|ha.x|{<p3> Y}\"];\n\
+             \x20 d8 [label=\"{<p1> A|<p2> B}|ha.c|{<p3> Y}\"];\n\
+             \x20 n0 [shape=point, xlabel=\"in\"];\n\
+             \x20 n1 [shape=point, xlabel=\"sum\"];\n\
+             \x20 d0:p1 -> n0;\n\
+             \x20 n0 -> d4:p1;\n\
+             \x20 n0 -> d7:p1;\n\
+             \x20 n0 -> d8:p1;\n\
+             \x20 n0 -> d5:p1;\n\
+             \x20 n0 -> d7:p2;\n\
+             \x20 n0 -> d8:p2;\n\
+             \x20 d6:p1 -> n1 [dir=both];\n\
+             \x20 d7:p3 -> n1;\n\
+             \x20 n1 -> d3:p1;\n\
+             \x20 d3:p2 -> d1:p1 [label=\"inverted\", dir=forward];\n\
+             }\n",
+            write(&circuit, &DotOptions::new())
+        );
+
+        let mut options = DotOptions::new();
+        options.set_clusters(true);
+        options.set_values(true);
+        let dot = write(&circuit, &options);
+        assert!(dot.contains(
+            "\n  subgraph cluster0 {\n    label=\"ha\";\n    d4 [label=\"{<p1> pin}|a\"];\n"
+        ));
+        assert!(dot.contains("\n  n1 [shape=point, xlabel=\"sum = 1\", color=\"green\"];\n"));
+        assert!(dot.contains(
+            "\n  d3:p2 -> d1:p1 [label=\"inverted = 0\", dir=forward, color=\"darkgreen\"];\n"
+        ));
+    }
+}
@@ -0,0 +1,17 @@
+/// Selects how a `Circuit` runs its devices.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Engine {
+    /// Runs every device on its own thread and exchanges messages over channels.
+    /// Every device is notified of every tick.
+    Threaded,
+    /// Runs every device on the calling thread and keeps a time-ordered event
+    /// queue, so only devices with a pending wake-up, input change or data
+    /// message are called on a given tick.
+    EventDriven,
+}
+
+impl Default for Engine {
+    fn default() -> Engine {
+        return Engine::Threaded;
+    }
+}
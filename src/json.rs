@@ -0,0 +1,302 @@
+use crate::NetlistParseError;
+
+/// How deep arrays and objects may be nested, so that deeply nested text is
+/// an error rather than a stack overflow.
+const MAX_DEPTH: usize = 128;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum JsonValue {
+    Null,
+    Bool(bool),
+    // kept as text so large integers are not rounded
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    // in the order of the text
+    Object(Vec<(String, Json)>),
+}
+
+/// A JSON value and the line it starts on, for the importers of JSON netlists.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Json {
+    value: JsonValue,
+    line: usize,
+}
+
+impl Json {
+    pub(crate) fn parse(text: &str) -> Result<Json, NetlistParseError> {
+        let mut parser = JsonParser {
+            chars: text.chars().collect(),
+            position: 0,
+            line: 1,
+            depth: 0,
+        };
+        let json = parser.parse_value()?;
+        parser.skip_white_space();
+        if parser.position < parser.chars.len() {
+            return Err(parser.error("expected the end of the text"));
+        }
+        return Ok(json);
+    }
+
+    pub(crate) fn get_line(&self) -> usize {
+        return self.line;
+    }
+
+    /// Gets member `key` of an object.
+    pub(crate) fn get(&self, key: &str) -> Option<&Json> {
+        return self
+            .as_object()?
+            .iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value);
+    }
+
+    pub(crate) fn as_str(&self) -> Option<&str> {
+        match &self.value {
+            JsonValue::String(text) => return Some(text),
+            _ => return None,
+        }
+    }
+
+    pub(crate) fn as_u64(&self) -> Option<u64> {
+        match &self.value {
+            JsonValue::Number(text) => return text.parse().ok(),
+            _ => return None,
+        }
+    }
+
+    pub(crate) fn as_array(&self) -> Option<&[Json]> {
+        match &self.value {
+            JsonValue::Array(values) => return Some(values),
+            _ => return None,
+        }
+    }
+
+    pub(crate) fn as_object(&self) -> Option<&[(String, Json)]> {
+        match &self.value {
+            JsonValue::Object(members) => return Some(members),
+            _ => return None,
+        }
+    }
+}
+
+struct JsonParser {
+    chars: Vec<char>,
+    position: usize,
+    line: usize,
+    // the arrays and objects the parser is in
+    depth: usize,
+}
+
+impl JsonParser {
+    fn error(&self, message: &str) -> NetlistParseError {
+        return NetlistParseError::new(self.line, message);
+    }
+
+    fn peek(&self) -> Option<char> {
+        return self.chars.get(self.position).copied();
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += 1;
+        if c == '\n' {
+            self.line += 1;
+        }
+        return Some(c);
+    }
+
+    fn skip_white_space(&mut self) {
+        while self.peek().is_some_and(|c| c.is_whitespace()) {
+            self.next();
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), NetlistParseError> {
+        self.skip_white_space();
+        match self.next() {
+            Some(c) if c == expected => return Ok(()),
+            Some(c) => return Err(self.error(&format!("expected {}, got {}", expected, c))),
+            None => {
+                return Err(self.error(&format!("expected {}, got the end of the text", expected)))
+            }
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<Json, NetlistParseError> {
+        self.skip_white_space();
+        let line = self.line;
+        let value = match self.peek() {
+            Some('{') => {
+                self.enter()?;
+                let mut members: Vec<(String, Json)> = Vec::new();
+                self.skip_white_space();
+                if self.peek() == Some('}') {
+                    self.next();
+                } else {
+                    loop {
+                        self.skip_white_space();
+                        let key = self.parse_string()?;
+                        self.expect(':')?;
+                        members.push((key, self.parse_value()?));
+                        if !self.parse_separator('}')? {
+                            break;
+                        }
+                    }
+                }
+                self.depth -= 1;
+                JsonValue::Object(members)
+            }
+            Some('[') => {
+                self.enter()?;
+                let mut values: Vec<Json> = Vec::new();
+                self.skip_white_space();
+                if self.peek() == Some(']') {
+                    self.next();
+                } else {
+                    loop {
+                        values.push(self.parse_value()?);
+                        if !self.parse_separator(']')? {
+                            break;
+                        }
+                    }
+                }
+                self.depth -= 1;
+                JsonValue::Array(values)
+            }
+            Some('"') => JsonValue::String(self.parse_string()?),
+            Some(c) if c == '-' || c.is_ascii_digit() => {
+                let start = self.position;
+                while self
+                    .peek()
+                    .is_some_and(|c| c.is_ascii_digit() || "+-.eE".contains(c))
+                {
+                    self.next();
+                }
+                let text: String = self.chars[start..self.position].iter().collect();
+                if text.parse::<f64>().is_err() {
+                    return Err(self.error(&format!("invalid number {}", text)));
+                }
+                JsonValue::Number(text)
+            }
+            Some(c) if c.is_ascii_alphabetic() => {
+                let start = self.position;
+                while self.peek().is_some_and(|c| c.is_ascii_alphabetic()) {
+                    self.next();
+                }
+                let word: String = self.chars[start..self.position].iter().collect();
+                match word.as_str() {
+                    "null" => JsonValue::Null,
+                    "true" => JsonValue::Bool(true),
+                    "false" => JsonValue::Bool(false),
+                    _ => return Err(self.error(&format!("unexpected {}", word))),
+                }
+            }
+            Some(c) => return Err(self.error(&format!("unexpected {}", c))),
+            None => return Err(self.error("unexpected end of the text")),
+        };
+        return Ok(Json { value, line });
+    }
+
+    /// Reads the `{` or `[` of an object or array.
+    fn enter(&mut self) -> Result<(), NetlistParseError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(self.error(&format!("values are nested more than {} deep", MAX_DEPTH)));
+        }
+        self.next();
+        return Ok(());
+    }
+
+    /// Reads a `,` or the `end` of an array or object, returns `true` if more
+    /// values follow.
+    fn parse_separator(&mut self, end: char) -> Result<bool, NetlistParseError> {
+        self.skip_white_space();
+        match self.next() {
+            Some(',') => return Ok(true),
+            Some(c) if c == end => return Ok(false),
+            Some(c) => return Err(self.error(&format!("expected , or {}, got {}", end, c))),
+            None => {
+                return Err(self.error(&format!("expected , or {}, got the end of the text", end)))
+            }
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String, NetlistParseError> {
+        self.expect('"')?;
+        let mut text = String::new();
+        loop {
+            match self.next() {
+                Some('"') => return Ok(text),
+                Some('\\') => {
+                    let c = match self.next() {
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        Some('r') => '\r',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('u') => {
+                            let code: String = (0..4).filter_map(|_| self.next()).collect();
+                            u32::from_str_radix(&code, 16)
+                                .ok()
+                                .and_then(char::from_u32)
+                                .ok_or_else(|| self.error(&format!("invalid escape \\u{}", code)))?
+                        }
+                        Some(c) => c,
+                        None => return Err(self.error("unterminated string")),
+                    };
+                    text.push(c);
+                }
+                Some(c) => text.push(c),
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::json::Json;
+    use crate::json::JsonValue;
+    use crate::NetlistParseError;
+
+    #[test]
+    fn parses_values() {
+        let json = Json::parse(
+            "{\n  \"a\": [1, -2.5e3, \"x\\\"y\"],\n  \"b\": {\"c\": null, \"d\": true}\n}",
+        )
+        .unwrap();
+        let a = json.get("a").unwrap();
+        assert_eq!(2, a.get_line());
+        assert_eq!(Some(1), a.as_array().unwrap()[0].as_u64());
+        assert_eq!(
+            JsonValue::Number("-2.5e3".to_string()),
+            a.as_array().unwrap()[1].value
+        );
+        assert_eq!(Some("x\"y"), a.as_array().unwrap()[2].as_str());
+        let b = json.get("b").unwrap();
+        assert_eq!(JsonValue::Null, b.get("c").unwrap().value);
+        assert_eq!(JsonValue::Bool(true), b.get("d").unwrap().value);
+        assert_eq!(None, json.get("e"));
+    }
+
+    #[test]
+    fn reports_lines() {
+        assert_eq!(
+            Err(NetlistParseError::new(3, "expected , or }, got ]")),
+            Json::parse("{\n  \"a\": 1,\n  \"b\": 2]")
+        );
+        assert_eq!(
+            Err(NetlistParseError::new(1, "unterminated string")),
+            Json::parse("[\"a")
+        );
+        assert_eq!(
+            Err(NetlistParseError::new(
+                1,
+                "values are nested more than 128 deep"
+            )),
+            Json::parse(&"[".repeat(200_000))
+        );
+    }
+}
@@ -1,18 +1,104 @@
+#![allow(clippy::needless_return)]
+
 pub mod device;
 
 mod circuit;
 pub use circuit::Circuit;
 
+mod circuit_builder;
+pub use circuit_builder::CircuitBuilder;
+
+mod delay;
+pub use delay::Delay;
+pub use delay::DelayMode;
+
+mod device_handle;
+pub use device_handle::DeviceHandle;
+pub use device_handle::DeviceRef;
+
+mod engine;
+pub use engine::Engine;
+
 mod net;
 pub use net::Net;
 pub use net::NetConnection;
+pub use net::NetPull;
+pub use net::NetResolution;
+
+mod netlist_error;
+pub use netlist_error::NetlistError;
+
+mod netlist_validation;
+
+mod netlist;
+pub use netlist::Netlist;
+pub use netlist::NetlistDevice;
+
+mod netlist_assembler;
+use netlist_assembler::NetlistAssembler;
+
+mod verilog;
+
+mod verilog_writer;
+
+mod verilog_export_error;
+pub use verilog_export_error::VerilogExportError;
+
+mod yosys_json;
+
+mod blif;
+
+mod json;
+
+mod netlist_parse_error;
+pub use netlist_parse_error::NetlistParseError;
+
+mod device_registry;
+pub use device_registry::DeviceConstructor;
+pub use device_registry::DeviceParams;
+pub use device_registry::DeviceRegistry;
 
 mod pin_direction;
 pub use pin_direction::PinDirection;
 
+mod logic_value;
+pub use logic_value::LogicValue;
+
+mod bus_value;
+pub use bus_value::BusValue;
+
+mod pin_info;
+pub use pin_info::PinInfo;
+
+mod pin_value;
+pub use pin_value::PinValue;
+
+mod run_summary;
+pub use run_summary::RunSummary;
+
+mod sim_error;
+pub use sim_error::SimError;
+
+mod sim_event;
+pub use sim_event::SimEvent;
+
+mod timing_check;
+pub use timing_check::Edge;
+pub use timing_check::TimingCheck;
+pub use timing_check::TimingCheckKind;
+pub use timing_check::TimingChecker;
+pub use timing_check::TimingViolation;
+
+mod dot;
+pub use dot::DotOptions;
+
+mod waveform;
+pub use waveform::Waveform;
+pub use waveform::WaveformChange;
+
 mod message;
-pub use message::DeviceToCircuitMessage;
 pub use message::CircuitToDeviceMessage;
+pub use message::DeviceToCircuitMessage;
 
 mod device_data;
 pub use device_data::DeviceData;
@@ -0,0 +1,128 @@
+use std::fmt;
+use std::ops::BitAnd;
+use std::ops::BitOr;
+use std::ops::BitXor;
+use std::ops::Not;
+
+/// Four-state value of a single bit on a pin or net.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum LogicValue {
+    /// Driven low.
+    Zero,
+    /// Driven high.
+    One,
+    /// Unknown, e.g. an uninitialized flip-flop or conflicting drivers.
+    X,
+    /// High impedance, nothing is driving the net.
+    Z,
+}
+
+impl LogicValue {
+    pub fn from_bool(value: bool) -> LogicValue {
+        if value {
+            return LogicValue::One;
+        }
+        return LogicValue::Zero;
+    }
+
+    /// Gets the value as a bool, or `None` for X and Z.
+    pub fn to_bool(self) -> Option<bool> {
+        match self {
+            LogicValue::Zero => Some(false),
+            LogicValue::One => Some(true),
+            LogicValue::X | LogicValue::Z => None,
+        }
+    }
+
+    /// True for 0 and 1.
+    pub fn is_known(self) -> bool {
+        return self.to_bool().is_some();
+    }
+
+    /// Gets the value as seen by a gate input, a floating input reads as X.
+    pub fn as_input(self) -> LogicValue {
+        if self == LogicValue::Z {
+            return LogicValue::X;
+        }
+        return self;
+    }
+
+    pub fn to_char(self) -> char {
+        match self {
+            LogicValue::Zero => '0',
+            LogicValue::One => '1',
+            LogicValue::X => 'x',
+            LogicValue::Z => 'z',
+        }
+    }
+
+    pub fn from_char(c: char) -> Option<LogicValue> {
+        match c {
+            '0' => Some(LogicValue::Zero),
+            '1' => Some(LogicValue::One),
+            'x' | 'X' => Some(LogicValue::X),
+            'z' | 'Z' => Some(LogicValue::Z),
+            _ => None,
+        }
+    }
+}
+
+impl From<bool> for LogicValue {
+    fn from(value: bool) -> LogicValue {
+        return LogicValue::from_bool(value);
+    }
+}
+
+impl fmt::Display for LogicValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_char())
+    }
+}
+
+impl BitAnd for LogicValue {
+    type Output = LogicValue;
+
+    /// A known 0 on either side wins, otherwise any X or Z gives X.
+    fn bitand(self, rhs: LogicValue) -> LogicValue {
+        match (self.to_bool(), rhs.to_bool()) {
+            (Some(false), _) | (_, Some(false)) => LogicValue::Zero,
+            (Some(true), Some(true)) => LogicValue::One,
+            _ => LogicValue::X,
+        }
+    }
+}
+
+impl BitOr for LogicValue {
+    type Output = LogicValue;
+
+    /// A known 1 on either side wins, otherwise any X or Z gives X.
+    fn bitor(self, rhs: LogicValue) -> LogicValue {
+        match (self.to_bool(), rhs.to_bool()) {
+            (Some(true), _) | (_, Some(true)) => LogicValue::One,
+            (Some(false), Some(false)) => LogicValue::Zero,
+            _ => LogicValue::X,
+        }
+    }
+}
+
+impl BitXor for LogicValue {
+    type Output = LogicValue;
+
+    fn bitxor(self, rhs: LogicValue) -> LogicValue {
+        match (self.to_bool(), rhs.to_bool()) {
+            (Some(a), Some(b)) => LogicValue::from_bool(a != b),
+            _ => LogicValue::X,
+        }
+    }
+}
+
+impl Not for LogicValue {
+    type Output = LogicValue;
+
+    fn not(self) -> LogicValue {
+        match self.to_bool() {
+            Some(value) => LogicValue::from_bool(!value),
+            None => LogicValue::X,
+        }
+    }
+}
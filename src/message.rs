@@ -1,16 +1,26 @@
+use crate::BusValue;
 use crate::DeviceData;
 use crate::PinDirection;
+use crate::TimingViolation;
 
 #[derive(Debug)]
 pub enum DeviceToCircuitMessage {
     NextTick {
         tick: u64,
     },
+    /// With `Output` direction the device drives `value` onto the pin's net,
+    /// with `Input` direction it stops driving and receives the net's value.
+    /// `InOut` is treated as `Output`, driving an all-Z value keeps the pin
+    /// on the net without taking part in contention.
     SetPin {
         pin: usize,
-        value: u32,
+        value: BusValue,
         direction: PinDirection,
     },
+    /// A timing check of the device failed on the current tick.
+    TimingViolation {
+        violation: TimingViolation,
+    },
     Data {
         data: Box<dyn DeviceData>,
     },
@@ -21,13 +31,17 @@ pub enum CircuitToDeviceMessage {
     Data {
         data: Box<dyn DeviceData>,
     },
+    /// Like `Data` but the device must reply with a `DeviceToCircuitMessage::Data`.
+    DataRequest {
+        data: Box<dyn DeviceData>,
+    },
     NextTick {
         tick: u64,
     },
     SetPin {
         tick: u64,
         pin: usize,
-        value: u32,
+        value: BusValue,
         last: bool,
     },
     Terminate,
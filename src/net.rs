@@ -1,14 +1,82 @@
+use crate::BusValue;
+use crate::LogicValue;
 use core::slice::Iter;
+use core::slice::IterMut;
 
-#[derive(Debug)]
+/// Attaches bits of a device pin to bits of a net. By default the whole pin is
+/// attached starting at bit 0 of the net.
+#[derive(Debug, Clone)]
 pub struct NetConnection {
     device: usize,
     pin: usize,
+    // "device.pin" name, resolved to a device and pin when the circuit is built
+    pin_name: Option<String>,
+    pin_lsb: usize,
+    net_lsb: usize,
+    width: Option<usize>,
 }
 
 impl NetConnection {
     pub fn new(device: usize, pin: usize) -> NetConnection {
-        return NetConnection { device, pin };
+        return NetConnection::at(device, pin, 0);
+    }
+
+    /// Attaches a pin by name, e.g. `"and1.A"`. The device is found by name and
+    /// the pin by `Device::get_pin_by_name` when the circuit is built.
+    pub fn named(pin_name: &str) -> NetConnection {
+        return NetConnection::named_at(pin_name, 0);
+    }
+
+    /// Like `named` but attaches the pin to the net bits starting at `net_lsb`.
+    pub fn named_at(pin_name: &str, net_lsb: usize) -> NetConnection {
+        let mut connection = NetConnection::at(usize::MAX, 0, net_lsb);
+        connection.pin_name = Some(pin_name.to_string());
+        return connection;
+    }
+
+    /// Like `slice` but attaches the pin by name, see `named`.
+    pub fn named_slice(
+        pin_name: &str,
+        pin_lsb: usize,
+        net_lsb: usize,
+        width: usize,
+    ) -> NetConnection {
+        let mut connection = NetConnection::slice(usize::MAX, 0, pin_lsb, net_lsb, width);
+        connection.pin_name = Some(pin_name.to_string());
+        return connection;
+    }
+
+    /// Attaches the whole pin to the net bits starting at `net_lsb`, e.g. an 8
+    /// bit port on the upper half of a 16 bit bus.
+    pub fn at(device: usize, pin: usize, net_lsb: usize) -> NetConnection {
+        return NetConnection {
+            device,
+            pin,
+            pin_name: None,
+            pin_lsb: 0,
+            net_lsb,
+            width: None,
+        };
+    }
+
+    /// Attaches `width` pin bits starting at `pin_lsb` to the net bits starting
+    /// at `net_lsb`. Connecting slices of one wide pin to several narrow nets
+    /// concatenates them.
+    pub fn slice(
+        device: usize,
+        pin: usize,
+        pin_lsb: usize,
+        net_lsb: usize,
+        width: usize,
+    ) -> NetConnection {
+        return NetConnection {
+            device,
+            pin,
+            pin_name: None,
+            pin_lsb,
+            net_lsb,
+            width: Some(width),
+        };
     }
 
     pub fn get_device(&self) -> usize {
@@ -18,19 +86,476 @@ impl NetConnection {
     pub fn get_pin(&self) -> usize {
         return self.pin;
     }
+
+    /// Gets the `"device.pin"` name the connection was made with, if any.
+    pub fn get_pin_name(&self) -> Option<&str> {
+        return self.pin_name.as_deref();
+    }
+
+    /// True once the connection refers to a device and pin by number.
+    pub(crate) fn is_resolved(&self) -> bool {
+        return self.device != usize::MAX;
+    }
+
+    pub(crate) fn resolve(&mut self, device: usize, pin: usize) {
+        self.device = device;
+        self.pin = pin;
+    }
+
+    pub(crate) fn set_pin_name(&mut self, pin_name: String) {
+        self.pin_name = Some(pin_name);
+    }
+
+    /// Moves the connection `bits` bits up the net.
+    pub(crate) fn shift(&mut self, bits: usize) {
+        self.net_lsb += bits;
+    }
+
+    pub fn get_pin_lsb(&self) -> usize {
+        return self.pin_lsb;
+    }
+
+    pub fn get_net_lsb(&self) -> usize {
+        return self.net_lsb;
+    }
+
+    /// Gets the number of bits connected, `None` means the rest of the pin.
+    pub fn get_width(&self) -> Option<usize> {
+        return self.width;
+    }
+}
+
+/// How a net combines the values of several drivers.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum NetResolution {
+    /// Z loses to any driven value, two drivers disagreeing give X and a
+    /// bus contention event.
+    TriState,
+    /// Any driver pulling low wins, like open-drain outputs with a pull-up.
+    WiredAnd,
+    /// Any driver pulling high wins.
+    WiredOr,
+}
+
+impl NetResolution {
+    /// Resolves the values of every driver on a `width` bit net, bit by bit.
+    /// Drivers driving Z are ignored, if none are left the bit takes the value
+    /// of the net's pull.
+    pub fn resolve(self, width: usize, drivers: &[BusValue], pull: NetPull) -> BusValue {
+        let mut result = BusValue::z(width);
+        let mut bits: Vec<LogicValue> = Vec::with_capacity(drivers.len());
+        for bit in 0..width {
+            bits.clear();
+            bits.extend(drivers.iter().map(|driver| driver.get_bit(bit)));
+            result.set_bit(bit, self.resolve_bit(&bits, pull));
+        }
+        return result;
+    }
+
+    /// True if two drivers fight over any bit of the net, only possible on
+    /// tri-state nets.
+    pub fn is_contention(self, width: usize, drivers: &[BusValue]) -> bool {
+        if self != NetResolution::TriState {
+            return false;
+        }
+        for bit in 0..width {
+            let mut driven = drivers
+                .iter()
+                .map(|driver| driver.get_bit(bit))
+                .filter(|value| *value != LogicValue::Z);
+            if let Some(first) = driven.next() {
+                if driven.any(|value| value != first) {
+                    return true;
+                }
+            }
+        }
+        return false;
+    }
+
+    fn resolve_bit(self, drivers: &[LogicValue], pull: NetPull) -> LogicValue {
+        let mut result: Option<LogicValue> = None;
+        for driver in drivers.iter().filter(|driver| **driver != LogicValue::Z) {
+            result = Some(match result {
+                None => *driver,
+                Some(value) => match self {
+                    NetResolution::TriState => {
+                        if value == *driver {
+                            value
+                        } else {
+                            LogicValue::X
+                        }
+                    }
+                    NetResolution::WiredAnd => value & *driver,
+                    NetResolution::WiredOr => value | *driver,
+                },
+            });
+        }
+        return result.unwrap_or_else(|| pull.get_value());
+    }
+}
+
+impl Default for NetResolution {
+    fn default() -> NetResolution {
+        return NetResolution::TriState;
+    }
+}
+
+/// Value a net takes when nothing drives it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum NetPull {
+    /// The net floats at Z.
+    None,
+    Up,
+    Down,
+}
+
+impl NetPull {
+    pub fn get_value(self) -> LogicValue {
+        match self {
+            NetPull::None => LogicValue::Z,
+            NetPull::Up => LogicValue::One,
+            NetPull::Down => LogicValue::Zero,
+        }
+    }
+}
+
+impl Default for NetPull {
+    fn default() -> NetPull {
+        return NetPull::None;
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Net {
+    name: Option<String>,
+    width: Option<usize>,
     connections: Vec<NetConnection>,
+    resolution: NetResolution,
+    pull: NetPull,
+    delay: u64,
 }
 
 impl Net {
     pub fn new(connections: Vec<NetConnection>) -> Net {
-        return Net { connections };
+        return Net {
+            name: None,
+            width: None,
+            connections,
+            resolution: NetResolution::default(),
+            pull: NetPull::default(),
+            delay: 0,
+        };
+    }
+
+    pub fn named(name: &str, connections: Vec<NetConnection>) -> Net {
+        let mut net = Net::new(connections);
+        net.set_name(name);
+        return net;
     }
 
-    pub fn connections_iter(&self) -> Iter<NetConnection> {
+    /// Creates a `width` bit net, a plain `Net::new` is as wide as the widest
+    /// connection.
+    pub fn bus(width: usize, connections: Vec<NetConnection>) -> Net {
+        let mut net = Net::new(connections);
+        net.width = Some(width);
+        return net;
+    }
+
+    pub fn connections_iter(&self) -> Iter<'_, NetConnection> {
         return self.connections.iter();
     }
+
+    pub(crate) fn connections_iter_mut(&mut self) -> IterMut<'_, NetConnection> {
+        return self.connections.iter_mut();
+    }
+
+    pub(crate) fn take_connections(&mut self) -> Vec<NetConnection> {
+        return std::mem::take(&mut self.connections);
+    }
+
+    pub(crate) fn add_connection(&mut self, connection: NetConnection) {
+        self.connections.push(connection);
+    }
+
+    /// Gets the name of the net, unnamed nets are called `net<index>` by the circuit.
+    pub fn get_name(&self) -> Option<&str> {
+        return self.name.as_deref();
+    }
+
+    /// Gets the name of the net, `net<index>` if it was not given one.
+    pub(crate) fn get_display_name(&self, net_index: usize) -> String {
+        match &self.name {
+            Some(name) => name.clone(),
+            None => format!("net{}", net_index),
+        }
+    }
+
+    pub fn set_name(&mut self, name: &str) {
+        self.name = Some(name.to_string());
+    }
+
+    /// Gets the width given to `Net::bus`, `None` if it is taken from the connections.
+    pub fn get_width(&self) -> Option<usize> {
+        return self.width;
+    }
+
+    pub fn get_resolution(&self) -> NetResolution {
+        return self.resolution;
+    }
+
+    pub fn set_resolution(&mut self, resolution: NetResolution) {
+        self.resolution = resolution;
+    }
+
+    pub fn get_pull(&self) -> NetPull {
+        return self.pull;
+    }
+
+    pub fn set_pull(&mut self, pull: NetPull) {
+        self.pull = pull;
+    }
+
+    pub fn get_delay(&self) -> u64 {
+        return self.delay;
+    }
+
+    /// Sets the wire delay, added to the delay of every value driven onto the
+    /// net.
+    pub fn set_delay(&mut self, delay: u64) {
+        self.delay = delay;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::device::Device;
+    use crate::device::TestProbe;
+    use crate::BusValue;
+    use crate::Circuit;
+    use crate::Engine;
+    use crate::LogicValue;
+    use crate::Net;
+    use crate::NetConnection;
+    use crate::NetPull;
+    use crate::NetResolution;
+    use crate::PinDirection;
+    use crate::SimError;
+    use crate::SimEvent;
+    use std::cell::RefCell;
+
+    const DEVICE_DRIVER1: usize = 0;
+    const DEVICE_DRIVER2: usize = 1;
+    const DEVICE_OUTPUT: usize = 2;
+
+    fn create_circuit(resolution: NetResolution, pull: NetPull) -> Circuit {
+        let devices: Vec<RefCell<Box<dyn Device>>> = vec![
+            RefCell::new(Box::new(TestProbe::new(
+                "driver1",
+                LogicValue::Z,
+                PinDirection::Output,
+            ))),
+            RefCell::new(Box::new(TestProbe::new(
+                "driver2",
+                LogicValue::Z,
+                PinDirection::Output,
+            ))),
+            RefCell::new(Box::new(TestProbe::new(
+                "output",
+                LogicValue::Z,
+                PinDirection::Input,
+            ))),
+        ];
+        let mut net = Net::named(
+            "bus",
+            vec![
+                NetConnection::new(DEVICE_DRIVER1, TestProbe::PIN),
+                NetConnection::new(DEVICE_DRIVER2, TestProbe::PIN),
+                NetConnection::new(DEVICE_OUTPUT, TestProbe::PIN),
+            ],
+        );
+        net.set_resolution(resolution);
+        net.set_pull(pull);
+        return Circuit::with_engine(devices, vec![net], Engine::EventDriven).unwrap();
+    }
+
+    #[test]
+    fn tri_state() {
+        let mut circuit = create_circuit(NetResolution::TriState, NetPull::None);
+        circuit.tick(1).unwrap();
+        assert_eq!(
+            LogicValue::Z,
+            TestProbe::get_value(&circuit, DEVICE_OUTPUT).unwrap()
+        );
+
+        TestProbe::set_output_high(&circuit, DEVICE_DRIVER1).unwrap();
+        circuit.tick(2).unwrap();
+        assert_eq!(
+            LogicValue::One,
+            TestProbe::get_value(&circuit, DEVICE_OUTPUT).unwrap()
+        );
+
+        TestProbe::set_output_low(&circuit, DEVICE_DRIVER2).unwrap();
+        circuit.tick(3).unwrap();
+        assert_eq!(
+            LogicValue::X,
+            TestProbe::get_value(&circuit, DEVICE_OUTPUT).unwrap()
+        );
+        assert_eq!(
+            vec![SimEvent::BusContention {
+                tick: 3,
+                net: "bus".to_string(),
+                devices: vec!["driver1".to_string(), "driver2".to_string()],
+            }],
+            circuit.take_events()
+        );
+
+        TestProbe::set_output(&circuit, DEVICE_DRIVER1, LogicValue::Z).unwrap();
+        circuit.tick(4).unwrap();
+        assert_eq!(
+            LogicValue::Zero,
+            TestProbe::get_value(&circuit, DEVICE_OUTPUT).unwrap()
+        );
+        assert!(circuit.take_events().is_empty());
+    }
+
+    #[test]
+    fn wired_and_with_pull_up() {
+        let mut circuit = create_circuit(NetResolution::WiredAnd, NetPull::Up);
+        circuit.tick(1).unwrap();
+        assert_eq!(
+            LogicValue::One,
+            TestProbe::get_value(&circuit, DEVICE_OUTPUT).unwrap()
+        );
+
+        TestProbe::set_output_low(&circuit, DEVICE_DRIVER1).unwrap();
+        TestProbe::set_output_high(&circuit, DEVICE_DRIVER2).unwrap();
+        circuit.tick(2).unwrap();
+        assert_eq!(
+            LogicValue::Zero,
+            TestProbe::get_value(&circuit, DEVICE_OUTPUT).unwrap()
+        );
+        assert!(circuit.take_events().is_empty());
+    }
+
+    #[test]
+    fn bus_slices() {
+        const DEVICE_BUS: usize = 0;
+        const DEVICE_LOW: usize = 1;
+        const DEVICE_HIGH: usize = 2;
+        const DEVICE_JOINED: usize = 3;
+        const DEVICE_SOURCE: usize = 4;
+        let devices: Vec<RefCell<Box<dyn Device>>> = vec![
+            RefCell::new(Box::new(TestProbe::new_bus(
+                "bus",
+                BusValue::from_u64(16, 0x12ab),
+                PinDirection::Output,
+            ))),
+            RefCell::new(Box::new(TestProbe::new_bus(
+                "low",
+                BusValue::z(8),
+                PinDirection::Input,
+            ))),
+            RefCell::new(Box::new(TestProbe::new_bus(
+                "high",
+                BusValue::z(8),
+                PinDirection::Input,
+            ))),
+            RefCell::new(Box::new(TestProbe::new_bus(
+                "joined",
+                BusValue::z(16),
+                PinDirection::Input,
+            ))),
+            RefCell::new(Box::new(TestProbe::new_bus(
+                "source",
+                BusValue::from_u64(16, 0x12ab),
+                PinDirection::Output,
+            ))),
+        ];
+        // a 16 bit bus feeding two 8 bit ports
+        let data = Net::bus(
+            16,
+            vec![
+                NetConnection::new(DEVICE_BUS, TestProbe::PIN),
+                NetConnection::at(DEVICE_LOW, TestProbe::PIN, 0),
+                NetConnection::at(DEVICE_HIGH, TestProbe::PIN, 8),
+            ],
+        );
+        // the two halves of a second port concatenated into a 16 bit port, swapped
+        let low = Net::new(vec![
+            NetConnection::slice(DEVICE_SOURCE, TestProbe::PIN, 0, 0, 8),
+            NetConnection::slice(DEVICE_JOINED, TestProbe::PIN, 8, 0, 8),
+        ]);
+        let high = Net::new(vec![
+            NetConnection::slice(DEVICE_SOURCE, TestProbe::PIN, 8, 0, 8),
+            NetConnection::slice(DEVICE_JOINED, TestProbe::PIN, 0, 0, 8),
+        ]);
+        let mut circuit =
+            Circuit::with_engine(devices, vec![data, low, high], Engine::EventDriven).unwrap();
+        circuit.tick(1).unwrap();
+        assert_eq!(
+            BusValue::from_u64(8, 0xab),
+            TestProbe::get_bus_value(&circuit, DEVICE_LOW).unwrap()
+        );
+        assert_eq!(
+            BusValue::from_u64(8, 0x12),
+            TestProbe::get_bus_value(&circuit, DEVICE_HIGH).unwrap()
+        );
+        assert_eq!(
+            BusValue::from_u64(16, 0xab12),
+            TestProbe::get_bus_value(&circuit, DEVICE_JOINED).unwrap()
+        );
+
+        let value = BusValue::parse("zzzz_xxxx_0000_1111").unwrap();
+        TestProbe::set_output_bus(&circuit, DEVICE_BUS, value).unwrap();
+        TestProbe::set_output_bus(&circuit, DEVICE_SOURCE, value).unwrap();
+        circuit.tick(2).unwrap();
+        assert_eq!(
+            "zzzzxxxx",
+            TestProbe::get_bus_value(&circuit, DEVICE_HIGH)
+                .unwrap()
+                .to_string()
+        );
+        assert_eq!(
+            "00001111zzzzxxxx",
+            TestProbe::get_bus_value(&circuit, DEVICE_JOINED)
+                .unwrap()
+                .to_string()
+        );
+
+        // a value of the wrong width is refused and the probe keeps driving
+        assert_eq!(
+            Err(SimError::PinWidthMismatch {
+                device: "bus".to_string(),
+                pin: "pin".to_string(),
+                expected: 16,
+                actual: 8,
+            }),
+            TestProbe::set_output_bus(&circuit, DEVICE_BUS, BusValue::from_u64(8, 0))
+        );
+        circuit.tick(3).unwrap();
+        assert_eq!(
+            "zzzzxxxx",
+            TestProbe::get_bus_value(&circuit, DEVICE_HIGH)
+                .unwrap()
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn wired_or_with_pull_down() {
+        let mut circuit = create_circuit(NetResolution::WiredOr, NetPull::Down);
+        circuit.tick(1).unwrap();
+        assert_eq!(
+            LogicValue::Zero,
+            TestProbe::get_value(&circuit, DEVICE_OUTPUT).unwrap()
+        );
+
+        TestProbe::set_output_low(&circuit, DEVICE_DRIVER1).unwrap();
+        TestProbe::set_output_high(&circuit, DEVICE_DRIVER2).unwrap();
+        circuit.tick(2).unwrap();
+        assert_eq!(
+            LogicValue::One,
+            TestProbe::get_value(&circuit, DEVICE_OUTPUT).unwrap()
+        );
+    }
 }
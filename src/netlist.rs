@@ -0,0 +1,678 @@
+use crate::device::Device;
+use crate::device::SubCircuit;
+use crate::DeviceParams;
+use crate::DeviceRegistry;
+use crate::Net;
+use crate::NetConnection;
+use crate::NetPull;
+use crate::NetResolution;
+use crate::NetlistParseError;
+use std::cell::RefCell;
+use std::fmt;
+
+type DevicesAndNets = (Vec<RefCell<Box<dyn Device>>>, Vec<Net>);
+
+/// A device in a netlist: its name, registered type and parameters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetlistDevice {
+    name: String,
+    type_name: String,
+    params: DeviceParams,
+    // line the device was read from, 0 if it was not read from a file
+    line: usize,
+}
+
+impl NetlistDevice {
+    pub fn new(name: &str, type_name: &str, params: DeviceParams) -> NetlistDevice {
+        return NetlistDevice {
+            name: name.to_string(),
+            type_name: type_name.to_string(),
+            params,
+            line: 0,
+        };
+    }
+
+    pub fn get_name(&self) -> &str {
+        return &self.name;
+    }
+
+    pub fn get_type_name(&self) -> &str {
+        return &self.type_name;
+    }
+
+    pub fn get_params(&self) -> &DeviceParams {
+        return &self.params;
+    }
+
+    /// Gets the line the device was read from, 0 if it was not read from a file.
+    pub fn get_line(&self) -> usize {
+        return self.line;
+    }
+
+    pub(crate) fn set_line(&mut self, line: usize) {
+        self.line = line;
+    }
+}
+
+/// Devices and the nets connecting them by pin name, as read from or written
+/// to a netlist file. Devices are created through a `DeviceRegistry` by
+/// `build`.
+///
+/// A netlist file has one statement per line, `#` starts a comment:
+///
+/// ```text
+/// # a full adder built from two half adders
+/// module half_adder
+///   device x xor
+///   device a and
+///   net a x.A a.A
+///   net b x.B a.B
+///   port a x.A
+///   port b x.B
+///   port s x.Y
+///   port c a.Y
+/// end
+///
+/// device in test_probe direction=output width=3 value=011
+/// device ha1 half_adder
+/// device ha2 half_adder
+/// device carry or
+/// net a in.pin[0] ha1.a
+/// net b in.pin[1] ha1.b
+/// net cin in.pin[2] ha2.b
+/// net out width=2 pull=down ha2.s carry.Y@1
+/// ...
+/// ```
+///
+/// - `device <name> <type> [<param>=<value> ...]` creates a device, `type` is
+///   either a type of the registry or a module.
+/// - `net <name> [<option>=<value> ...] <connection> ...` connects pins. The
+///   options are `width`, `pull` (`none`, `up` or `down`), `resolution`
+///   (`tri_state`, `wired_and` or `wired_or`) and `delay`. A connection is
+///   `device.pin`, optionally followed by the pin bits, `[msb:lsb]` or `[bit]`,
+///   and by `@lsb`, the net bit the first of them attaches to.
+/// - `module <name>` ... `end` defines a module, its instances become a
+///   `SubCircuit` whose devices are named `instance.device`. Modules are only
+///   defined at the top level but can instantiate each other.
+/// - `port <name> <device.pin>` exposes a pin of a module as a port of its
+///   instances, see `SubCircuit::add_port`.
+#[derive(Debug, Clone, Default)]
+pub struct Netlist {
+    // None for the top level
+    name: Option<String>,
+    devices: Vec<NetlistDevice>,
+    nets: Vec<Net>,
+    // (name, "device.pin", line)
+    ports: Vec<(String, String, usize)>,
+    modules: Vec<Netlist>,
+}
+
+impl Netlist {
+    pub fn new() -> Netlist {
+        return Netlist::default();
+    }
+
+    /// Creates a module to be added to a netlist with `add_module`.
+    pub fn module(name: &str) -> Netlist {
+        let mut module = Netlist::new();
+        module.name = Some(name.to_string());
+        return module;
+    }
+
+    /// Reads a netlist file, see `Netlist` for the format. Only the syntax is
+    /// checked, device types and parameters are checked by `build`.
+    pub fn parse(text: &str) -> Result<Netlist, NetlistParseError> {
+        let mut netlist = Netlist::new();
+        // the module being read and the line it started on
+        let mut module: Option<(Netlist, usize)> = None;
+        for (index, line) in text.lines().enumerate() {
+            let line_number = index + 1;
+            let error = |message: String| NetlistParseError::new(line_number, &message);
+            let code = line.split('#').next().unwrap_or("");
+            let tokens: Vec<&str> = code.split_whitespace().collect();
+            let Some((keyword, args)) = tokens.split_first() else {
+                continue;
+            };
+            match *keyword {
+                "module" => {
+                    if module.is_some() {
+                        return Err(error("modules cannot be nested".to_string()));
+                    }
+                    let [name] = args else {
+                        return Err(error("expected module <name>".to_string()));
+                    };
+                    Netlist::check_name(name, line_number)?;
+                    module = Some((Netlist::module(name), line_number));
+                }
+                "end" => {
+                    let Some((ended, _)) = module.take() else {
+                        return Err(error("end outside of a module".to_string()));
+                    };
+                    if !args.is_empty() {
+                        return Err(error("expected end".to_string()));
+                    }
+                    let name = ended.name.clone().unwrap_or_default();
+                    if netlist.get_module(&name).is_some() {
+                        return Err(error(format!("module {} is defined twice", name)));
+                    }
+                    netlist.modules.push(ended);
+                }
+                "device" => {
+                    let device = Netlist::parse_device(args, line_number)?;
+                    match &mut module {
+                        Some((module, _)) => module.devices.push(device),
+                        None => netlist.devices.push(device),
+                    }
+                }
+                "net" => {
+                    let net = Netlist::parse_net(args, line_number)?;
+                    match &mut module {
+                        Some((module, _)) => module.nets.push(net),
+                        None => netlist.nets.push(net),
+                    }
+                }
+                "port" => {
+                    let Some((module, _)) = &mut module else {
+                        return Err(error("ports are only allowed in modules".to_string()));
+                    };
+                    let [name, pin_name] = args else {
+                        return Err(error("expected port <name> <device.pin>".to_string()));
+                    };
+                    Netlist::check_name(name, line_number)?;
+                    Netlist::check_pin_name(pin_name, line_number)?;
+                    module
+                        .ports
+                        .push((name.to_string(), pin_name.to_string(), line_number));
+                }
+                _ => return Err(error(format!("unknown statement {}", keyword))),
+            }
+        }
+        if let Some((module, line_number)) = module {
+            return Err(NetlistParseError::new(
+                line_number,
+                &format!("module {} has no end", module.name.unwrap_or_default()),
+            ));
+        }
+        return Ok(netlist);
+    }
+
+    /// Gets the name of a module, `None` for a top level netlist.
+    pub fn get_name(&self) -> Option<&str> {
+        return self.name.as_deref();
+    }
+
+    pub fn add_device(&mut self, device: NetlistDevice) {
+        self.devices.push(device);
+    }
+
+    pub fn get_devices(&self) -> &[NetlistDevice] {
+        return &self.devices;
+    }
+
+    /// Adds a net, every connection must be made by pin name, e.g. with
+    /// `NetConnection::named`.
+    pub fn add_net(&mut self, net: Net) {
+        assert!(
+            net.connections_iter()
+                .all(|connection| connection.get_pin_name().is_some()),
+            "netlists connect pins by name"
+        );
+        self.nets.push(net);
+    }
+
+    pub fn get_nets(&self) -> &[Net] {
+        return &self.nets;
+    }
+
+    /// Exposes the pin `pin_name`, e.g. `"and1.A"`, as port `name` of the
+    /// module.
+    pub fn add_port(&mut self, name: &str, pin_name: &str) {
+        self.ports.push((name.to_string(), pin_name.to_string(), 0));
+    }
+
+    /// Gets the `(name, "device.pin")` of each port of the module.
+    pub fn ports_iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        return self
+            .ports
+            .iter()
+            .map(|(name, pin_name, _)| (name.as_str(), pin_name.as_str()));
+    }
+
+    /// Adds a module created by `Netlist::module`, its name can then be used
+    /// as a device type.
+    pub fn add_module(&mut self, module: Netlist) {
+        assert!(module.name.is_some(), "only modules can be added");
+        self.modules.push(module);
+    }
+
+    pub fn get_modules(&self) -> &[Netlist] {
+        return &self.modules;
+    }
+
+    pub fn get_module(&self, name: &str) -> Option<&Netlist> {
+        return self
+            .modules
+            .iter()
+            .find(|module| module.name.as_deref() == Some(name));
+    }
+
+    /// Creates the devices and nets of the top level, ready for
+    /// `Circuit::new`. Devices whose type is a module become a `SubCircuit`,
+    /// other types are created by `registry`.
+    pub fn build(&self, registry: &DeviceRegistry) -> Result<DevicesAndNets, NetlistParseError> {
+        return self.build_level(&self.modules, registry, &mut Vec::new());
+    }
+
+    /// `modules` are the modules of the top level, `stack` the names of the
+    /// modules being built so a module cannot contain itself.
+    pub(crate) fn build_level<'a>(
+        &'a self,
+        modules: &'a [Netlist],
+        registry: &DeviceRegistry,
+        stack: &mut Vec<&'a str>,
+    ) -> Result<DevicesAndNets, NetlistParseError> {
+        let mut devices: Vec<RefCell<Box<dyn Device>>> = Vec::new();
+        for device in &self.devices {
+            let error = |message: String| NetlistParseError::new(device.line, &message);
+            let module = modules
+                .iter()
+                .find(|module| module.name.as_deref() == Some(device.get_type_name()));
+            let built: Box<dyn Device> = match module {
+                Some(module) => {
+                    if stack.contains(&device.get_type_name()) {
+                        return Err(error(format!(
+                            "module {} contains itself",
+                            device.get_type_name()
+                        )));
+                    }
+                    if !device.params.is_empty() {
+                        return Err(error(format!(
+                            "device {}: modules take no parameters",
+                            device.name
+                        )));
+                    }
+                    stack.push(device.get_type_name());
+                    let (sub_devices, sub_nets) = module.build_level(modules, registry, stack)?;
+                    stack.pop();
+                    let mut sub_circuit = SubCircuit::new(&device.name, sub_devices, sub_nets);
+                    for (name, pin_name, line) in &module.ports {
+                        sub_circuit
+                            .add_port(name, pin_name)
+                            .map_err(|err| NetlistParseError::new(*line, &err.to_string()))?;
+                    }
+                    Box::new(sub_circuit)
+                }
+                None => registry
+                    .create(&device.type_name, &device.name, &device.params)
+                    .map_err(error)?,
+            };
+            devices.push(RefCell::new(built));
+        }
+        return Ok((devices, self.nets.clone()));
+    }
+
+    fn parse_device(args: &[&str], line: usize) -> Result<NetlistDevice, NetlistParseError> {
+        let [name, type_name, params @ ..] = args else {
+            return Err(NetlistParseError::new(
+                line,
+                "expected device <name> <type> [<param>=<value> ...]",
+            ));
+        };
+        Netlist::check_name(name, line)?;
+        Netlist::check_name(type_name, line)?;
+        let mut device = NetlistDevice::new(name, type_name, DeviceParams::new());
+        device.set_line(line);
+        for param in params {
+            let (param_name, value) = Netlist::parse_option(param, line)?;
+            if device.params.get(param_name).is_some() {
+                return Err(NetlistParseError::new(
+                    line,
+                    &format!("parameter {} is given twice", param_name),
+                ));
+            }
+            device.params.set(param_name, value);
+        }
+        return Ok(device);
+    }
+
+    fn parse_net(args: &[&str], line: usize) -> Result<Net, NetlistParseError> {
+        let error = |message: String| NetlistParseError::new(line, &message);
+        let [name, rest @ ..] = args else {
+            return Err(error(
+                "expected net <name> [<option>=<value> ...] <device.pin> ...".to_string(),
+            ));
+        };
+        Netlist::check_name(name, line)?;
+        let mut width: Option<usize> = None;
+        let mut pull = NetPull::default();
+        let mut resolution = NetResolution::default();
+        let mut delay = 0;
+        let mut connections: Vec<NetConnection> = Vec::new();
+        for token in rest {
+            if !token.contains('=') {
+                connections.push(Netlist::parse_connection(token, line)?);
+                continue;
+            }
+            let (option, value) = Netlist::parse_option(token, line)?;
+            match option {
+                "width" => match value.parse::<usize>() {
+                    Ok(value) if value > 0 => width = Some(value),
+                    _ => return Err(error(format!("invalid net width {}", value))),
+                },
+                "pull" => {
+                    pull = match value {
+                        "none" => NetPull::None,
+                        "up" => NetPull::Up,
+                        "down" => NetPull::Down,
+                        _ => return Err(error(format!("invalid pull {}", value))),
+                    }
+                }
+                "resolution" => {
+                    resolution = match value {
+                        "tri_state" => NetResolution::TriState,
+                        "wired_and" => NetResolution::WiredAnd,
+                        "wired_or" => NetResolution::WiredOr,
+                        _ => return Err(error(format!("invalid resolution {}", value))),
+                    }
+                }
+                "delay" => {
+                    delay = value
+                        .parse()
+                        .map_err(|_| error(format!("invalid delay {}", value)))?
+                }
+                _ => return Err(error(format!("unknown net option {}", option))),
+            }
+        }
+        let mut net = match width {
+            Some(width) => Net::bus(width, connections),
+            None => Net::new(connections),
+        };
+        net.set_name(name);
+        net.set_pull(pull);
+        net.set_resolution(resolution);
+        net.set_delay(delay);
+        return Ok(net);
+    }
+
+    /// Parses `device.pin[msb:lsb]@net_lsb`, where the slice and net bit are
+    /// optional.
+    fn parse_connection(token: &str, line: usize) -> Result<NetConnection, NetlistParseError> {
+        let parse_bit = |text: &str| -> Result<usize, NetlistParseError> {
+            return text.parse().map_err(|_| {
+                NetlistParseError::new(line, &format!("invalid connection {}", token))
+            });
+        };
+        let (pin_name, net_lsb) = match token.split_once('@') {
+            Some((pin_name, net_lsb)) => (pin_name, parse_bit(net_lsb)?),
+            None => (token, 0),
+        };
+        let Some((pin_name, bits)) = pin_name
+            .strip_suffix(']')
+            .and_then(|pin_name| pin_name.split_once('['))
+        else {
+            Netlist::check_pin_name(pin_name, line)?;
+            return Ok(NetConnection::named_at(pin_name, net_lsb));
+        };
+        Netlist::check_pin_name(pin_name, line)?;
+        let (msb, lsb) = match bits.split_once(':') {
+            Some((msb, lsb)) => (parse_bit(msb)?, parse_bit(lsb)?),
+            None => (parse_bit(bits)?, parse_bit(bits)?),
+        };
+        if msb < lsb {
+            return Err(NetlistParseError::new(
+                line,
+                &format!("bits of {} must be given msb first", token),
+            ));
+        }
+        return Ok(NetConnection::named_slice(
+            pin_name,
+            lsb,
+            net_lsb,
+            msb - lsb + 1,
+        ));
+    }
+
+    fn parse_option(token: &str, line: usize) -> Result<(&str, &str), NetlistParseError> {
+        match token.split_once('=') {
+            Some((name, value)) if !name.is_empty() && !value.is_empty() => {
+                return Ok((name, value))
+            }
+            _ => {
+                return Err(NetlistParseError::new(
+                    line,
+                    &format!("expected <name>=<value>, got {}", token),
+                ))
+            }
+        }
+    }
+
+    fn check_name(name: &str, line: usize) -> Result<(), NetlistParseError> {
+        if name.contains(['=', '[', ']', '@']) {
+            return Err(NetlistParseError::new(
+                line,
+                &format!("invalid name {}", name),
+            ));
+        }
+        return Ok(());
+    }
+
+    fn check_pin_name(pin_name: &str, line: usize) -> Result<(), NetlistParseError> {
+        match pin_name.rsplit_once('.') {
+            Some((device, pin)) if !device.is_empty() && !pin.is_empty() => {
+                return Netlist::check_name(pin_name, line)
+            }
+            _ => {
+                return Err(NetlistParseError::new(
+                    line,
+                    &format!("expected <device>.<pin>, got {}", pin_name),
+                ))
+            }
+        }
+    }
+
+    fn write_body(&self, f: &mut fmt::Formatter<'_>, indent: &str) -> fmt::Result {
+        for device in &self.devices {
+            write!(f, "{}device {} {}", indent, device.name, device.type_name)?;
+            for (name, value) in device.params.iter() {
+                write!(f, " {}={}", name, value)?;
+            }
+            writeln!(f)?;
+        }
+        for (net_index, net) in self.nets.iter().enumerate() {
+            write!(f, "{}net {}", indent, net.get_display_name(net_index))?;
+            if let Some(width) = net.get_width() {
+                write!(f, " width={}", width)?;
+            }
+            match net.get_pull() {
+                NetPull::None => {}
+                NetPull::Up => write!(f, " pull=up")?,
+                NetPull::Down => write!(f, " pull=down")?,
+            }
+            match net.get_resolution() {
+                NetResolution::TriState => {}
+                NetResolution::WiredAnd => write!(f, " resolution=wired_and")?,
+                NetResolution::WiredOr => write!(f, " resolution=wired_or")?,
+            }
+            if net.get_delay() != 0 {
+                write!(f, " delay={}", net.get_delay())?;
+            }
+            for connection in net.connections_iter() {
+                write!(f, " {}", connection.get_pin_name().unwrap_or_default())?;
+                let lsb = connection.get_pin_lsb();
+                match connection.get_width() {
+                    Some(1) => write!(f, "[{}]", lsb)?,
+                    Some(width) => write!(f, "[{}:{}]", lsb + width - 1, lsb)?,
+                    None => {}
+                }
+                if connection.get_net_lsb() != 0 {
+                    write!(f, "@{}", connection.get_net_lsb())?;
+                }
+            }
+            writeln!(f)?;
+        }
+        for (name, pin_name, _) in &self.ports {
+            writeln!(f, "{}port {} {}", indent, name, pin_name)?;
+        }
+        return Ok(());
+    }
+}
+
+impl fmt::Display for Netlist {
+    /// Writes the netlist file format, which `Netlist::parse` reads back into
+    /// the same netlist.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(name) = &self.name {
+            writeln!(f, "module {}", name)?;
+            self.write_body(f, "  ")?;
+            return writeln!(f, "end");
+        }
+        for module in &self.modules {
+            writeln!(f, "{}", module)?;
+        }
+        return self.write_body(f, "");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::device::TestProbe;
+    use crate::Circuit;
+    use crate::DeviceParams;
+    use crate::DeviceRegistry;
+    use crate::LogicValue;
+    use crate::Net;
+    use crate::NetConnection;
+    use crate::NetPull;
+    use crate::Netlist;
+    use crate::NetlistDevice;
+    use crate::NetlistParseError;
+
+    const FULL_ADDER: &str = "# a full adder built from two half adders
+module half_adder
+  device x xor
+  device a and
+  net a x.A a.A
+  net b x.B a.B
+  port a x.A
+  port b x.B
+  port s x.Y
+  port c a.Y
+end
+
+device in test_probe direction=output width=3 value=011   # cin b a
+device ha1 half_adder
+device ha2 half_adder
+device carry or
+device out test_probe width=2
+net a in.pin[0] ha1.a
+net b in.pin[1] ha1.b
+net cin in.pin[2] ha2.b
+net p ha1.s ha2.a
+net c1 ha1.c carry.A
+net c2 ha2.c carry.B
+net out width=2 pull=down ha2.s carry.Y@1 out.pin
+";
+
+    #[test]
+    fn loads_and_runs() {
+        let netlist = Netlist::parse(FULL_ADDER).unwrap();
+        let (devices, nets) = netlist.build(&DeviceRegistry::new()).unwrap();
+        let mut circuit = Circuit::new(devices, nets).unwrap();
+        circuit.run_until(10).unwrap();
+        // 1 + 1 + 0 = 10
+        assert_eq!(
+            "10",
+            TestProbe::get_bus_value(&circuit, "out")
+                .unwrap()
+                .to_string()
+        );
+        assert_eq!(
+            LogicValue::Zero,
+            TestProbe::get_bus_value(&circuit, "in").unwrap().get_bit(2)
+        );
+    }
+
+    #[test]
+    fn round_trips() {
+        let netlist = Netlist::parse(FULL_ADDER).unwrap();
+        let text = netlist.to_string();
+        assert!(text.starts_with("module half_adder\n  device x xor\n"));
+        assert!(text.contains("\nnet a in.pin[0] ha1.a\n"));
+        assert!(text.contains("\nnet out width=2 pull=down ha2.s carry.Y@1 out.pin\n"));
+        assert_eq!(text, Netlist::parse(&text).unwrap().to_string());
+
+        let mut netlist = Netlist::new();
+        let mut params = DeviceParams::new();
+        params.set("width", "8");
+        netlist.add_device(NetlistDevice::new("bus", "test_probe", params));
+        let mut net = Net::named(
+            "data",
+            vec![
+                NetConnection::named_slice("bus.pin", 4, 0, 4),
+                NetConnection::named_at("other.pin", 4),
+            ],
+        );
+        net.set_pull(NetPull::Up);
+        net.set_delay(3);
+        netlist.add_net(net);
+        let text = netlist.to_string();
+        assert_eq!(
+            "device bus test_probe width=8\nnet data pull=up delay=3 bus.pin[7:4] other.pin@4\n",
+            text
+        );
+        assert_eq!(text, Netlist::parse(&text).unwrap().to_string());
+    }
+
+    #[test]
+    fn reports_lines() {
+        let parse_error = |text: &str| Netlist::parse(text).unwrap_err();
+        assert_eq!(
+            NetlistParseError::new(2, "unknown statement wire"),
+            parse_error("device a and\nwire w a.Y\n")
+        );
+        assert_eq!(
+            NetlistParseError::new(1, "expected <device>.<pin>, got aY"),
+            parse_error("net w aY")
+        );
+        assert_eq!(
+            NetlistParseError::new(3, "invalid connection a.Y[1:x]"),
+            parse_error("\n\nnet w a.Y[1:x]")
+        );
+        assert_eq!(
+            NetlistParseError::new(1, "invalid pull sideways"),
+            parse_error("net w pull=sideways a.Y")
+        );
+        assert_eq!(
+            NetlistParseError::new(1, "module m has no end"),
+            parse_error("module m\ndevice a and\n")
+        );
+        assert_eq!(
+            NetlistParseError::new(2, "ports are only allowed in modules"),
+            parse_error("device a and\nport a a.A\n")
+        );
+
+        let build_error = |text: &str| {
+            let netlist = Netlist::parse(text).unwrap();
+            return netlist
+                .build(&DeviceRegistry::new())
+                .map(|_| ())
+                .unwrap_err();
+        };
+        assert_eq!(
+            NetlistParseError::new(2, "unknown device type adder"),
+            build_error("device a and\ndevice b adder\n")
+        );
+        assert_eq!(
+            NetlistParseError::new(1, "device c: parameter period must be a number, got fast"),
+            build_error("device c clock period=fast\n")
+        );
+        assert_eq!(
+            NetlistParseError::new(3, "unknown pin i.a.Q"),
+            build_error("module m\ndevice a and\nport q a.Q\nend\ndevice i m\n")
+        );
+        assert_eq!(
+            NetlistParseError::new(2, "module m contains itself"),
+            build_error("module m\ndevice i m\nend\ndevice i m\n")
+        );
+    }
+}
@@ -0,0 +1,141 @@
+use crate::BusValue;
+use crate::DeviceParams;
+use crate::Net;
+use crate::NetConnection;
+use crate::Netlist;
+use crate::NetlistDevice;
+use crate::PinDirection;
+
+/// Builds a netlist or module for the importers, which connect pins bit by bit
+/// to nets numbered in the order they were added.
+#[derive(Debug)]
+pub(crate) struct NetlistAssembler {
+    netlist: Netlist,
+    // (name, width, connections)
+    nets: Vec<(String, usize, Vec<NetConnection>)>,
+    // numbers the names made up for devices
+    generated: usize,
+}
+
+impl NetlistAssembler {
+    /// Assembles the top level if `module` is `None`.
+    pub(crate) fn new(module: Option<&str>) -> NetlistAssembler {
+        return NetlistAssembler {
+            netlist: match module {
+                Some(name) => Netlist::module(name),
+                None => Netlist::new(),
+            },
+            nets: Vec::new(),
+            generated: 0,
+        };
+    }
+
+    pub(crate) fn add_net(&mut self, name: &str, width: usize) -> usize {
+        self.nets.push((name.to_string(), width, Vec::new()));
+        return self.nets.len() - 1;
+    }
+
+    pub(crate) fn add_device(
+        &mut self,
+        name: &str,
+        type_name: &str,
+        params: DeviceParams,
+        line: usize,
+    ) {
+        let mut device = NetlistDevice::new(name, type_name, params);
+        device.set_line(line);
+        self.netlist.add_device(device);
+    }
+
+    /// Makes up a device name, `$` keeps it apart from the names of the source.
+    pub(crate) fn generate_name(&mut self, prefix: &str) -> String {
+        self.generated += 1;
+        return format!("${}{}", prefix, self.generated);
+    }
+
+    /// Adds a test probe called `name` on the whole of `net` for a port. At the
+    /// top level the probe drives inputs and samples outputs, in a module it
+    /// only stands for the port, which is exposed as `name`.
+    pub(crate) fn add_port(
+        &mut self,
+        name: &str,
+        direction: PinDirection,
+        net: usize,
+        line: usize,
+    ) {
+        let top = self.netlist.get_name().is_none();
+        let probe_direction = match (direction, top) {
+            (PinDirection::Input, true) => "output",
+            (PinDirection::Input, false) => "input",
+            (PinDirection::Output, true) => "input",
+            _ => "inout",
+        };
+        let mut params = DeviceParams::new();
+        params.set("direction", probe_direction);
+        let width = self.nets[net].1;
+        if width > 1 {
+            params.set("width", &width.to_string());
+        }
+        self.add_device(name, "test_probe", params, line);
+        let pin_name = format!("{}.pin", name);
+        self.nets[net].2.push(NetConnection::named(&pin_name));
+        if !top {
+            self.netlist.add_port(name, &pin_name);
+        }
+    }
+
+    /// Adds an output test probe driving `value` onto a new net, returns the net.
+    pub(crate) fn add_constant(&mut self, value: BusValue, line: usize) -> usize {
+        let name = self.generate_name("const");
+        let mut params = DeviceParams::new();
+        params.set("direction", "output");
+        params.set("value", &value.to_string());
+        self.add_device(&name, "test_probe", params, line);
+        let net = self.add_net(&name, value.get_width());
+        self.nets[net]
+            .2
+            .push(NetConnection::named(&format!("{}.pin", name)));
+        return net;
+    }
+
+    /// Connects pin `pin_name` bit by bit to the `(net, bit)` pairs of `bits`,
+    /// least significant first, joining runs of bits into slices. Returns the
+    /// number of slices.
+    pub(crate) fn connect(&mut self, pin_name: &str, bits: &[(usize, usize)]) -> usize {
+        let mut slices = 0;
+        let mut start = 0;
+        while start < bits.len() {
+            let (net, net_lsb) = bits[start];
+            let mut end = start + 1;
+            while end < bits.len() && bits[end] == (net, net_lsb + end - start) {
+                end += 1;
+            }
+            let connection = if start == 0 && end == bits.len() {
+                NetConnection::named_at(pin_name, net_lsb)
+            } else {
+                NetConnection::named_slice(pin_name, start, net_lsb, end - start)
+            };
+            self.nets[net].2.push(connection);
+            slices += 1;
+            start = end;
+        }
+        return slices;
+    }
+
+    /// Adds the nets that have connections and returns the netlist.
+    pub(crate) fn finish(mut self) -> Netlist {
+        for (name, width, connections) in self.nets {
+            if connections.is_empty() {
+                continue;
+            }
+            let mut net = if width > 1 {
+                Net::bus(width, connections)
+            } else {
+                Net::new(connections)
+            };
+            net.set_name(&name);
+            self.netlist.add_net(net);
+        }
+        return self.netlist;
+    }
+}
@@ -0,0 +1,98 @@
+use std::fmt;
+
+/// A problem found in the devices and nets given to `Circuit::new`. Devices
+/// and pins are identified by name, nets by name or `net<index>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NetlistError {
+    /// A net connects to a device index that is not part of the circuit.
+    UnknownDevice { net: String, device: usize },
+    /// A net connects to a pin the device does not have. Pins are numbered
+    /// from 1, pin 0 is never valid.
+    UnknownPin {
+        net: String,
+        device: String,
+        pin: usize,
+    },
+    /// A net connects to a `"device.pin"` name that does not exist.
+    UnknownPinName { net: String, pin: String },
+    /// A connection covers bits outside of the pin or outside of the net.
+    SliceOutOfRange {
+        net: String,
+        device: String,
+        pin: String,
+    },
+    /// The same bits of a pin are connected to more than one net.
+    PinInMultipleNets {
+        device: String,
+        pin: String,
+        nets: Vec<String>,
+    },
+    /// No output or in/out pin is connected to the net and it has no pull.
+    NetWithoutDriver { net: String },
+    /// Some bits of an input pin without a default value are not connected to
+    /// any net.
+    UnconnectedInput { device: String, pin: String },
+    /// A port of a sub circuit is connected in part, to several nets, or to a
+    /// net it cannot be merged with, see `SubCircuit::add_port`.
+    InvalidPortConnection {
+        net: String,
+        device: String,
+        pin: String,
+    },
+    /// More than one device has the same name, `devices` holds their indexes.
+    DuplicateDeviceName { device: String, devices: Vec<usize> },
+    /// The nets feed back into themselves through devices without delay, see
+    /// `Device::get_zero_delay_paths`.
+    CombinationalLoop {
+        devices: Vec<String>,
+        nets: Vec<String>,
+    },
+}
+
+impl fmt::Display for NetlistError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetlistError::UnknownDevice { net, device } => {
+                write!(f, "net {} connects to unknown device {}", net, device)
+            }
+            NetlistError::UnknownPin { net, device, pin } => write!(
+                f,
+                "net {} connects to unknown pin {} of device {}",
+                net, pin, device
+            ),
+            NetlistError::UnknownPinName { net, pin } => {
+                write!(f, "net {} connects to unknown pin {}", net, pin)
+            }
+            NetlistError::SliceOutOfRange { net, device, pin } => write!(
+                f,
+                "net {} connects bits outside of pin {}.{} or of the net",
+                net, device, pin
+            ),
+            NetlistError::PinInMultipleNets { device, pin, nets } => write!(
+                f,
+                "pin {}.{} is connected to nets {}",
+                device,
+                pin,
+                nets.join(", ")
+            ),
+            NetlistError::NetWithoutDriver { net } => write!(f, "net {} has no driver", net),
+            NetlistError::UnconnectedInput { device, pin } => {
+                write!(f, "input pin {}.{} is not connected", device, pin)
+            }
+            NetlistError::InvalidPortConnection { net, device, pin } => write!(
+                f,
+                "net {} must connect to the whole of port {}.{} and be its only net",
+                net, device, pin
+            ),
+            NetlistError::DuplicateDeviceName { device, devices } => {
+                write!(f, "device name {} is used by devices {:?}", device, devices)
+            }
+            NetlistError::CombinationalLoop { devices, nets } => write!(
+                f,
+                "combinational loop through devices {} and nets {}",
+                devices.join(", "),
+                nets.join(", ")
+            ),
+        }
+    }
+}
@@ -0,0 +1,33 @@
+use std::fmt;
+
+/// A problem found while reading a netlist file, `line` counts from 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetlistParseError {
+    line: usize,
+    message: String,
+}
+
+impl NetlistParseError {
+    pub fn new(line: usize, message: &str) -> NetlistParseError {
+        return NetlistParseError {
+            line,
+            message: message.to_string(),
+        };
+    }
+
+    pub fn get_line(&self) -> usize {
+        return self.line;
+    }
+
+    pub fn get_message(&self) -> &str {
+        return &self.message;
+    }
+}
+
+impl fmt::Display for NetlistParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for NetlistParseError {}
@@ -0,0 +1,438 @@
+use crate::device::Device;
+use crate::Net;
+use crate::NetPull;
+use crate::NetlistError;
+use crate::PinDirection;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+/// Resolves connections made by pin name, then checks the netlist and returns
+/// every problem found, empty if it is valid.
+pub(crate) fn validate_netlist(
+    devices: &[RefCell<Box<dyn Device>>],
+    nets: &mut [Net],
+) -> Vec<NetlistError> {
+    let mut errors: Vec<NetlistError> = Vec::new();
+
+    let mut name_indexes: HashMap<String, Vec<usize>> = HashMap::new();
+    let mut names: Vec<String> = Vec::new();
+    for (device_index, device) in devices.iter().enumerate() {
+        let name = device.borrow().get_name().to_string();
+        name_indexes
+            .entry(name.clone())
+            .or_default()
+            .push(device_index);
+        names.push(name);
+    }
+    for (device_index, name) in names.iter().enumerate() {
+        let indexes = &name_indexes[name];
+        if indexes.len() > 1 && indexes[0] == device_index {
+            errors.push(NetlistError::DuplicateDeviceName {
+                device: name.clone(),
+                devices: indexes.clone(),
+            });
+        }
+    }
+
+    for (net_index, net) in nets.iter_mut().enumerate() {
+        let net_name = net.get_display_name(net_index);
+        for conn in net.connections_iter_mut() {
+            let pin_name = match conn.get_pin_name() {
+                Some(pin_name) if !conn.is_resolved() => pin_name.to_string(),
+                _ => continue,
+            };
+            match find_pin(devices, &name_indexes, &pin_name) {
+                Some((device, pin)) => conn.resolve(device, pin),
+                None => errors.push(NetlistError::UnknownPinName {
+                    net: net_name.clone(),
+                    pin: pin_name,
+                }),
+            }
+        }
+    }
+
+    // pin_bits[device_index][pin_index] = (net index, bits) for every connection
+    let mut pin_bits: Vec<Vec<Vec<(usize, u64)>>> = devices
+        .iter()
+        .map(|device| vec![Vec::new(); device.borrow().get_pin_count() + 1])
+        .collect();
+    for (net_index, net) in nets.iter().enumerate() {
+        let net_name = net.get_display_name(net_index);
+        let mut has_driver = false;
+        for conn in net.connections_iter().filter(|conn| conn.is_resolved()) {
+            let device = match devices.get(conn.get_device()) {
+                Some(device) => device.borrow(),
+                None => {
+                    errors.push(NetlistError::UnknownDevice {
+                        net: net_name.clone(),
+                        device: conn.get_device(),
+                    });
+                    continue;
+                }
+            };
+            let pin = conn.get_pin();
+            if pin == 0 || pin > device.get_pin_count() {
+                errors.push(NetlistError::UnknownPin {
+                    net: net_name.clone(),
+                    device: device.get_name().to_string(),
+                    pin,
+                });
+                continue;
+            }
+            let pin_info = device.get_pin_info(pin);
+            let pin_width = pin_info.get_width();
+            let width = conn
+                .get_width()
+                .unwrap_or_else(|| pin_width.saturating_sub(conn.get_pin_lsb()));
+            let outside_net = match net.get_width() {
+                Some(net_width) => conn.get_net_lsb() + width > net_width,
+                None => false,
+            };
+            if width == 0 || conn.get_pin_lsb() + width > pin_width || outside_net {
+                errors.push(NetlistError::SliceOutOfRange {
+                    net: net_name.clone(),
+                    device: device.get_name().to_string(),
+                    pin: pin_info.get_name().to_string(),
+                });
+                continue;
+            }
+
+            let bits = bit_mask(conn.get_pin_lsb(), width);
+            let pin_nets = &mut pin_bits[conn.get_device()][pin];
+            let overlapping: Vec<String> = pin_nets
+                .iter()
+                .filter(|(other_net, other_bits)| *other_net != net_index && other_bits & bits != 0)
+                .map(|(other_net, _)| nets[*other_net].get_display_name(*other_net))
+                .collect();
+            if !overlapping.is_empty() {
+                let mut nets = overlapping;
+                nets.push(net_name.clone());
+                nets.dedup();
+                errors.push(NetlistError::PinInMultipleNets {
+                    device: device.get_name().to_string(),
+                    pin: pin_info.get_name().to_string(),
+                    nets,
+                });
+            }
+            pin_nets.push((net_index, bits));
+
+            if pin_info.get_direction() != PinDirection::Input {
+                has_driver = true;
+            }
+        }
+        if !has_driver && net.get_pull() == NetPull::None {
+            errors.push(NetlistError::NetWithoutDriver { net: net_name });
+        }
+    }
+
+    for (device, pins) in devices.iter().zip(pin_bits.iter()) {
+        let device = device.borrow();
+        for (pin, connections) in pins.iter().enumerate().skip(1) {
+            let pin_info = device.get_pin_info(pin);
+            if pin_info.get_direction() != PinDirection::Input
+                || pin_info.get_default_value().is_some()
+            {
+                continue;
+            }
+            let connected = connections
+                .iter()
+                .fold(0, |bits, (_, conn_bits)| bits | conn_bits);
+            if connected != bit_mask(0, pin_info.get_width()) {
+                errors.push(NetlistError::UnconnectedInput {
+                    device: device.get_name().to_string(),
+                    pin: pin_info.get_name().to_string(),
+                });
+            }
+        }
+    }
+
+    errors.extend(find_combinational_loops(devices, nets, &pin_bits));
+    return errors;
+}
+
+/// Finds cycles in the graph of nets connected through zero-delay device paths.
+fn find_combinational_loops(
+    devices: &[RefCell<Box<dyn Device>>],
+    nets: &[Net],
+    pin_bits: &[Vec<Vec<(usize, u64)>>],
+) -> Vec<NetlistError> {
+    // edges[net_index] = (net index, device index) for every net it feeds directly
+    let mut edges: Vec<Vec<(usize, usize)>> = vec![Vec::new(); nets.len()];
+    for (device_index, device) in devices.iter().enumerate() {
+        let pins = &pin_bits[device_index];
+        for (input, output) in device.borrow().get_zero_delay_paths() {
+            if input >= pins.len() || output >= pins.len() {
+                continue;
+            }
+            for (input_net, _) in &pins[input] {
+                for (output_net, _) in &pins[output] {
+                    edges[*input_net].push((*output_net, device_index));
+                }
+            }
+        }
+    }
+
+    let mut errors: Vec<NetlistError> = Vec::new();
+    for component in strongly_connected_components(&edges) {
+        let mut loop_devices: Vec<usize> = Vec::new();
+        for net_index in &component {
+            for (next_net, device_index) in &edges[*net_index] {
+                if component.contains(next_net) {
+                    loop_devices.push(*device_index);
+                }
+            }
+        }
+        if loop_devices.is_empty() {
+            continue;
+        }
+        loop_devices.sort_unstable();
+        loop_devices.dedup();
+        errors.push(NetlistError::CombinationalLoop {
+            devices: loop_devices
+                .iter()
+                .map(|device_index| devices[*device_index].borrow().get_name().to_string())
+                .collect(),
+            nets: component
+                .iter()
+                .map(|net_index| nets[*net_index].get_display_name(*net_index))
+                .collect(),
+        });
+    }
+    return errors;
+}
+
+/// Tarjan's algorithm, each component lists its nodes in ascending order and
+/// components are ordered by their lowest node.
+fn strongly_connected_components(edges: &[Vec<(usize, usize)>]) -> Vec<Vec<usize>> {
+    struct State<'a> {
+        edges: &'a [Vec<(usize, usize)>],
+        index: Vec<Option<usize>>,
+        low_link: Vec<usize>,
+        on_stack: Vec<bool>,
+        stack: Vec<usize>,
+        next_index: usize,
+        components: Vec<Vec<usize>>,
+    }
+
+    fn visit(state: &mut State, node: usize) {
+        state.index[node] = Some(state.next_index);
+        state.low_link[node] = state.next_index;
+        state.next_index += 1;
+        state.stack.push(node);
+        state.on_stack[node] = true;
+        for (next, _) in state.edges[node].iter() {
+            match state.index[*next] {
+                None => {
+                    visit(state, *next);
+                    state.low_link[node] = state.low_link[node].min(state.low_link[*next]);
+                }
+                Some(next_index) => {
+                    if state.on_stack[*next] {
+                        state.low_link[node] = state.low_link[node].min(next_index);
+                    }
+                }
+            }
+        }
+        if Some(state.low_link[node]) == state.index[node] {
+            let mut component = Vec::new();
+            while let Some(member) = state.stack.pop() {
+                state.on_stack[member] = false;
+                component.push(member);
+                if member == node {
+                    break;
+                }
+            }
+            component.sort_unstable();
+            state.components.push(component);
+        }
+    }
+
+    let mut state = State {
+        edges,
+        index: vec![None; edges.len()],
+        low_link: vec![0; edges.len()],
+        on_stack: vec![false; edges.len()],
+        stack: Vec::new(),
+        next_index: 0,
+        components: Vec::new(),
+    };
+    for node in 0..edges.len() {
+        if state.index[node].is_none() {
+            visit(&mut state, node);
+        }
+    }
+    let mut components = state.components;
+    components.sort_unstable_by_key(|component| component[0]);
+    return components;
+}
+
+/// Finds the device and pin numbers of a `"device.pin"` name.
+fn find_pin(
+    devices: &[RefCell<Box<dyn Device>>],
+    name_indexes: &HashMap<String, Vec<usize>>,
+    pin_name: &str,
+) -> Option<(usize, usize)> {
+    let (device_name, pin_name) = pin_name.rsplit_once('.')?;
+    let device_index = *name_indexes.get(device_name)?.first()?;
+    let pin = devices[device_index].borrow().get_pin_by_name(pin_name)?;
+    return Some((device_index, pin));
+}
+
+fn bit_mask(lsb: usize, width: usize) -> u64 {
+    if width >= 64 {
+        return u64::MAX << lsb;
+    }
+    return ((1 << width) - 1) << lsb;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::device::AndGate;
+    use crate::device::Device;
+    use crate::device::DeviceResponse;
+    use crate::device::TestProbe;
+    use crate::Circuit;
+    use crate::CircuitBuilder;
+    use crate::LogicValue;
+    use crate::Net;
+    use crate::NetConnection;
+    use crate::NetlistError;
+    use crate::PinDirection;
+    use crate::PinInfo;
+    use crate::PinValue;
+    use crate::SimError;
+    use std::cell::RefCell;
+
+    #[test]
+    fn reports_every_problem() {
+        let devices: Vec<RefCell<Box<dyn Device>>> = vec![
+            RefCell::new(Box::new(AndGate::new("and1"))),
+            RefCell::new(Box::new(TestProbe::new(
+                "tp",
+                LogicValue::Zero,
+                PinDirection::Output,
+            ))),
+            RefCell::new(Box::new(TestProbe::new(
+                "tp",
+                LogicValue::Zero,
+                PinDirection::Output,
+            ))),
+        ];
+        let nets = vec![
+            Net::named(
+                "in1",
+                vec![
+                    NetConnection::new(0, AndGate::PIN_INPUT1),
+                    NetConnection::new(1, TestProbe::PIN),
+                ],
+            ),
+            Net::new(vec![
+                NetConnection::new(0, AndGate::PIN_INPUT1),
+                NetConnection::new(9, 1),
+                NetConnection::new(0, 0),
+                NetConnection::named("and1.C"),
+            ]),
+        ];
+        let errors = match Circuit::new(devices, nets) {
+            Err(SimError::InvalidNetlist { errors }) => errors,
+            result => panic!("expected an invalid netlist, got {:?}", result),
+        };
+        assert_eq!(
+            vec![
+                NetlistError::DuplicateDeviceName {
+                    device: "tp".to_string(),
+                    devices: vec![1, 2],
+                },
+                NetlistError::UnknownPinName {
+                    net: "net1".to_string(),
+                    pin: "and1.C".to_string(),
+                },
+                NetlistError::PinInMultipleNets {
+                    device: "and1".to_string(),
+                    pin: "A".to_string(),
+                    nets: vec!["in1".to_string(), "net1".to_string()],
+                },
+                NetlistError::UnknownDevice {
+                    net: "net1".to_string(),
+                    device: 9,
+                },
+                NetlistError::UnknownPin {
+                    net: "net1".to_string(),
+                    device: "and1".to_string(),
+                    pin: 0,
+                },
+                NetlistError::NetWithoutDriver {
+                    net: "net1".to_string(),
+                },
+                NetlistError::UnconnectedInput {
+                    device: "and1".to_string(),
+                    pin: "B".to_string(),
+                },
+            ],
+            errors
+        );
+        assert_eq!(
+            "net net1 connects to unknown pin 0 of device and1",
+            errors[4].to_string()
+        );
+    }
+
+    /// Inverts input `A` onto output `Y` without delay.
+    struct Inverter {
+        name: String,
+    }
+
+    impl Device for Inverter {
+        fn get_name(&self) -> &str {
+            return &self.name;
+        }
+
+        fn get_pin_count(&self) -> usize {
+            return 2;
+        }
+
+        fn get_pin_info(&self, pin: usize) -> PinInfo {
+            if pin == 1 {
+                return PinInfo::new("A", PinDirection::Input);
+            }
+            return PinInfo::new("Y", PinDirection::Output);
+        }
+
+        fn get_zero_delay_paths(&self) -> Vec<(usize, usize)> {
+            return vec![(1, 2)];
+        }
+
+        fn on_inputs_changed(&mut self, _tick: u64, pins: &[PinValue]) -> DeviceResponse {
+            let mut response = DeviceResponse::new();
+            response.set_output(2, !pins[0].get_logic_value());
+            return response;
+        }
+    }
+
+    #[test]
+    fn reports_combinational_loops() {
+        let mut builder = CircuitBuilder::new();
+        for name in &["inv1", "inv2", "inv3"] {
+            builder.add_device(Inverter {
+                name: name.to_string(),
+            });
+        }
+        builder.add_device(TestProbe::new("in", LogicValue::Zero, PinDirection::Output));
+        builder.connect("inv1.Y", "inv2.A");
+        builder.connect("inv2.Y", "inv1.A");
+        // not part of the loop, it only reads from it
+        builder.connect("in.pin", "inv3.A");
+        builder.add_net(Net::named("out", vec![NetConnection::named("inv3.Y")]));
+        let errors = match builder.build() {
+            Err(SimError::InvalidNetlist { errors }) => errors,
+            result => panic!("expected an invalid netlist, got {:?}", result.err()),
+        };
+        assert_eq!(
+            vec![NetlistError::CombinationalLoop {
+                devices: vec!["inv1".to_string(), "inv2".to_string()],
+                nets: vec!["net1".to_string(), "net2".to_string()],
+            }],
+            errors
+        );
+    }
+}
@@ -1,5 +1,7 @@
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum PinDirection {
     Input = 0,
     Output = 1,
+    /// Both drives and reads its net, e.g. a tri-state bus port.
+    InOut = 2,
 }
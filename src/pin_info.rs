@@ -0,0 +1,122 @@
+use crate::BusValue;
+use crate::PinDirection;
+
+/// Describes one pin of a device. Pin names are used to connect nets by
+/// `"device.pin"` name and to label errors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PinInfo {
+    name: String,
+    direction: PinDirection,
+    width: usize,
+    default_value: Option<BusValue>,
+}
+
+impl PinInfo {
+    /// Creates a single-bit pin.
+    pub fn new(name: &str, direction: PinDirection) -> PinInfo {
+        return PinInfo::bus(name, direction, 1);
+    }
+
+    /// Creates a `width` bit pin.
+    pub fn bus(name: &str, direction: PinDirection, width: usize) -> PinInfo {
+        return PinInfo {
+            name: name.to_string(),
+            direction,
+            width,
+            default_value: None,
+        };
+    }
+
+    pub fn get_name(&self) -> &str {
+        return &self.name;
+    }
+
+    pub fn get_direction(&self) -> PinDirection {
+        return self.direction;
+    }
+
+    pub fn get_width(&self) -> usize {
+        return self.width;
+    }
+
+    /// Gets the value an input reads on bits not connected to any net, `None`
+    /// if the pin must be connected.
+    pub fn get_default_value(&self) -> Option<BusValue> {
+        return self.default_value;
+    }
+
+    pub fn set_default_value<V: Into<BusValue>>(&mut self, value: V) {
+        let value = value.into();
+        if value.get_width() != self.width {
+            panic!(
+                "pin {} is {} bits wide, cannot default to {:?}",
+                self.name, self.width, value
+            );
+        }
+        self.default_value = Some(value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::device::Device;
+    use crate::device::DeviceResponse;
+    use crate::device::TestProbe;
+    use crate::Circuit;
+    use crate::LogicValue;
+    use crate::Net;
+    use crate::NetConnection;
+    use crate::PinDirection;
+    use crate::PinInfo;
+    use crate::PinValue;
+    use std::cell::RefCell;
+
+    /// Copies input `D`, which defaults to 1, to output `Q`.
+    struct Follower {}
+
+    impl Device for Follower {
+        fn get_name(&self) -> &str {
+            return "follower";
+        }
+
+        fn get_pin_count(&self) -> usize {
+            return 2;
+        }
+
+        fn get_pin_info(&self, pin: usize) -> PinInfo {
+            if pin == 1 {
+                let mut info = PinInfo::new("D", PinDirection::Input);
+                info.set_default_value(LogicValue::One);
+                return info;
+            }
+            return PinInfo::new("Q", PinDirection::Output);
+        }
+
+        fn on_inputs_changed(&mut self, _tick: u64, pins: &[PinValue]) -> DeviceResponse {
+            let mut response = DeviceResponse::new();
+            response.set_output(2, pins[0].get_value());
+            return response;
+        }
+    }
+
+    #[test]
+    fn unconnected_input_reads_default_value() {
+        let devices: Vec<RefCell<Box<dyn Device>>> = vec![
+            RefCell::new(Box::new(Follower {})),
+            RefCell::new(Box::new(TestProbe::new(
+                "output",
+                LogicValue::Z,
+                PinDirection::Input,
+            ))),
+        ];
+        let nets = vec![Net::new(vec![
+            NetConnection::named("follower.Q"),
+            NetConnection::named("output.pin"),
+        ])];
+        let mut circuit = Circuit::new(devices, nets).unwrap();
+        assert_eq!("D", circuit.get_pin_info(0, 1).unwrap().get_name());
+        assert_eq!(None, circuit.get_pin_info(0, 3));
+        circuit.tick(1).unwrap();
+        assert_eq!(LogicValue::One, TestProbe::get_value(&circuit, 1).unwrap());
+    }
+}
@@ -0,0 +1,28 @@
+use crate::BusValue;
+use crate::LogicValue;
+
+/// A value on a single device pin.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PinValue {
+    pin: usize,
+    value: BusValue,
+}
+
+impl PinValue {
+    pub fn new(pin: usize, value: BusValue) -> PinValue {
+        return PinValue { pin, value };
+    }
+
+    pub fn get_pin(&self) -> usize {
+        return self.pin;
+    }
+
+    pub fn get_value(&self) -> BusValue {
+        return self.value;
+    }
+
+    /// Gets the value of a single-bit pin.
+    pub fn get_logic_value(&self) -> LogicValue {
+        return self.value.get_bit(0);
+    }
+}
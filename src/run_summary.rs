@@ -0,0 +1,33 @@
+/// Result of `Circuit::run_until`, `run_until_stable` and `run_for`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RunSummary {
+    final_tick: u64,
+    events: u64,
+    quiescent: bool,
+}
+
+impl RunSummary {
+    pub(crate) fn new(final_tick: u64, events: u64, quiescent: bool) -> RunSummary {
+        return RunSummary {
+            final_tick,
+            events,
+            quiescent,
+        };
+    }
+
+    /// Gets the last tick of the circuit when the run stopped.
+    pub fn get_final_tick(&self) -> u64 {
+        return self.final_tick;
+    }
+
+    /// Gets the number of ticks at which the circuit had something to do.
+    pub fn get_events(&self) -> u64 {
+        return self.events;
+    }
+
+    /// True if no device is waiting to be woken, the circuit will not change
+    /// again until it is given new data.
+    pub fn is_quiescent(&self) -> bool {
+        return self.quiescent;
+    }
+}
@@ -0,0 +1,130 @@
+use crate::NetlistError;
+use std::any::Any;
+use std::fmt;
+
+/// Errors returned while building or running a `Circuit`. Once a tick fails
+/// the circuit keeps returning the same error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SimError {
+    /// The devices and nets given to `Circuit::new` do not form a valid
+    /// circuit, `errors` lists every problem found.
+    InvalidNetlist { errors: Vec<NetlistError> },
+    /// `Circuit::tick` was called with a tick not greater than the last one.
+    NonIncreasingTick { tick: u64, last_tick: u64 },
+    /// A device index that is not part of the circuit.
+    UnknownDevice { device: usize },
+    /// A device name that is not part of the circuit.
+    UnknownDeviceName { name: String },
+    /// A pin name the device does not have.
+    UnknownPinName { device: String, pin: String },
+    /// A device drove or released pin number `pin`, which it does not have.
+    UnknownPin { device: String, pin: usize },
+    /// A device replied with a message the circuit was not expecting.
+    UnexpectedMessage { device: String, message: String },
+    /// A device drove a value of the wrong width onto a pin, or a test probe
+    /// was set to drive one, `pin` is the pin's name.
+    PinWidthMismatch {
+        device: String,
+        pin: String,
+        expected: usize,
+        actual: usize,
+    },
+    /// A device stopped responding without panicking.
+    DeviceDisconnected { device: String },
+    /// A device panicked, `message` is the panic message.
+    DevicePanicked { device: String, message: String },
+    /// Outputs were still changing after `Circuit::MAX_DELTA_CYCLES` rounds of
+    /// routing within one tick.
+    DidNotSettle { tick: u64 },
+    /// A net changed more often than allowed by `Circuit::set_oscillation_limit`.
+    /// `nets` are the nets that changed at least as often as the limit, so the
+    /// other nets of a loop, which lag one change behind, are included too.
+    /// `devices` are the devices driving them.
+    Oscillation {
+        tick: u64,
+        nets: Vec<String>,
+        devices: Vec<String>,
+    },
+    /// A run processed `budget` events without reaching its goal, see
+    /// `Circuit::set_event_budget`.
+    EventBudgetExceeded { budget: u64, tick: u64 },
+}
+
+impl SimError {
+    pub(crate) fn device_panicked(device: &str, payload: Box<dyn Any + Send>) -> SimError {
+        let message = if let Some(message) = payload.downcast_ref::<&str>() {
+            message.to_string()
+        } else if let Some(message) = payload.downcast_ref::<String>() {
+            message.clone()
+        } else {
+            "unknown panic".to_string()
+        };
+        return SimError::DevicePanicked {
+            device: device.to_string(),
+            message,
+        };
+    }
+}
+
+impl fmt::Display for SimError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SimError::InvalidNetlist { errors } => {
+                write!(f, "invalid netlist")?;
+                for (i, err) in errors.iter().enumerate() {
+                    write!(f, "{} {}", if i == 0 { ":" } else { ";" }, err)?;
+                }
+                return Ok(());
+            }
+            SimError::NonIncreasingTick { tick, last_tick } => write!(
+                f,
+                "tick {} must be greater than last tick {}",
+                tick, last_tick
+            ),
+            SimError::UnknownDevice { device } => write!(f, "unknown device {}", device),
+            SimError::UnknownDeviceName { name } => write!(f, "unknown device {}", name),
+            SimError::UnknownPinName { device, pin } => write!(f, "unknown pin {}.{}", device, pin),
+            SimError::UnknownPin { device, pin } => {
+                write!(f, "device {} has no pin {}", device, pin)
+            }
+            SimError::UnexpectedMessage { device, message } => {
+                write!(f, "unexpected message from device {}: {}", device, message)
+            }
+            SimError::PinWidthMismatch {
+                device,
+                pin,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "device {} drove {} bits on {} bit pin {}",
+                device, actual, expected, pin
+            ),
+            SimError::DeviceDisconnected { device } => {
+                write!(f, "device {} stopped responding", device)
+            }
+            SimError::DevicePanicked { device, message } => {
+                write!(f, "device {} panicked: {}", device, message)
+            }
+            SimError::DidNotSettle { tick } => write!(f, "circuit did not settle at tick {}", tick),
+            SimError::Oscillation {
+                tick,
+                nets,
+                devices,
+            } => write!(
+                f,
+                "nets {} driven by {} are oscillating at tick {}",
+                nets.join(", "),
+                devices.join(", "),
+                tick
+            ),
+            SimError::EventBudgetExceeded { budget, tick } => write!(
+                f,
+                "circuit was still busy at tick {} after {} events",
+                tick, budget
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SimError {}
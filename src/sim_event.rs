@@ -0,0 +1,23 @@
+use crate::TimingCheckKind;
+
+/// Something noteworthy that happened while simulating, collected by the
+/// circuit and retrieved with `Circuit::take_events`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SimEvent {
+    /// Two or more drivers on a tri-state net drove different values.
+    BusContention {
+        tick: u64,
+        net: String,
+        /// Names of the devices driving the net.
+        devices: Vec<String>,
+    },
+    /// A timing check of a device failed, `pin` missed the check's limit by
+    /// `amount` ticks.
+    TimingViolation {
+        tick: u64,
+        device: String,
+        pin: String,
+        check: TimingCheckKind,
+        amount: u64,
+    },
+}
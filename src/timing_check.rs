@@ -0,0 +1,386 @@
+use crate::LogicValue;
+use crate::PinValue;
+use std::fmt;
+
+/// The kinds of timing check, named like their Verilog `$setup`, `$hold`, ...
+/// counterparts.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TimingCheckKind {
+    /// Data must be stable for `limit` ticks before the reference edge.
+    Setup,
+    /// Data must stay stable for `limit` ticks after the reference edge.
+    Hold,
+    /// An asynchronous control must be released `limit` ticks before the
+    /// reference edge.
+    Recovery,
+    /// An asynchronous control must not be released within `limit` ticks after
+    /// the reference edge.
+    Removal,
+    /// A pulse must last at least `limit` ticks.
+    PulseWidth,
+}
+
+impl fmt::Display for TimingCheckKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            TimingCheckKind::Setup => "setup",
+            TimingCheckKind::Hold => "hold",
+            TimingCheckKind::Recovery => "recovery",
+            TimingCheckKind::Removal => "removal",
+            TimingCheckKind::PulseWidth => "pulse width",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A change between 0 and 1, changes from or to X or Z are not edges.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Edge {
+    Rising,
+    Falling,
+    Any,
+}
+
+/// A timing constraint between a pin and a reference pin of the same device,
+/// see `TimingChecker`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TimingCheck {
+    kind: TimingCheckKind,
+    pin: usize,
+    pin_edge: Edge,
+    reference: usize,
+    reference_edge: Edge,
+    limit: u64,
+}
+
+impl TimingCheck {
+    /// Any change of `data` must come at least `limit` ticks before a rising
+    /// edge of `clock`.
+    pub fn setup(data: usize, clock: usize, limit: u64) -> TimingCheck {
+        return TimingCheck::new(TimingCheckKind::Setup, data, Edge::Any, clock, limit);
+    }
+
+    /// Any change of `data` must come at least `limit` ticks after a rising
+    /// edge of `clock`.
+    pub fn hold(data: usize, clock: usize, limit: u64) -> TimingCheck {
+        return TimingCheck::new(TimingCheckKind::Hold, data, Edge::Any, clock, limit);
+    }
+
+    /// The active-high `control` must fall at least `limit` ticks before a
+    /// rising edge of `clock`.
+    pub fn recovery(control: usize, clock: usize, limit: u64) -> TimingCheck {
+        return TimingCheck::new(
+            TimingCheckKind::Recovery,
+            control,
+            Edge::Falling,
+            clock,
+            limit,
+        );
+    }
+
+    /// The active-high `control` must fall at least `limit` ticks after a
+    /// rising edge of `clock`.
+    pub fn removal(control: usize, clock: usize, limit: u64) -> TimingCheck {
+        return TimingCheck::new(
+            TimingCheckKind::Removal,
+            control,
+            Edge::Falling,
+            clock,
+            limit,
+        );
+    }
+
+    /// A pulse on `pin` starting with `edge`, i.e. a high pulse for a rising
+    /// edge, must last at least `limit` ticks.
+    pub fn pulse_width(pin: usize, edge: Edge, limit: u64) -> TimingCheck {
+        return TimingCheck::new(TimingCheckKind::PulseWidth, pin, edge, pin, limit);
+    }
+
+    fn new(
+        kind: TimingCheckKind,
+        pin: usize,
+        pin_edge: Edge,
+        reference: usize,
+        limit: u64,
+    ) -> TimingCheck {
+        return TimingCheck {
+            kind,
+            pin,
+            pin_edge,
+            reference,
+            reference_edge: Edge::Rising,
+            limit,
+        };
+    }
+
+    pub fn get_kind(&self) -> TimingCheckKind {
+        return self.kind;
+    }
+
+    pub fn get_pin(&self) -> usize {
+        return self.pin;
+    }
+
+    pub fn get_pin_edge(&self) -> Edge {
+        return self.pin_edge;
+    }
+
+    /// Sets the edges of the checked pin that count, e.g. `Rising` for the
+    /// recovery of an active-low control.
+    pub fn set_pin_edge(&mut self, edge: Edge) {
+        self.pin_edge = edge;
+    }
+
+    pub fn get_reference(&self) -> usize {
+        return self.reference;
+    }
+
+    pub fn get_reference_edge(&self) -> Edge {
+        return self.reference_edge;
+    }
+
+    /// Sets the edges of the reference pin that count, e.g. `Falling` for a
+    /// falling-edge clock.
+    pub fn set_reference_edge(&mut self, edge: Edge) {
+        self.reference_edge = edge;
+    }
+
+    pub fn get_limit(&self) -> u64 {
+        return self.limit;
+    }
+}
+
+/// A failed timing check: `pin` of the device missed the check's limit by
+/// `amount` ticks.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TimingViolation {
+    kind: TimingCheckKind,
+    pin: usize,
+    amount: u64,
+}
+
+impl TimingViolation {
+    pub fn new(kind: TimingCheckKind, pin: usize, amount: u64) -> TimingViolation {
+        return TimingViolation { kind, pin, amount };
+    }
+
+    pub fn get_kind(&self) -> TimingCheckKind {
+        return self.kind;
+    }
+
+    pub fn get_pin(&self) -> usize {
+        return self.pin;
+    }
+
+    pub fn get_amount(&self) -> u64 {
+        return self.amount;
+    }
+}
+
+/// Evaluates timing checks for a device. The device passes every input change
+/// to `on_inputs_changed` and returns the violations through
+/// `DeviceResponse::add_timing_violation`, the circuit then reports them as
+/// `SimEvent::TimingViolation`.
+#[derive(Debug, Clone, Default)]
+pub struct TimingChecker {
+    checks: Vec<TimingCheck>,
+    // (pin, value, tick of the last rising edge, tick of the last falling edge)
+    pins: Vec<(usize, LogicValue, Option<u64>, Option<u64>)>,
+}
+
+impl TimingChecker {
+    pub fn new() -> TimingChecker {
+        return TimingChecker::default();
+    }
+
+    pub fn add_check(&mut self, check: TimingCheck) {
+        for pin in [check.pin, check.reference] {
+            if !self.pins.iter().any(|watched| watched.0 == pin) {
+                self.pins.push((pin, LogicValue::X, None, None));
+            }
+        }
+        self.checks.push(check);
+    }
+
+    pub fn get_checks(&self) -> &[TimingCheck] {
+        return &self.checks;
+    }
+
+    /// Records the edges among `pins`, which changed at `tick`, and returns the
+    /// checks they violate.
+    pub fn on_inputs_changed(&mut self, tick: u64, pins: &[PinValue]) -> Vec<TimingViolation> {
+        let mut edges: Vec<(usize, Edge)> = Vec::new();
+        for pin_value in pins {
+            let watched = self
+                .pins
+                .iter_mut()
+                .find(|watched| watched.0 == pin_value.get_pin());
+            if let Some((pin, value, last_rising, last_falling)) = watched {
+                let new_value = pin_value.get_logic_value();
+                match (*value, new_value) {
+                    (LogicValue::Zero, LogicValue::One) => {
+                        *last_rising = Some(tick);
+                        edges.push((*pin, Edge::Rising));
+                    }
+                    (LogicValue::One, LogicValue::Zero) => {
+                        *last_falling = Some(tick);
+                        edges.push((*pin, Edge::Falling));
+                    }
+                    _ => {}
+                }
+                *value = new_value;
+            }
+        }
+
+        let mut violations: Vec<TimingViolation> = Vec::new();
+        for check in &self.checks {
+            let has_edge = |pin: usize, edge: Edge| -> bool {
+                return edges.iter().any(|(edge_pin, kind)| {
+                    *edge_pin == pin && TimingChecker::matches(edge, *kind)
+                });
+            };
+            // the tick of the earlier event the check measures from
+            let since = match check.kind {
+                TimingCheckKind::Setup | TimingCheckKind::Recovery => {
+                    if !has_edge(check.reference, check.reference_edge) {
+                        continue;
+                    }
+                    self.get_last_edge(check.pin, check.pin_edge)
+                }
+                TimingCheckKind::Hold | TimingCheckKind::Removal => {
+                    if !has_edge(check.pin, check.pin_edge) {
+                        continue;
+                    }
+                    self.get_last_edge(check.reference, check.reference_edge)
+                }
+                TimingCheckKind::PulseWidth => {
+                    let end_edge = match check.pin_edge {
+                        Edge::Rising => Edge::Falling,
+                        Edge::Falling => Edge::Rising,
+                        Edge::Any => Edge::Any,
+                    };
+                    if !has_edge(check.pin, end_edge) {
+                        continue;
+                    }
+                    self.get_pulse_start(check.pin, check.pin_edge, tick)
+                }
+            };
+            if let Some(since) = since {
+                let elapsed = tick - since;
+                if elapsed < check.limit {
+                    violations.push(TimingViolation::new(
+                        check.kind,
+                        check.pin,
+                        check.limit - elapsed,
+                    ));
+                }
+            }
+        }
+        return violations;
+    }
+
+    fn matches(wanted: Edge, edge: Edge) -> bool {
+        return wanted == Edge::Any || wanted == edge;
+    }
+
+    fn get_last_edge(&self, pin: usize, edge: Edge) -> Option<u64> {
+        let (_pin, _value, last_rising, last_falling) =
+            self.pins.iter().find(|watched| watched.0 == pin)?;
+        match edge {
+            Edge::Rising => return *last_rising,
+            Edge::Falling => return *last_falling,
+            Edge::Any => return (*last_rising).max(*last_falling),
+        }
+    }
+
+    /// Gets the start of the pulse ending at `tick`, the last `edge` before it.
+    fn get_pulse_start(&self, pin: usize, edge: Edge, tick: u64) -> Option<u64> {
+        let (_pin, _value, last_rising, last_falling) =
+            self.pins.iter().find(|watched| watched.0 == pin)?;
+        let before = |last: &Option<u64>| last.filter(|last| *last < tick);
+        match edge {
+            Edge::Rising => return before(last_rising),
+            Edge::Falling => return before(last_falling),
+            Edge::Any => return before(last_rising).max(before(last_falling)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Edge;
+    use crate::LogicValue;
+    use crate::PinValue;
+    use crate::TimingCheck;
+    use crate::TimingCheckKind;
+    use crate::TimingChecker;
+    use crate::TimingViolation;
+
+    const PIN_D: usize = 1;
+    const PIN_CLK: usize = 2;
+    const PIN_RST: usize = 3;
+
+    fn change(
+        checker: &mut TimingChecker,
+        tick: u64,
+        pin: usize,
+        value: LogicValue,
+    ) -> Vec<TimingViolation> {
+        return checker.on_inputs_changed(tick, &[PinValue::new(pin, value.into())]);
+    }
+
+    #[test]
+    fn setup_and_hold() {
+        let mut checker = TimingChecker::new();
+        checker.add_check(TimingCheck::setup(PIN_D, PIN_CLK, 3));
+        checker.add_check(TimingCheck::hold(PIN_D, PIN_CLK, 2));
+        change(&mut checker, 1, PIN_D, LogicValue::Zero);
+        change(&mut checker, 1, PIN_CLK, LogicValue::Zero);
+        assert!(change(&mut checker, 5, PIN_D, LogicValue::One).is_empty());
+        assert_eq!(
+            vec![TimingViolation::new(TimingCheckKind::Setup, PIN_D, 1)],
+            change(&mut checker, 7, PIN_CLK, LogicValue::One)
+        );
+        assert_eq!(
+            vec![TimingViolation::new(TimingCheckKind::Hold, PIN_D, 1)],
+            change(&mut checker, 8, PIN_D, LogicValue::Zero)
+        );
+        assert!(change(&mut checker, 9, PIN_CLK, LogicValue::Zero).is_empty());
+        assert!(change(&mut checker, 12, PIN_D, LogicValue::One).is_empty());
+        assert!(change(&mut checker, 15, PIN_CLK, LogicValue::One).is_empty());
+    }
+
+    #[test]
+    fn recovery_removal_and_pulse_width() {
+        let mut checker = TimingChecker::new();
+        checker.add_check(TimingCheck::recovery(PIN_RST, PIN_CLK, 2));
+        checker.add_check(TimingCheck::removal(PIN_RST, PIN_CLK, 2));
+        checker.add_check(TimingCheck::pulse_width(PIN_CLK, Edge::Rising, 3));
+        change(&mut checker, 1, PIN_RST, LogicValue::One);
+        change(&mut checker, 1, PIN_CLK, LogicValue::Zero);
+        assert!(change(&mut checker, 4, PIN_CLK, LogicValue::One).is_empty());
+        assert_eq!(
+            vec![TimingViolation::new(TimingCheckKind::Removal, PIN_RST, 1)],
+            change(&mut checker, 5, PIN_RST, LogicValue::Zero)
+        );
+        assert_eq!(
+            vec![TimingViolation::new(
+                TimingCheckKind::PulseWidth,
+                PIN_CLK,
+                1
+            )],
+            change(&mut checker, 6, PIN_CLK, LogicValue::Zero)
+        );
+        assert!(change(&mut checker, 7, PIN_CLK, LogicValue::One).is_empty());
+        // asserting the reset is not checked, only releasing it
+        assert!(change(&mut checker, 8, PIN_RST, LogicValue::One).is_empty());
+        assert!(change(&mut checker, 9, PIN_RST, LogicValue::Zero).is_empty());
+        assert!(change(&mut checker, 10, PIN_CLK, LogicValue::Zero).is_empty());
+        change(&mut checker, 11, PIN_RST, LogicValue::One);
+        change(&mut checker, 12, PIN_RST, LogicValue::Zero);
+        assert_eq!(
+            vec![TimingViolation::new(TimingCheckKind::Recovery, PIN_RST, 1)],
+            change(&mut checker, 13, PIN_CLK, LogicValue::One)
+        );
+    }
+}
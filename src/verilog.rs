@@ -0,0 +1,1362 @@
+use crate::device::LogicGate;
+use crate::BusValue;
+use crate::DeviceParams;
+use crate::LogicValue;
+use crate::Netlist;
+use crate::NetlistAssembler;
+use crate::NetlistParseError;
+use crate::PinDirection;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Gate primitives and the device types they become, their output is the
+/// first terminal.
+pub(crate) const PRIMITIVES: [(&str, &str); 9] = [
+    ("and", "and"),
+    ("or", "or"),
+    ("nand", "nand"),
+    ("nor", "nor"),
+    ("xor", "xor"),
+    ("xnor", "xnor"),
+    ("not", "not"),
+    ("buf", "buf"),
+    ("bufif1", "tri_state_buffer"),
+];
+
+/// Keywords outside of the structural subset, including the gate primitives
+/// without a device.
+const UNSUPPORTED: [&str; 31] = [
+    "reg",
+    "always",
+    "initial",
+    "parameter",
+    "localparam",
+    "generate",
+    "function",
+    "task",
+    "integer",
+    "specify",
+    "supply0",
+    "supply1",
+    "tri",
+    "defparam",
+    "bufif0",
+    "notif0",
+    "notif1",
+    "pullup",
+    "pulldown",
+    "cmos",
+    "rcmos",
+    "nmos",
+    "pmos",
+    "rnmos",
+    "rpmos",
+    "tran",
+    "tranif0",
+    "tranif1",
+    "rtran",
+    "rtranif0",
+    "rtranif1",
+];
+
+/// How deep concatenations may be nested, so that deeply nested text is an
+/// error rather than a stack overflow.
+const MAX_DEPTH: usize = 128;
+
+impl Netlist {
+    /// Imports structural Verilog, as written by synthesis tools: modules with
+    /// `input`, `output`, `inout` and `wire` declarations, the gate primitives
+    /// `and`, `or`, `nand`, `nor`, `xor`, `xnor`, `not`, `buf` and `bufif1`,
+    /// module instances, `assign` and vector selects and concatenations.
+    ///
+    /// `top` is the module to import, by default the only module not
+    /// instantiated by another. Its ports become test probes named after them,
+    /// inputs drive their wire and outputs sample it. The other modules become
+    /// netlist modules whose ports are backed by test probes the same way, so
+    /// `u1.a` is port `a` of instance `u1` once built. Instances of modules not
+    /// defined in `text` are created by the registry and must connect their
+    /// ports by name, their parameters, e.g. `#(.width(4))`, become device
+    /// parameters and sized numbers become their bits, so `#(.table(4'b0110))`
+    /// is `table=0110`. `assign` becomes a `buf` per bit and constants become
+    /// output test probes called `$const<n>`.
+    pub fn from_verilog(text: &str, top: Option<&str>) -> Result<Netlist, NetlistParseError> {
+        let modules = Parser::new(text)?.parse_modules()?;
+        let first_line = modules.first().map(|module| module.line).unwrap_or(1);
+        let top = match top {
+            Some(top) => modules
+                .iter()
+                .find(|module| module.name == top)
+                .ok_or_else(|| {
+                    NetlistParseError::new(first_line, &format!("module {} is not defined", top))
+                })?,
+            None => {
+                let candidates: Vec<&VerilogModule> = modules
+                    .iter()
+                    .filter(|module| {
+                        !modules.iter().any(|other| {
+                            other
+                                .instances
+                                .iter()
+                                .any(|instance| instance.type_name == module.name)
+                        })
+                    })
+                    .collect();
+                match candidates[..] {
+                    [top] => top,
+                    [] => return Err(NetlistParseError::new(first_line, "no top module found")),
+                    _ => {
+                        let names: Vec<&str> = candidates
+                            .iter()
+                            .map(|module| module.name.as_str())
+                            .collect();
+                        return Err(NetlistParseError::new(
+                            first_line,
+                            &format!("choose the top module from {}", names.join(", ")),
+                        ));
+                    }
+                }
+            }
+        };
+        let mut netlist = Elaborator::new(&modules).elaborate(top, true)?;
+        for module in &modules {
+            if module.name != top.name {
+                netlist.add_module(Elaborator::new(&modules).elaborate(module, false)?);
+            }
+        }
+        return Ok(netlist);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Identifier(String),
+    /// An escaped identifier, which is never a keyword, without its `\`.
+    Escaped(String),
+    Number(String),
+    String(String),
+    Symbol(char),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Identifier(text) | Token::Number(text) => write!(f, "{}", text),
+            Token::Escaped(text) => write!(f, "\\{}", text),
+            Token::String(text) => write!(f, "\"{}\"", text),
+            Token::Symbol(symbol) => write!(f, "{}", symbol),
+        }
+    }
+}
+
+/// A wire or port, `range` is the declared `[msb:lsb]`.
+#[derive(Debug)]
+struct Wire {
+    name: String,
+    direction: Option<PinDirection>,
+    range: Option<(i64, i64)>,
+    line: usize,
+}
+
+impl Wire {
+    fn get_width(&self) -> usize {
+        match self.range {
+            Some((msb, lsb)) => return msb.abs_diff(lsb) as usize + 1,
+            None => return 1,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Expression {
+    /// A wire, all of it or the bits from `select.0` to `select.1`.
+    Wire {
+        name: String,
+        select: Option<(i64, i64)>,
+        line: usize,
+    },
+    /// Bits least significant first, `sized` is false for plain numbers.
+    Constant {
+        bits: Vec<LogicValue>,
+        sized: bool,
+        line: usize,
+    },
+    /// Parts most significant first.
+    Concatenation { parts: Vec<Expression>, line: usize },
+}
+
+impl Expression {
+    fn get_line(&self) -> usize {
+        match self {
+            Expression::Wire { line, .. }
+            | Expression::Constant { line, .. }
+            | Expression::Concatenation { line, .. } => return *line,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Connections {
+    Ordered(Vec<Option<Expression>>),
+    Named(Vec<(String, Option<Expression>)>),
+}
+
+#[derive(Debug)]
+struct Instance {
+    type_name: String,
+    name: Option<String>,
+    params: DeviceParams,
+    connections: Connections,
+    line: usize,
+}
+
+#[derive(Debug)]
+struct VerilogModule {
+    name: String,
+    // port names in the order of the module header
+    ports: Vec<String>,
+    wires: Vec<Wire>,
+    instances: Vec<Instance>,
+    // (left hand side, right hand side)
+    assigns: Vec<(Expression, Expression)>,
+    line: usize,
+}
+
+impl VerilogModule {
+    fn get_wire(&self, name: &str) -> Option<&Wire> {
+        return self.wires.iter().find(|wire| wire.name == name);
+    }
+
+    /// Declares a wire or adds a direction or range to one already declared,
+    /// e.g. `output [3:0] y; wire [3:0] y;`.
+    fn declare(
+        &mut self,
+        name: &str,
+        direction: Option<PinDirection>,
+        range: Option<(i64, i64)>,
+        line: usize,
+    ) -> Result<(), NetlistParseError> {
+        if let Some((msb, lsb)) = range {
+            if msb.abs_diff(lsb) >= BusValue::MAX_WIDTH as u64 {
+                return Err(NetlistParseError::new(
+                    line,
+                    &format!("{} is wider than {} bits", name, BusValue::MAX_WIDTH),
+                ));
+            }
+        }
+        let Some(wire) = self.wires.iter_mut().find(|wire| wire.name == name) else {
+            self.wires.push(Wire {
+                name: name.to_string(),
+                direction,
+                range,
+                line,
+            });
+            return Ok(());
+        };
+        if (direction.is_some() && wire.direction.is_some())
+            || (range.is_some() && wire.range.is_some() && range != wire.range)
+        {
+            return Err(NetlistParseError::new(
+                line,
+                &format!("{} is already declared", name),
+            ));
+        }
+        wire.direction = wire.direction.or(direction);
+        wire.range = wire.range.or(range);
+        return Ok(());
+    }
+}
+
+struct Parser {
+    // each token with its line
+    tokens: Vec<(Token, usize)>,
+    position: usize,
+    // the concatenations the parser is in
+    depth: usize,
+}
+
+impl Parser {
+    fn new(text: &str) -> Result<Parser, NetlistParseError> {
+        let chars: Vec<char> = text.chars().collect();
+        let mut tokens: Vec<(Token, usize)> = Vec::new();
+        let mut line = 1;
+        let mut i = 0;
+        while i < chars.len() {
+            let c = chars[i];
+            let next = chars.get(i + 1).copied();
+            if c == '\n' {
+                line += 1;
+                i += 1;
+            } else if c.is_whitespace() {
+                i += 1;
+            } else if c == '/' && next == Some('/') {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            } else if (c == '/' || c == '(') && next == Some('*') {
+                // block comments and (* attributes *) are skipped alike
+                let end = if c == '/' { '/' } else { ')' };
+                let start_line = line;
+                i += 2;
+                loop {
+                    if i + 1 >= chars.len() {
+                        return Err(NetlistParseError::new(
+                            start_line,
+                            "comment or attribute is not closed",
+                        ));
+                    }
+                    if chars[i] == '\n' {
+                        line += 1;
+                    }
+                    if chars[i] == '*' && chars[i + 1] == end {
+                        i += 2;
+                        break;
+                    }
+                    i += 1;
+                }
+            } else if c == '\\' {
+                // escaped identifiers run to the next white space
+                let start = i + 1;
+                i = start;
+                while i < chars.len() && !chars[i].is_whitespace() {
+                    i += 1;
+                }
+                let name: String = chars[start..i].iter().collect();
+                tokens.push((Token::Escaped(name), line));
+            } else if c.is_ascii_alphabetic() || c == '_' {
+                let start = i;
+                while i < chars.len()
+                    && (chars[i].is_ascii_alphanumeric() || chars[i] == '_' || chars[i] == '$')
+                {
+                    i += 1;
+                }
+                tokens.push((Token::Identifier(chars[start..i].iter().collect()), line));
+            } else if c.is_ascii_digit() || c == '\'' {
+                let mut number = String::new();
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '_') {
+                    number.push(chars[i]);
+                    i += 1;
+                }
+                if chars.get(i) == Some(&'\'') {
+                    number.push('\'');
+                    i += 1;
+                    while i < chars.len()
+                        && (chars[i].is_ascii_alphanumeric() || chars[i] == '_' || chars[i] == '?')
+                    {
+                        number.push(chars[i]);
+                        i += 1;
+                    }
+                }
+                tokens.push((Token::Number(number), line));
+            } else if c == '"' {
+                let start_line = line;
+                let mut text = String::new();
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        None | Some('\n') => {
+                            return Err(NetlistParseError::new(start_line, "string is not closed"))
+                        }
+                        Some('"') => break,
+                        Some('\\') if i + 1 < chars.len() => {
+                            text.push(chars[i + 1]);
+                            i += 2;
+                        }
+                        Some(c) => {
+                            text.push(*c);
+                            i += 1;
+                        }
+                    }
+                }
+                i += 1;
+                tokens.push((Token::String(text), line));
+            } else if c.is_ascii_punctuation() {
+                tokens.push((Token::Symbol(c), line));
+                i += 1;
+            } else {
+                return Err(NetlistParseError::new(
+                    line,
+                    &format!("unexpected character {}", c),
+                ));
+            }
+        }
+        return Ok(Parser {
+            tokens,
+            position: 0,
+            depth: 0,
+        });
+    }
+
+    fn get_line(&self) -> usize {
+        return self
+            .tokens
+            .get(self.position)
+            .or(self.tokens.last())
+            .map(|(_, line)| *line)
+            .unwrap_or(1);
+    }
+
+    fn error(&self, message: &str) -> NetlistParseError {
+        return NetlistParseError::new(self.get_line(), message);
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        return self.tokens.get(self.position).map(|(token, _)| token);
+    }
+
+    fn is_symbol(&self, symbol: char) -> bool {
+        return self.peek() == Some(&Token::Symbol(symbol));
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        return matches!(self.peek(), Some(Token::Identifier(word)) if word == keyword);
+    }
+
+    fn next(&mut self) -> Result<Token, NetlistParseError> {
+        let token = self
+            .peek()
+            .cloned()
+            .ok_or_else(|| self.error("unexpected end of file"))?;
+        self.position += 1;
+        return Ok(token);
+    }
+
+    /// Consumes `symbol` if it is next.
+    fn take_symbol(&mut self, symbol: char) -> bool {
+        if self.is_symbol(symbol) {
+            self.position += 1;
+            return true;
+        }
+        return false;
+    }
+
+    /// Consumes `keyword` if it is next.
+    fn take_keyword(&mut self, keyword: &str) -> bool {
+        if self.is_keyword(keyword) {
+            self.position += 1;
+            return true;
+        }
+        return false;
+    }
+
+    fn take_direction(&mut self) -> Option<PinDirection> {
+        let direction = match self.peek() {
+            Some(Token::Identifier(word)) if word == "input" => PinDirection::Input,
+            Some(Token::Identifier(word)) if word == "output" => PinDirection::Output,
+            Some(Token::Identifier(word)) if word == "inout" => PinDirection::InOut,
+            _ => return None,
+        };
+        self.position += 1;
+        return Some(direction);
+    }
+
+    fn expect_symbol(&mut self, symbol: char) -> Result<(), NetlistParseError> {
+        let line = self.get_line();
+        let token = self.next()?;
+        if token != Token::Symbol(symbol) {
+            return Err(NetlistParseError::new(
+                line,
+                &format!("expected {}, got {}", symbol, token),
+            ));
+        }
+        return Ok(());
+    }
+
+    fn expect_identifier(&mut self) -> Result<String, NetlistParseError> {
+        let line = self.get_line();
+        match self.next()? {
+            Token::Identifier(name) | Token::Escaped(name) => return Ok(name),
+            token => {
+                return Err(NetlistParseError::new(
+                    line,
+                    &format!("expected a name, got {}", token),
+                ))
+            }
+        }
+    }
+
+    fn expect_index(&mut self) -> Result<i64, NetlistParseError> {
+        let line = self.get_line();
+        let token = self.next()?;
+        if let Token::Number(number) = &token {
+            if let Ok(index) = number.replace('_', "").parse() {
+                return Ok(index);
+            }
+        }
+        return Err(NetlistParseError::new(
+            line,
+            &format!("expected a bit index, got {}", token),
+        ));
+    }
+
+    fn parse_modules(&mut self) -> Result<Vec<VerilogModule>, NetlistParseError> {
+        let mut modules: Vec<VerilogModule> = Vec::new();
+        while self.peek().is_some() {
+            if !self.is_keyword("module") {
+                let line = self.get_line();
+                let token = self.next()?;
+                return Err(NetlistParseError::new(
+                    line,
+                    &format!("expected module, got {}", token),
+                ));
+            }
+            let module = self.parse_module()?;
+            if modules.iter().any(|other| other.name == module.name) {
+                return Err(NetlistParseError::new(
+                    module.line,
+                    &format!("module {} is defined twice", module.name),
+                ));
+            }
+            modules.push(module);
+        }
+        if modules.is_empty() {
+            return Err(self.error("no module found"));
+        }
+        return Ok(modules);
+    }
+
+    fn parse_module(&mut self) -> Result<VerilogModule, NetlistParseError> {
+        let line = self.get_line();
+        self.position += 1;
+        let mut module = VerilogModule {
+            name: self.expect_identifier()?,
+            ports: Vec::new(),
+            wires: Vec::new(),
+            instances: Vec::new(),
+            assigns: Vec::new(),
+            line,
+        };
+        if self.is_symbol('#') {
+            return Err(self.error("module parameters are not supported"));
+        }
+        if self.take_symbol('(') && !self.take_symbol(')') {
+            // the direction and range of ANSI style ports carry over to the
+            // names that follow until the next direction
+            let mut declaration: Option<(PinDirection, Option<(i64, i64)>)> = None;
+            loop {
+                let line = self.get_line();
+                if let Some(direction) = self.take_direction() {
+                    self.take_keyword("wire");
+                    declaration = Some((direction, self.parse_range()?));
+                }
+                let name = self.expect_identifier()?;
+                if let Some((direction, range)) = declaration {
+                    module.declare(&name, Some(direction), range, line)?;
+                }
+                module.ports.push(name);
+                if self.take_symbol(')') {
+                    break;
+                }
+                self.expect_symbol(',')?;
+            }
+        }
+        self.expect_symbol(';')?;
+        loop {
+            let line = self.get_line();
+            let token = self.next()?;
+            let word = match token {
+                Token::Identifier(word) => word,
+                // an instance of a module with an escaped name
+                Token::Escaped(type_name) => {
+                    self.parse_instances(&type_name, &mut module)?;
+                    continue;
+                }
+                token => {
+                    return Err(NetlistParseError::new(
+                        line,
+                        &format!("unexpected {}", token),
+                    ))
+                }
+            };
+            if word == "endmodule" {
+                break;
+            }
+            self.position -= 1;
+            if let Some(direction) = self.take_direction() {
+                self.take_keyword("wire");
+                let range = self.parse_range()?;
+                for name in self.parse_names()? {
+                    module.declare(&name, Some(direction), range, line)?;
+                }
+            } else if self.take_keyword("wire") {
+                let range = self.parse_range()?;
+                for name in self.parse_names()? {
+                    module.declare(&name, None, range, line)?;
+                }
+            } else if self.take_keyword("assign") {
+                loop {
+                    let left = self.parse_expression()?;
+                    self.expect_symbol('=')?;
+                    let right = self.parse_expression()?;
+                    module.assigns.push((left, right));
+                    if !self.take_symbol(',') {
+                        break;
+                    }
+                }
+                self.expect_symbol(';')?;
+            } else if UNSUPPORTED.contains(&word.as_str()) || word == "module" {
+                return Err(NetlistParseError::new(
+                    line,
+                    &format!("{} is not supported in structural verilog", word),
+                ));
+            } else {
+                self.position += 1;
+                self.parse_instances(&word, &mut module)?;
+            }
+        }
+        for wire in &module.wires {
+            if wire.direction.is_some() && !module.ports.contains(&wire.name) {
+                return Err(NetlistParseError::new(
+                    wire.line,
+                    &format!("{} is not a port of module {}", wire.name, module.name),
+                ));
+            }
+        }
+        return Ok(module);
+    }
+
+    /// Parses an optional `[msb:lsb]`.
+    fn parse_range(&mut self) -> Result<Option<(i64, i64)>, NetlistParseError> {
+        if !self.take_symbol('[') {
+            return Ok(None);
+        }
+        let msb = self.expect_index()?;
+        self.expect_symbol(':')?;
+        let lsb = self.expect_index()?;
+        self.expect_symbol(']')?;
+        return Ok(Some((msb, lsb)));
+    }
+
+    /// Parses `name, name, ... ;`.
+    fn parse_names(&mut self) -> Result<Vec<String>, NetlistParseError> {
+        let mut names = vec![self.expect_identifier()?];
+        while self.take_symbol(',') {
+            names.push(self.expect_identifier()?);
+        }
+        self.expect_symbol(';')?;
+        return Ok(names);
+    }
+
+    /// Parses the instances of `type_name` up to the closing `;`.
+    fn parse_instances(
+        &mut self,
+        type_name: &str,
+        module: &mut VerilogModule,
+    ) -> Result<(), NetlistParseError> {
+        let mut params = DeviceParams::new();
+        if self.is_symbol('#') {
+            if PRIMITIVES
+                .iter()
+                .any(|(primitive, _)| *primitive == type_name)
+            {
+                return Err(self.error("parameters and delays are not supported"));
+            }
+            params = self.parse_params()?;
+        }
+        loop {
+            let line = self.get_line();
+            let name = match self.peek() {
+                Some(Token::Identifier(_) | Token::Escaped(_)) => Some(self.expect_identifier()?),
+                _ => None,
+            };
+            if self.is_symbol('[') {
+                return Err(self.error("arrays of instances are not supported"));
+            }
+            self.expect_symbol('(')?;
+            let connections = self.parse_connections()?;
+            module.instances.push(Instance {
+                type_name: type_name.to_string(),
+                name,
+                params: params.clone(),
+                connections,
+                line,
+            });
+            if !self.take_symbol(',') {
+                break;
+            }
+        }
+        return self.expect_symbol(';');
+    }
+
+    /// Parses the parameters of an instance, `#(.name(value), ...)`, whose
+    /// values are numbers or strings.
+    fn parse_params(&mut self) -> Result<DeviceParams, NetlistParseError> {
+        let mut params = DeviceParams::new();
+        self.expect_symbol('#')?;
+        self.expect_symbol('(')?;
+        if self.take_symbol(')') {
+            return Ok(params);
+        }
+        loop {
+            if !self.is_symbol('.') {
+                return Err(self.error("parameters must be given by name"));
+            }
+            self.position += 1;
+            let name = self.expect_identifier()?;
+            self.expect_symbol('(')?;
+            let line = self.get_line();
+            let value = match self.next()? {
+                Token::String(text) => text,
+                Token::Number(number) if !number.contains('\'') => number.replace('_', ""),
+                // bit patterns, e.g. 4'b0110, become their bits as in netlist files
+                Token::Number(number) => match parse_number(&number) {
+                    Some((bits, _)) => bits.iter().rev().map(|bit| bit.to_string()).collect(),
+                    None => {
+                        return Err(NetlistParseError::new(
+                            line,
+                            &format!("invalid number {}", number),
+                        ))
+                    }
+                },
+                token => {
+                    return Err(NetlistParseError::new(
+                        line,
+                        &format!("expected a number or a string, got {}", token),
+                    ))
+                }
+            };
+            self.expect_symbol(')')?;
+            params.set(&name, &value);
+            if self.take_symbol(')') {
+                return Ok(params);
+            }
+            self.expect_symbol(',')?;
+        }
+    }
+
+    /// Parses the connections of an instance after its `(`.
+    fn parse_connections(&mut self) -> Result<Connections, NetlistParseError> {
+        if self.take_symbol(')') {
+            return Ok(Connections::Ordered(Vec::new()));
+        }
+        if self.is_symbol('.') {
+            let mut connections: Vec<(String, Option<Expression>)> = Vec::new();
+            loop {
+                self.expect_symbol('.')?;
+                let port = self.expect_identifier()?;
+                self.expect_symbol('(')?;
+                let expression = if self.is_symbol(')') {
+                    None
+                } else {
+                    Some(self.parse_expression()?)
+                };
+                self.expect_symbol(')')?;
+                connections.push((port, expression));
+                if self.take_symbol(')') {
+                    return Ok(Connections::Named(connections));
+                }
+                self.expect_symbol(',')?;
+            }
+        }
+        let mut connections: Vec<Option<Expression>> = Vec::new();
+        loop {
+            if self.is_symbol(',') || self.is_symbol(')') {
+                connections.push(None);
+            } else {
+                connections.push(Some(self.parse_expression()?));
+            }
+            if self.take_symbol(')') {
+                return Ok(Connections::Ordered(connections));
+            }
+            self.expect_symbol(',')?;
+        }
+    }
+
+    fn parse_expression(&mut self) -> Result<Expression, NetlistParseError> {
+        let line = self.get_line();
+        match self.next()? {
+            Token::Symbol('{') => {
+                if matches!(self.peek(), Some(Token::Number(_)))
+                    && self.tokens.get(self.position + 1).map(|(token, _)| token)
+                        == Some(&Token::Symbol('{'))
+                {
+                    return Err(NetlistParseError::new(line, "replication is not supported"));
+                }
+                self.depth += 1;
+                if self.depth > MAX_DEPTH {
+                    return Err(NetlistParseError::new(
+                        line,
+                        &format!("concatenations are nested more than {} deep", MAX_DEPTH),
+                    ));
+                }
+                let mut parts = vec![self.parse_expression()?];
+                while self.take_symbol(',') {
+                    parts.push(self.parse_expression()?);
+                }
+                self.expect_symbol('}')?;
+                self.depth -= 1;
+                return Ok(Expression::Concatenation { parts, line });
+            }
+            Token::Number(number) => {
+                let (bits, sized) = parse_number(&number).ok_or_else(|| {
+                    NetlistParseError::new(line, &format!("invalid number {}", number))
+                })?;
+                return Ok(Expression::Constant { bits, sized, line });
+            }
+            Token::Identifier(name) | Token::Escaped(name) => {
+                let mut select = None;
+                if self.take_symbol('[') {
+                    let msb = self.expect_index()?;
+                    let lsb = if self.take_symbol(':') {
+                        self.expect_index()?
+                    } else {
+                        msb
+                    };
+                    self.expect_symbol(']')?;
+                    select = Some((msb, lsb));
+                }
+                return Ok(Expression::Wire { name, select, line });
+            }
+            token => {
+                return Err(NetlistParseError::new(
+                    line,
+                    &format!("expected a wire or a number, got {}", token),
+                ))
+            }
+        }
+    }
+}
+
+/// Parses a number like `0`, `4'b10x1` or `8'hff` into its bits, least
+/// significant first, and whether it has a size.
+fn parse_number(text: &str) -> Option<(Vec<LogicValue>, bool)> {
+    let text = text.replace('_', "");
+    let Some((size, value)) = text.split_once('\'') else {
+        let value: u64 = text.parse().ok()?;
+        let width = (64 - value.leading_zeros() as usize).max(1);
+        return Some((from_u64(width, value), false));
+    };
+    let value = value.strip_prefix(['s', 'S']).unwrap_or(value);
+    let mut chars = value.chars();
+    let base = chars.next()?.to_ascii_lowercase();
+    let digits: Vec<char> = chars.collect();
+    if digits.is_empty() {
+        return None;
+    }
+    let mut bits: Vec<LogicValue> = Vec::new();
+    if base == 'd' {
+        let digits: String = digits.iter().collect();
+        let value: u64 = digits.parse().ok()?;
+        bits = from_u64(64, value);
+    } else {
+        let digit_bits = match base {
+            'b' => 1,
+            'o' => 3,
+            'h' => 4,
+            _ => return None,
+        };
+        for digit in digits.iter().rev() {
+            let digit = digit.to_ascii_lowercase();
+            match digit {
+                'x' => bits.extend(vec![LogicValue::X; digit_bits]),
+                'z' | '?' => bits.extend(vec![LogicValue::Z; digit_bits]),
+                _ => {
+                    let value = digit.to_digit(1 << digit_bits)? as u64;
+                    bits.extend(from_u64(digit_bits, value));
+                }
+            }
+        }
+    }
+    if size.is_empty() {
+        return Some((bits, false));
+    }
+    let size: usize = size.parse().ok()?;
+    if size == 0 || size > BusValue::MAX_WIDTH {
+        return None;
+    }
+    resize(&mut bits, size);
+    return Some((bits, true));
+}
+
+fn from_u64(width: usize, value: u64) -> Vec<LogicValue> {
+    return (0..width)
+        .map(|bit| LogicValue::from_bool(bit < 64 && (value >> bit) & 1 == 1))
+        .collect();
+}
+
+/// Truncates or extends `bits` like Verilog does, with zeros unless the most
+/// significant bit is X or Z.
+fn resize(bits: &mut Vec<LogicValue>, width: usize) {
+    let fill = match bits.last() {
+        Some(LogicValue::X) => LogicValue::X,
+        Some(LogicValue::Z) => LogicValue::Z,
+        _ => LogicValue::Zero,
+    };
+    bits.resize(width, fill);
+}
+
+/// Gets the net bit of Verilog index `index` of a wire declared `[msb:lsb]`.
+fn get_bit((msb, lsb): (i64, i64), index: i64) -> Option<usize> {
+    if index < msb.min(lsb) || index > msb.max(lsb) {
+        return None;
+    }
+    return Some((index - lsb).unsigned_abs() as usize);
+}
+
+/// Turns a parsed module into a netlist module.
+struct Elaborator<'a> {
+    modules: &'a [VerilogModule],
+    assembler: NetlistAssembler,
+    // (net, declared [msb:lsb]) by wire name
+    wires: HashMap<String, (usize, (i64, i64))>,
+}
+
+impl<'a> Elaborator<'a> {
+    fn new(modules: &'a [VerilogModule]) -> Elaborator<'a> {
+        return Elaborator {
+            modules,
+            assembler: NetlistAssembler::new(None),
+            wires: HashMap::new(),
+        };
+    }
+
+    fn elaborate(
+        mut self,
+        module: &VerilogModule,
+        top: bool,
+    ) -> Result<Netlist, NetlistParseError> {
+        if !top {
+            self.assembler = NetlistAssembler::new(Some(&module.name));
+        }
+        for wire in &module.wires {
+            self.add_wire(&wire.name, wire.range.unwrap_or((0, 0)));
+        }
+        for port in &module.ports {
+            let (wire, direction) = module
+                .get_wire(port)
+                .and_then(|wire| wire.direction.map(|direction| (wire, direction)))
+                .ok_or_else(|| {
+                    NetlistParseError::new(
+                        module.line,
+                        &format!("port {} of module {} has no direction", port, module.name),
+                    )
+                })?;
+            let net = self.wires[port].0;
+            self.assembler.add_port(port, direction, net, wire.line);
+        }
+        for instance in &module.instances {
+            if let Some((_, type_name)) = PRIMITIVES
+                .iter()
+                .find(|(primitive, _)| *primitive == instance.type_name)
+            {
+                self.add_primitive(instance, type_name)?;
+            } else {
+                self.add_instance(instance)?;
+            }
+        }
+        for (left, right) in &module.assigns {
+            let left_bits = self.resolve(left, None)?;
+            let right_bits = self.resolve(right, Some(left_bits.len()))?;
+            Elaborator::check_width(right, left_bits.len(), right_bits.len())?;
+            for (left_bit, right_bit) in left_bits.into_iter().zip(right_bits) {
+                let name = self.assembler.generate_name("assign");
+                self.assembler
+                    .add_device(&name, "buf", DeviceParams::new(), left.get_line());
+                self.assembler.connect(&format!("{}.A", name), &[right_bit]);
+                self.assembler.connect(&format!("{}.Y", name), &[left_bit]);
+            }
+        }
+        return Ok(self.assembler.finish());
+    }
+
+    /// Adds a net for a wire, whose range `declare` checked.
+    fn add_wire(&mut self, name: &str, range: (i64, i64)) -> usize {
+        let width = range.0.abs_diff(range.1) as usize + 1;
+        let net = self.assembler.add_net(name, width);
+        self.wires.insert(name.to_string(), (net, range));
+        return net;
+    }
+
+    fn add_primitive(
+        &mut self,
+        instance: &Instance,
+        type_name: &str,
+    ) -> Result<(), NetlistParseError> {
+        let error = |message: String| NetlistParseError::new(instance.line, &message);
+        let Connections::Ordered(terminals) = &instance.connections else {
+            return Err(error(format!(
+                "{} takes its terminals in order",
+                instance.type_name
+            )));
+        };
+        // the inputs of gates that take any number of them are A, B, C, ...
+        let fixed_inputs: Option<&[&str]> = match instance.type_name.as_str() {
+            "not" | "buf" => Some(&["A"]),
+            "bufif1" => Some(&["A", "EN"]),
+            _ => None,
+        };
+        let input_count = terminals.len().saturating_sub(1);
+        if input_count == 0
+            || input_count > LogicGate::MAX_INPUTS
+            || fixed_inputs.is_some_and(|inputs| inputs.len() != input_count)
+        {
+            return Err(error(format!(
+                "wrong number of terminals for {}",
+                instance.type_name
+            )));
+        }
+        let name = match &instance.name {
+            Some(name) => name.clone(),
+            None => self.assembler.generate_name(&instance.type_name),
+        };
+        let mut params = DeviceParams::new();
+        if fixed_inputs.is_none() && input_count != 2 {
+            params.set("inputs", &input_count.to_string());
+        }
+        self.assembler
+            .add_device(&name, type_name, params, instance.line);
+        for (terminal, expression) in terminals.iter().enumerate() {
+            let Some(expression) = expression else {
+                return Err(error(format!(
+                    "terminal {} of {} is not connected",
+                    terminal + 1,
+                    name
+                )));
+            };
+            let bits = self.resolve(expression, Some(1))?;
+            Elaborator::check_width(expression, 1, bits.len())?;
+            let pin = match (terminal, fixed_inputs) {
+                (0, _) => "Y".to_string(),
+                (_, Some(inputs)) => inputs[terminal - 1].to_string(),
+                (_, None) => char::from(b'A' + (terminal - 1) as u8).to_string(),
+            };
+            self.assembler.connect(&format!("{}.{}", name, pin), &bits);
+        }
+        return Ok(());
+    }
+
+    fn add_instance(&mut self, instance: &Instance) -> Result<(), NetlistParseError> {
+        let error = |message: String| NetlistParseError::new(instance.line, &message);
+        let Some(name) = &instance.name else {
+            return Err(error(format!(
+                "instance of {} has no name",
+                instance.type_name
+            )));
+        };
+        let module = self
+            .modules
+            .iter()
+            .find(|module| module.name == instance.type_name);
+        let connections: Vec<(&str, &Expression)> = match (&instance.connections, module) {
+            (Connections::Named(connections), _) => connections
+                .iter()
+                .filter_map(|(port, expression)| {
+                    expression
+                        .as_ref()
+                        .map(|expression| (port.as_str(), expression))
+                })
+                .collect(),
+            (Connections::Ordered(connections), Some(module)) => {
+                if connections.len() > module.ports.len() {
+                    return Err(error(format!(
+                        "module {} has {} ports",
+                        module.name,
+                        module.ports.len()
+                    )));
+                }
+                module
+                    .ports
+                    .iter()
+                    .zip(connections)
+                    .filter_map(|(port, expression)| {
+                        expression
+                            .as_ref()
+                            .map(|expression| (port.as_str(), expression))
+                    })
+                    .collect()
+            }
+            (Connections::Ordered(_), None) => {
+                return Err(error(format!(
+                    "{} is not defined here, its ports must be connected by name",
+                    instance.type_name
+                )))
+            }
+        };
+        if let (Some(module), false) = (module, instance.params.is_empty()) {
+            return Err(error(format!("module {} takes no parameters", module.name)));
+        }
+        self.assembler.add_device(
+            name,
+            &instance.type_name,
+            instance.params.clone(),
+            instance.line,
+        );
+        for (port, expression) in connections {
+            let pin_name = format!("{}.{}", name, port);
+            let Some(module) = module else {
+                let bits = self.resolve(expression, None)?;
+                if bits.len() > BusValue::MAX_WIDTH {
+                    return Err(NetlistParseError::new(
+                        expression.get_line(),
+                        &format!(
+                            "expected at most {} bits, got {}",
+                            BusValue::MAX_WIDTH,
+                            bits.len()
+                        ),
+                    ));
+                }
+                self.assembler.connect(&pin_name, &bits);
+                continue;
+            };
+            let width = module
+                .get_wire(port)
+                .filter(|_| module.ports.iter().any(|other| other == port))
+                .ok_or_else(|| error(format!("module {} has no port {}", module.name, port)))?
+                .get_width();
+            let bits = self.resolve(expression, Some(width))?;
+            Elaborator::check_width(expression, width, bits.len())?;
+            // a port is merged with the net outside, so it cannot be split
+            if self.assembler.connect(&pin_name, &bits) > 1 {
+                return Err(NetlistParseError::new(
+                    expression.get_line(),
+                    &format!(
+                        "port {} of {} must connect to one wire or part of one",
+                        port, name
+                    ),
+                ));
+            }
+        }
+        return Ok(());
+    }
+
+    fn check_width(
+        expression: &Expression,
+        expected: usize,
+        actual: usize,
+    ) -> Result<(), NetlistParseError> {
+        if expected != actual {
+            return Err(NetlistParseError::new(
+                expression.get_line(),
+                &format!("expected {} bits, got {}", expected, actual),
+            ));
+        }
+        return Ok(());
+    }
+
+    /// Gets the `(net, bit)` of every bit of `expression`, least significant
+    /// first. Plain numbers are sized to `width` if given.
+    fn resolve(
+        &mut self,
+        expression: &Expression,
+        width: Option<usize>,
+    ) -> Result<Vec<(usize, usize)>, NetlistParseError> {
+        match expression {
+            Expression::Wire { name, select, line } => {
+                let (net, range) = match (self.wires.get(name), select) {
+                    (Some(wire), _) => *wire,
+                    // undeclared names are implicit one bit wires
+                    (None, None) => (self.add_wire(name, (0, 0)), (0, 0)),
+                    (None, Some(_)) => {
+                        return Err(NetlistParseError::new(
+                            *line,
+                            &format!("{} is not declared", name),
+                        ))
+                    }
+                };
+                let (msb, lsb) = select.unwrap_or(range);
+                let step = if msb >= lsb { 1 } else { -1 };
+                let mut bits: Vec<(usize, usize)> = Vec::new();
+                let mut index = lsb;
+                loop {
+                    let bit = get_bit(range, index).ok_or_else(|| {
+                        NetlistParseError::new(
+                            *line,
+                            &format!("{}[{}] is out of range", name, index),
+                        )
+                    })?;
+                    bits.push((net, bit));
+                    if index == msb {
+                        return Ok(bits);
+                    }
+                    index += step;
+                }
+            }
+            Expression::Constant { bits, sized, line } => {
+                let mut bits = bits.clone();
+                if let (false, Some(width)) = (sized, width) {
+                    resize(&mut bits, width);
+                }
+                // constants wider than a bus are split over several nets
+                let mut resolved: Vec<(usize, usize)> = Vec::new();
+                for chunk in bits.chunks(BusValue::MAX_WIDTH) {
+                    let mut value = BusValue::z(chunk.len());
+                    for (bit, bit_value) in chunk.iter().enumerate() {
+                        value.set_bit(bit, *bit_value);
+                    }
+                    let net = self.assembler.add_constant(value, *line);
+                    resolved.extend((0..chunk.len()).map(|bit| (net, bit)));
+                }
+                return Ok(resolved);
+            }
+            Expression::Concatenation { parts, .. } => {
+                let mut bits: Vec<(usize, usize)> = Vec::new();
+                for part in parts.iter().rev() {
+                    bits.extend(self.resolve(part, None)?);
+                }
+                return Ok(bits);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::device::TestProbe;
+    use crate::BusValue;
+    use crate::Circuit;
+    use crate::DeviceRegistry;
+    use crate::Netlist;
+    use crate::NetlistParseError;
+
+    const ADDER: &str = "
+// a two bit adder with a tri-state copy of its sum
+module half_adder(a, b, s, c);
+  input a, b;
+  output s, c;
+  xor (s, a, b);
+  and g1 (c, a, b);
+endmodule
+
+module adder2 (
+  input [1:0] x, y,
+  input en,
+  output [2:0] sum,
+  output [1:0] bus,
+  output [1:0] swapped
+);
+  wire [1:0] c, t;
+  wire p;
+  half_adder ha0 (.a(x[0]), .b(y[0]), .s(sum[0]), .c(c[0]));
+  half_adder ha1 (x[1], y[1], p, t[0]);
+  (* keep *) half_adder ha2 (.a(p), .b(c[0]), .s(sum[1]), .c(t[1]));
+  or (sum[2], t[0], t[1]);
+  bufif1 b0 (bus[0], sum[0], en), b1 (bus[1], 1'b1, en);
+  /* both bits of x, swapped */
+  assign swapped = {x[0], x[1]};
+endmodule
+";
+
+    #[test]
+    fn imports_hierarchy() {
+        let netlist = Netlist::from_verilog(ADDER, None).unwrap();
+        assert_eq!(None, netlist.get_name());
+        assert_eq!(
+            vec![Some("half_adder")],
+            netlist
+                .get_modules()
+                .iter()
+                .map(|module| module.get_name())
+                .collect::<Vec<Option<&str>>>()
+        );
+        let text = netlist.to_string();
+        assert!(text.contains("\ndevice x test_probe direction=output width=2\n"));
+        assert!(text.contains("\ndevice ha1 half_adder\n"));
+        assert!(text.contains("\ndevice b1 tri_state_buffer\n"));
+        assert!(text.contains("\nnet c width=2 ha0.c ha2.b\n"));
+        assert!(text.contains("\nnet t width=2 ha1.c ha2.c@1 $or1.A $or1.B@1\n"));
+        assert!(text.contains("\nnet swapped width=2 swapped.pin $assign3.Y $assign4.Y@1\n"));
+        assert_eq!(text, Netlist::parse(&text).unwrap().to_string());
+
+        let (devices, nets) = netlist.build(&DeviceRegistry::new()).unwrap();
+        let mut circuit = Circuit::new(devices, nets).unwrap();
+        assert!(circuit.get_device_index("ha2.g1").is_some());
+        // 3 + 1
+        TestProbe::set_output_bus(&circuit, "x", BusValue::from_u64(2, 3)).unwrap();
+        TestProbe::set_output_bus(&circuit, "y", BusValue::from_u64(2, 1)).unwrap();
+        TestProbe::set_output_bus(&circuit, "en", BusValue::from_u64(1, 1)).unwrap();
+        circuit.run_until(20).unwrap();
+        let get_value = |name: &str| {
+            return TestProbe::get_bus_value(&circuit, name)
+                .unwrap()
+                .to_string();
+        };
+        assert_eq!("100", get_value("sum"));
+        assert_eq!("10", get_value("bus"));
+        assert_eq!("11", get_value("swapped"));
+        assert_eq!("1", get_value("ha1.s"));
+
+        TestProbe::set_output_bus(&circuit, "x", BusValue::from_u64(2, 1)).unwrap();
+        TestProbe::set_output_bus(&circuit, "en", BusValue::from_u64(1, 0)).unwrap();
+        circuit.run_until(40).unwrap();
+        let get_value = |name: &str| {
+            return TestProbe::get_bus_value(&circuit, name)
+                .unwrap()
+                .to_string();
+        };
+        assert_eq!("010", get_value("sum"));
+        assert_eq!("zz", get_value("bus"));
+        assert_eq!("10", get_value("swapped"));
+    }
+
+    #[test]
+    fn reports_lines() {
+        let error = |text: &str| Netlist::from_verilog(text, None).unwrap_err();
+        assert_eq!(
+            NetlistParseError::new(3, "always is not supported in structural verilog"),
+            error("module m(a);\n  input a;\n  always @(a) begin end\nendmodule")
+        );
+        assert_eq!(
+            NetlistParseError::new(3, "bufif0 is not supported in structural verilog"),
+            error("module m(a, e);\n  input a, e;\n  bufif0 (y, a, e);\nendmodule")
+        );
+        assert_eq!(
+            NetlistParseError::new(3, "w[2] is out of range"),
+            error("module m(a);\n  input [1:0] a; wire [1:0] w;\n  and (w[2], a[0], a[1]);\nendmodule")
+        );
+        assert_eq!(
+            NetlistParseError::new(2, "expected 1 bits, got 2"),
+            error("module m(a);\n  input [1:0] a; not (a, a);\nendmodule")
+        );
+        assert_eq!(
+            NetlistParseError::new(1, "port b of module m has no direction"),
+            error("module m(a, b);\n  input a;\nendmodule")
+        );
+        assert_eq!(
+            NetlistParseError::new(4, "expected ;, got endmodule"),
+            error("module m(a);\n  input a;\n  not n1 (b, a)\nendmodule")
+        );
+        assert_eq!(
+            NetlistParseError::new(3, "parameters must be given by name"),
+            error("module m(a);\n  input a;\n  lut #(2) l (.A(a));\nendmodule")
+        );
+        assert_eq!(
+            NetlistParseError::new(2, "choose the top module from m, n"),
+            error("\nmodule m; endmodule\nmodule n; endmodule")
+        );
+        assert_eq!(
+            NetlistParseError::new(6, "port a of u must connect to one wire or part of one"),
+            error(
+                "module sub(a);\n  input [1:0] a;\nendmodule\n\
+                 module top(x, y);\n  input x, y;\n  sub u (.a({x, y}));\nendmodule"
+            )
+        );
+        assert_eq!(
+            NetlistParseError::new(2, "concatenations are nested more than 128 deep"),
+            error(&format!(
+                "module m(a);\n  output a; assign a = {}a{};\nendmodule",
+                "{".repeat(200_000),
+                "}".repeat(200_000)
+            ))
+        );
+        assert_eq!(
+            NetlistParseError::new(2, "a is wider than 64 bits"),
+            error("module m(a);\n  output [99:0] a;\n  assign a = 0;\nendmodule")
+        );
+        assert_eq!(
+            NetlistParseError::new(2, "expected at most 64 bits, got 128"),
+            error("module m;\n  lut l (.A({64'h0, 64'h1}));\nendmodule")
+        );
+    }
+
+    #[test]
+    fn splits_wide_constants() {
+        let text = "module m(a, b);\n  output [63:0] a;\n  output [7:0] b;\n  assign {b, a} = 1;\nendmodule";
+        let netlist = Netlist::from_verilog(text, None).unwrap();
+        let (devices, nets) = netlist.build(&DeviceRegistry::new()).unwrap();
+        let mut circuit = Circuit::new(devices, nets).unwrap();
+        circuit.run_until(10).unwrap();
+        let a = TestProbe::get_bus_value(&circuit, "a").unwrap();
+        assert_eq!(BusValue::from_u64(64, 1), a);
+        let b = TestProbe::get_bus_value(&circuit, "b").unwrap();
+        assert_eq!(BusValue::from_u64(8, 0), b);
+    }
+}
@@ -0,0 +1,61 @@
+use crate::NetlistParseError;
+use std::fmt;
+
+/// A reason `Netlist::to_verilog` cannot export a netlist. Devices, ports and
+/// pins are identified by name, nets by name or `net<index>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerilogExportError {
+    /// The netlist or one of its modules could not be built.
+    Build(NetlistParseError),
+    /// The net has a pull, a resolution other than tri-state or a delay.
+    UnsupportedNet { net: String },
+    /// A net connects to a `"device.pin"` name that does not exist.
+    UnknownPinName { net: String, pin: String },
+    /// A connection covers bits outside of the pin.
+    SliceOutOfRange { net: String, pin: String },
+    /// A port of the top module is not a test probe.
+    NotATestProbe { name: String },
+    /// A module port is on a `"device.pin"` name that does not exist.
+    UnknownPortPin { port: String, pin: String },
+    /// The same port is given twice.
+    DuplicatePort { port: String },
+    /// A device has the name of a port of its module.
+    DeviceNamedAsPort { device: String },
+}
+
+impl From<NetlistParseError> for VerilogExportError {
+    fn from(error: NetlistParseError) -> VerilogExportError {
+        return VerilogExportError::Build(error);
+    }
+}
+
+impl fmt::Display for VerilogExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerilogExportError::Build(error) => write!(f, "{}", error),
+            VerilogExportError::UnsupportedNet { net } => write!(
+                f,
+                "net {} has a pull, resolution or delay verilog cannot express",
+                net
+            ),
+            VerilogExportError::UnknownPinName { net, pin } => {
+                write!(f, "net {} connects to unknown pin {}", net, pin)
+            }
+            VerilogExportError::SliceOutOfRange { net, pin } => {
+                write!(f, "net {} connects bits outside of pin {}", net, pin)
+            }
+            VerilogExportError::NotATestProbe { name } => {
+                write!(f, "{} is not a test probe", name)
+            }
+            VerilogExportError::UnknownPortPin { port, pin } => {
+                write!(f, "port {} is on unknown pin {}", port, pin)
+            }
+            VerilogExportError::DuplicatePort { port } => write!(f, "{} is a port twice", port),
+            VerilogExportError::DeviceNamedAsPort { device } => {
+                write!(f, "device {} has the name of a port", device)
+            }
+        }
+    }
+}
+
+impl std::error::Error for VerilogExportError {}
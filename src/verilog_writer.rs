@@ -0,0 +1,801 @@
+use crate::verilog::PRIMITIVES;
+use crate::BusValue;
+use crate::DeviceRegistry;
+use crate::LogicValue;
+use crate::NetPull;
+use crate::NetResolution;
+use crate::Netlist;
+use crate::PinDirection;
+use crate::PinInfo;
+use crate::VerilogExportError;
+use std::collections::HashSet;
+
+/// The reserved words of IEEE 1364-2005, identifiers that are one of them are
+/// escaped.
+const KEYWORDS: &str = "always and assign automatic begin buf bufif0 bufif1 case casex casez \
+     cell cmos config deassign default defparam design disable edge else end \
+     endcase endconfig endfunction endgenerate endmodule endprimitive \
+     endspecify endtable endtask event for force forever fork function \
+     generate genvar highz0 highz1 if ifnone incdir include initial inout \
+     input instance integer join large liblist library localparam macromodule \
+     medium module nand negedge nmos nor noshowcancelled not notif0 notif1 or \
+     output parameter pmos posedge primitive pull0 pull1 pulldown pullup \
+     pulsestyle_onevent pulsestyle_ondetect rcmos real realtime reg release \
+     repeat rnmos rpmos rtran rtranif0 rtranif1 scalared showcancelled signed \
+     small specify specparam strong0 strong1 supply0 supply1 table task time \
+     tran tranif0 tranif1 tri tri0 tri1 triand trior trireg unsigned use \
+     uwire vectored wait wand weak0 weak1 while wire wor xnor xor";
+
+impl Netlist {
+    /// Exports the netlist as structural Verilog that `from_verilog` reads
+    /// back, e.g. to check a design against another simulator or hand it to
+    /// synthesis tools. Each module becomes a Verilog module and the top level
+    /// becomes module `top`, whose ports are the test probes named in `ports`:
+    /// output probes become inputs, input probes outputs and inout probes
+    /// inouts. The ports of the other modules are their netlist ports.
+    ///
+    /// The built-in gates and one bit tri-state buffers become gate
+    /// primitives, output test probes driving 0s and 1s become `assign`s of
+    /// constants and the other devices become instances of a module named
+    /// after their type, with their parameters as strings, e.g.
+    /// `d_flip_flop #(.init("1")) ff (.D(d), .CLK(clk), .Q(q));`. `registry`
+    /// gives the pins of the devices. Nets are wires named after them, names
+    /// that are not Verilog identifiers are escaped. Nets with a pull, a
+    /// resolution other than tri-state or a delay cannot be exported.
+    pub fn to_verilog(
+        &self,
+        registry: &DeviceRegistry,
+        top: &str,
+        ports: &[&str],
+    ) -> Result<String, VerilogExportError> {
+        let mut text = String::new();
+        for module in self.get_modules() {
+            let name = module.get_name().unwrap_or_default();
+            let mut writer = ModuleWriter::new(module, self.get_modules(), registry, vec![name])?;
+            for (port, pin_name) in module.ports_iter() {
+                writer.add_module_port(port, pin_name)?;
+            }
+            text.push_str(&writer.write(name)?);
+            text.push('\n');
+        }
+        let mut writer = ModuleWriter::new(self, self.get_modules(), registry, Vec::new())?;
+        for port in ports {
+            writer.add_top_port(port)?;
+        }
+        text.push_str(&writer.write(top)?);
+        return Ok(text);
+    }
+}
+
+/// A bit of a wire, `(wire, bit)`.
+type WireBit = (usize, usize);
+
+struct Wire {
+    name: String,
+    width: usize,
+    // ports keep their name
+    port: bool,
+}
+
+enum Statement {
+    /// `assign left = right;`, both least significant bit first.
+    Assign(Vec<WireBit>, Vec<WireBit>),
+    Constant(Vec<WireBit>, BusValue),
+    /// A gate primitive, its output is the first terminal.
+    Primitive {
+        primitive: &'static str,
+        name: String,
+        terminals: Vec<WireBit>,
+    },
+    Instance {
+        type_name: String,
+        name: String,
+        params: Vec<(String, String)>,
+        pins: Vec<(String, Vec<WireBit>)>,
+    },
+}
+
+/// Writes one level of a netlist as a Verilog module.
+struct ModuleWriter<'a> {
+    netlist: &'a Netlist,
+    // pins[device][pin - 1]
+    pins: Vec<Vec<PinInfo>>,
+    // bits[device][pin - 1][pin bit], None if the bit is not connected
+    bits: Vec<Vec<Vec<Option<WireBit>>>>,
+    wires: Vec<Wire>,
+    // (name, direction, wire)
+    ports: Vec<(String, PinDirection, usize)>,
+    // the test probes that stand for ports
+    hidden: Vec<bool>,
+    statements: Vec<Statement>,
+}
+
+impl<'a> ModuleWriter<'a> {
+    fn new(
+        netlist: &'a Netlist,
+        modules: &'a [Netlist],
+        registry: &DeviceRegistry,
+        mut stack: Vec<&'a str>,
+    ) -> Result<ModuleWriter<'a>, VerilogExportError> {
+        let (devices, nets) = netlist.build_level(modules, registry, &mut stack)?;
+        let pins: Vec<Vec<PinInfo>> = devices
+            .iter()
+            .map(|device| {
+                let device = device.borrow();
+                return (1..=device.get_pin_count())
+                    .map(|pin| device.get_pin_info(pin))
+                    .collect();
+            })
+            .collect();
+        let mut writer = ModuleWriter {
+            netlist,
+            bits: pins
+                .iter()
+                .map(|pins| pins.iter().map(|pin| vec![None; pin.get_width()]).collect())
+                .collect(),
+            pins,
+            wires: Vec::new(),
+            ports: Vec::new(),
+            hidden: vec![false; devices.len()],
+            statements: Vec::new(),
+        };
+        for (index, net) in nets.iter().enumerate() {
+            let name = match net.get_name() {
+                Some(name) => name.to_string(),
+                None => format!("net{}", index),
+            };
+            if net.get_pull() != NetPull::None
+                || net.get_resolution() != NetResolution::TriState
+                || net.get_delay() != 0
+            {
+                return Err(VerilogExportError::UnsupportedNet { net: name });
+            }
+            let wire = writer.wires.len();
+            let mut width = net.get_width().unwrap_or(1);
+            for connection in net.connections_iter() {
+                let pin_name = connection.get_pin_name().unwrap_or_default();
+                let (device, pin) = writer.find_pin(pin_name).ok_or_else(|| {
+                    VerilogExportError::UnknownPinName {
+                        net: name.clone(),
+                        pin: pin_name.to_string(),
+                    }
+                })?;
+                let pin_bits = &mut writer.bits[device][pin];
+                let pin_lsb = connection.get_pin_lsb();
+                let bits = connection
+                    .get_width()
+                    .unwrap_or(pin_bits.len().saturating_sub(pin_lsb));
+                if pin_lsb + bits > pin_bits.len() {
+                    return Err(VerilogExportError::SliceOutOfRange {
+                        net: name,
+                        pin: pin_name.to_string(),
+                    });
+                }
+                for bit in 0..bits {
+                    pin_bits[pin_lsb + bit] = Some((wire, connection.get_net_lsb() + bit));
+                }
+                if net.get_width().is_none() {
+                    width = width.max(connection.get_net_lsb() + bits);
+                }
+            }
+            writer.wires.push(Wire {
+                name,
+                width,
+                port: false,
+            });
+        }
+        return Ok(writer);
+    }
+
+    /// Finds the device and the pin, counted from 0, of `"device.pin"`.
+    fn find_pin(&self, pin_name: &str) -> Option<(usize, usize)> {
+        let (device_name, pin) = pin_name.rsplit_once('.')?;
+        let device = self.find_device(device_name)?;
+        let pin = self.pins[device]
+            .iter()
+            .position(|info| info.get_name() == pin)?;
+        return Some((device, pin));
+    }
+
+    fn find_device(&self, name: &str) -> Option<usize> {
+        return self
+            .netlist
+            .get_devices()
+            .iter()
+            .position(|device| device.get_name() == name);
+    }
+
+    /// Makes test probe `name` a port of the top module.
+    fn add_top_port(&mut self, name: &str) -> Result<(), VerilogExportError> {
+        let device = self
+            .find_device(name)
+            .filter(|device| self.netlist.get_devices()[*device].get_type_name() == "test_probe")
+            .ok_or_else(|| VerilogExportError::NotATestProbe {
+                name: name.to_string(),
+            })?;
+        let direction = match self.netlist.get_devices()[device]
+            .get_params()
+            .get("direction")
+        {
+            Some("output") => PinDirection::Input,
+            Some("inout") => PinDirection::InOut,
+            _ => PinDirection::Output,
+        };
+        self.hidden[device] = true;
+        self.add_port(name, direction, device, 0);
+        return Ok(());
+    }
+
+    /// Adds port `name` of a module on pin `pin_name`. A test probe on the
+    /// port stands for it like in modules imported from Verilog, an input
+    /// probe for an input and other probes for an output.
+    fn add_module_port(&mut self, name: &str, pin_name: &str) -> Result<(), VerilogExportError> {
+        let (device, pin) =
+            self.find_pin(pin_name)
+                .ok_or_else(|| VerilogExportError::UnknownPortPin {
+                    port: name.to_string(),
+                    pin: pin_name.to_string(),
+                })?;
+        let netlist_device = &self.netlist.get_devices()[device];
+        let direction = if netlist_device.get_type_name() == "test_probe" {
+            self.hidden[device] = true;
+            match netlist_device.get_params().get("direction") {
+                None | Some("input") => PinDirection::Input,
+                _ => PinDirection::Output,
+            }
+        } else {
+            self.pins[device][pin].get_direction()
+        };
+        self.add_port(name, direction, device, pin);
+        return Ok(());
+    }
+
+    /// The port takes over the wire of the pin if the pin is all of it,
+    /// otherwise it gets its own wire assigned to or from the pin's wires.
+    fn add_port(&mut self, name: &str, direction: PinDirection, device: usize, pin: usize) {
+        let bits = &mut self.bits[device][pin];
+        let width = bits.len();
+        if let Some(Some((wire, 0))) = bits.first().copied() {
+            if !self.wires[wire].port
+                && self.wires[wire].width == width
+                && (0..width).all(|bit| bits[bit] == Some((wire, bit)))
+            {
+                self.wires[wire].name = name.to_string();
+                self.wires[wire].port = true;
+                self.ports.push((name.to_string(), direction, wire));
+                return;
+            }
+        }
+        let port_wire = self.wires.len();
+        self.wires.push(Wire {
+            name: name.to_string(),
+            width,
+            port: true,
+        });
+        let mut port_bits: Vec<WireBit> = Vec::new();
+        let mut pin_bits: Vec<WireBit> = Vec::new();
+        for (bit, wire_bit) in bits.iter_mut().enumerate() {
+            match wire_bit {
+                Some(wire_bit) => {
+                    port_bits.push((port_wire, bit));
+                    pin_bits.push(*wire_bit);
+                }
+                // the pin is only connected to the port
+                None => *wire_bit = Some((port_wire, bit)),
+            }
+        }
+        if !port_bits.is_empty() {
+            let assign = match direction {
+                PinDirection::Input => Statement::Assign(pin_bits, port_bits),
+                _ => Statement::Assign(port_bits, pin_bits),
+            };
+            self.statements.push(assign);
+        }
+        self.ports.push((name.to_string(), direction, port_wire));
+    }
+
+    /// Gets the wire bits of a pin, unconnected bits get a wire of their own
+    /// named after the pin.
+    fn get_pin_bits(&mut self, device: usize, pin: usize) -> Vec<WireBit> {
+        let bits = &self.bits[device][pin];
+        if bits.iter().all(|bit| bit.is_some()) {
+            return bits.iter().flatten().copied().collect();
+        }
+        let wire = self.wires.len();
+        self.wires.push(Wire {
+            name: format!(
+                "{}.{}",
+                self.netlist.get_devices()[device].get_name(),
+                self.pins[device][pin].get_name()
+            ),
+            width: bits.len(),
+            port: false,
+        });
+        return bits
+            .iter()
+            .enumerate()
+            .map(|(bit, wire_bit)| wire_bit.unwrap_or((wire, bit)))
+            .collect();
+    }
+
+    fn add_device(&mut self, device: usize) {
+        let netlist_device = &self.netlist.get_devices()[device];
+        let name = netlist_device.get_name().to_string();
+        let type_name = netlist_device.get_type_name();
+        let params = netlist_device.get_params();
+        let pins = &self.pins[device];
+
+        if type_name == "test_probe" && params.get("direction") == Some("output") {
+            let width = pins[0].get_width();
+            let value = match params.get("value") {
+                Some(value) => BusValue::parse(value),
+                None => Some(BusValue::filled(width, LogicValue::Zero)),
+            };
+            if let Some(value) = value.filter(|value| value.is_known()) {
+                // a constant for each run of connected bits
+                let bits = &self.bits[device][0];
+                let mut start = 0;
+                while start < width {
+                    let mut end = start;
+                    while end < width && bits[end].is_some() {
+                        end += 1;
+                    }
+                    if end > start {
+                        let run: Vec<WireBit> =
+                            bits[start..end].iter().flatten().copied().collect();
+                        self.statements
+                            .push(Statement::Constant(run, value.slice(start, end - start)));
+                    }
+                    start = end + 1;
+                }
+                return;
+            }
+        }
+
+        let primitive = PRIMITIVES
+            .iter()
+            .find(|(_, primitive_type)| *primitive_type == type_name)
+            .map(|(primitive, _)| *primitive);
+        if let Some(primitive) = primitive.filter(|_| pins.iter().all(|pin| pin.get_width() == 1)) {
+            // the output then the inputs, which come in terminal order
+            let output = pins.iter().position(|pin| pin.get_name() == "Y");
+            let order: Vec<usize> = output
+                .into_iter()
+                .chain((0..pins.len()).filter(|pin| Some(*pin) != output))
+                .collect();
+            let terminals: Vec<WireBit> = order
+                .into_iter()
+                .map(|pin| self.get_pin_bits(device, pin)[0])
+                .collect();
+            self.statements.push(Statement::Primitive {
+                primitive,
+                name,
+                terminals,
+            });
+            return;
+        }
+
+        let params: Vec<(String, String)> = params
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        let type_name = type_name.to_string();
+        let mut connections: Vec<(String, Vec<WireBit>)> = Vec::new();
+        for pin in 0..self.pins[device].len() {
+            if self.bits[device][pin].iter().any(|bit| bit.is_some()) {
+                connections.push((
+                    self.pins[device][pin].get_name().to_string(),
+                    self.get_pin_bits(device, pin),
+                ));
+            }
+        }
+        self.statements.push(Statement::Instance {
+            type_name,
+            name,
+            params,
+            pins: connections,
+        });
+    }
+
+    fn write(mut self, module_name: &str) -> Result<String, VerilogExportError> {
+        // instance and port names are kept, wires are renamed around them
+        let mut names: HashSet<String> = HashSet::new();
+        for (name, _, _) in &self.ports {
+            if !names.insert(name.clone()) {
+                return Err(VerilogExportError::DuplicatePort { port: name.clone() });
+            }
+        }
+        for device in 0..self.pins.len() {
+            if !self.hidden[device] {
+                self.add_device(device);
+            }
+        }
+        for statement in &self.statements {
+            let (Statement::Primitive { name, .. } | Statement::Instance { name, .. }) = statement
+            else {
+                continue;
+            };
+            if !names.insert(name.clone()) {
+                return Err(VerilogExportError::DeviceNamedAsPort {
+                    device: name.clone(),
+                });
+            }
+        }
+        for wire in self.wires.iter_mut().filter(|wire| !wire.port) {
+            let mut name = wire.name.clone();
+            let mut count = 0;
+            while names.contains(&name) {
+                count += 1;
+                name = format!("{}${}", wire.name, count);
+            }
+            names.insert(name.clone());
+            wire.name = name;
+        }
+
+        let mut text = String::new();
+        let port_names: Vec<String> = self
+            .ports
+            .iter()
+            .map(|(name, _, _)| format_identifier(name))
+            .collect();
+        if port_names.is_empty() {
+            text.push_str(&format!("module {};\n", format_identifier(module_name)));
+        } else {
+            text.push_str(&format!(
+                "module {}({});\n",
+                format_identifier(module_name),
+                port_names.join(", ")
+            ));
+        }
+        for (name, direction, wire) in &self.ports {
+            let keyword = match direction {
+                PinDirection::Input => "input",
+                PinDirection::Output => "output",
+                PinDirection::InOut => "inout",
+            };
+            text.push_str(&format!(
+                "  {}{} {};\n",
+                keyword,
+                format_range(self.wires[*wire].width),
+                format_identifier(name)
+            ));
+        }
+        for wire in self.wires.iter().filter(|wire| !wire.port) {
+            text.push_str(&format!(
+                "  wire{} {};\n",
+                format_range(wire.width),
+                format_identifier(&wire.name)
+            ));
+        }
+        for statement in &self.statements {
+            let line = match statement {
+                Statement::Assign(left, right) => format!(
+                    "assign {} = {};",
+                    self.format_bits(left),
+                    self.format_bits(right)
+                ),
+                Statement::Constant(bits, value) => format!(
+                    "assign {} = {}'b{};",
+                    self.format_bits(bits),
+                    value.get_width(),
+                    value
+                ),
+                Statement::Primitive {
+                    primitive,
+                    name,
+                    terminals,
+                } => {
+                    let terminals: Vec<String> = terminals
+                        .iter()
+                        .map(|bit| self.format_bits(&[*bit]))
+                        .collect();
+                    format!(
+                        "{} {} ({});",
+                        primitive,
+                        format_identifier(name),
+                        terminals.join(", ")
+                    )
+                }
+                Statement::Instance {
+                    type_name,
+                    name,
+                    params,
+                    pins,
+                } => {
+                    let mut line = format_identifier(type_name);
+                    if !params.is_empty() {
+                        let params: Vec<String> = params
+                            .iter()
+                            .map(|(name, value)| {
+                                return format!(
+                                    ".{}({})",
+                                    format_identifier(name),
+                                    format_value(value)
+                                );
+                            })
+                            .collect();
+                        line.push_str(&format!(" #({})", params.join(", ")));
+                    }
+                    let pins: Vec<String> = pins
+                        .iter()
+                        .map(|(pin, bits)| {
+                            return format!(
+                                ".{}({})",
+                                format_identifier(pin),
+                                self.format_bits(bits)
+                            );
+                        })
+                        .collect();
+                    line.push_str(&format!(
+                        " {} ({});",
+                        format_identifier(name),
+                        pins.join(", ")
+                    ));
+                    line
+                }
+            };
+            text.push_str(&format!("  {}\n", line));
+        }
+        text.push_str("endmodule\n");
+        return Ok(text);
+    }
+
+    /// Formats bits, least significant first, as a wire, a select or a
+    /// concatenation of them.
+    fn format_bits(&self, bits: &[WireBit]) -> String {
+        let mut parts: Vec<String> = Vec::new();
+        let mut start = 0;
+        while start < bits.len() {
+            let (wire, lsb) = bits[start];
+            let mut end = start + 1;
+            while end < bits.len() && bits[end] == (wire, lsb + end - start) {
+                end += 1;
+            }
+            let msb = lsb + end - start - 1;
+            let name = format_identifier(&self.wires[wire].name);
+            if lsb == 0 && msb + 1 == self.wires[wire].width {
+                parts.push(name);
+            } else if lsb == msb {
+                parts.push(format!("{}[{}]", name, lsb));
+            } else {
+                parts.push(format!("{}[{}:{}]", name, msb, lsb));
+            }
+            start = end;
+        }
+        if parts.len() == 1 {
+            return parts.remove(0);
+        }
+        parts.reverse();
+        return format!("{{{}}}", parts.join(", "));
+    }
+}
+
+fn format_range(width: usize) -> String {
+    if width == 1 {
+        return String::new();
+    }
+    return format!(" [{}:0]", width - 1);
+}
+
+/// Escapes a name that is not a Verilog identifier, `\` and a space around it.
+fn format_identifier(name: &str) -> String {
+    let mut chars = name.chars();
+    let simple = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+        && !KEYWORDS.split_whitespace().any(|keyword| keyword == name);
+    if simple {
+        return name.to_string();
+    }
+    return format!("\\{} ", name);
+}
+
+/// Formats a parameter value as a string, so bit patterns like a table of
+/// `0110` are not read as decimal numbers.
+fn format_value(value: &str) -> String {
+    return format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::device::TestProbe;
+    use crate::BusValue;
+    use crate::Circuit;
+    use crate::DeviceRegistry;
+    use crate::Netlist;
+    use crate::NetlistParseError;
+    use crate::VerilogExportError;
+
+    const NETLIST: &str = "
+module half_adder
+  device a test_probe direction=input
+  device b test_probe direction=input
+  device s test_probe direction=inout
+  device x xor
+  device c and
+  net a a.pin x.A c.A
+  net b b.pin x.B c.B
+  net s s.pin x.Y
+  port a a.pin
+  port b b.pin
+  port s s.pin
+  port carry c.Y
+end
+
+device in test_probe direction=output width=2
+device clk test_probe direction=output
+device out test_probe width=2
+device one test_probe direction=output value=1
+device ha half_adder
+device $inv not
+device ff d_flip_flop init=1
+device tri tri_state_buffer width=2
+net in width=2 in.pin ha.a ha.b@1
+net clk clk.pin $inv.A
+net clk_n $inv.Y ff.CLK
+net sum ha.s ff.D
+net carry ha.carry tri.EN
+net q ff.Q tri.A[0]
+net one one.pin tri.A[1]
+net out width=2 tri.Y out.pin
+";
+
+    fn run(netlist: &Netlist, clk: u64) -> String {
+        let (devices, nets) = netlist.build(&DeviceRegistry::new()).unwrap();
+        let mut circuit = Circuit::new(devices, nets).unwrap();
+        TestProbe::set_output_bus(&circuit, "in", BusValue::from_u64(2, 3)).unwrap();
+        TestProbe::set_output_bus(&circuit, "clk", BusValue::from_u64(1, 1)).unwrap();
+        circuit.run_until(10).unwrap();
+        TestProbe::set_output_bus(&circuit, "clk", BusValue::from_u64(1, clk)).unwrap();
+        circuit.run_until(20).unwrap();
+        return TestProbe::get_bus_value(&circuit, "out")
+            .unwrap()
+            .to_string();
+    }
+
+    #[test]
+    fn round_trips() {
+        let registry = DeviceRegistry::new();
+        let netlist = Netlist::parse(NETLIST).unwrap();
+        let verilog = netlist
+            .to_verilog(&registry, "top", &["in", "clk", "out"])
+            .unwrap();
+        assert_eq!(
+            "module half_adder(a, b, s, carry);\n\
+             \x20 input a;\n\
+             \x20 input b;\n\
+             \x20 output s;\n\
+             \x20 output carry;\n\
+             \x20 xor x (s, a, b);\n\
+             \x20 and c (carry, a, b);\n\
+             endmodule\n\
+             \n\
+             module top(in, clk, out);\n\
+             \x20 input [1:0] in;\n\
+             \x20 input clk;\n\
+             \x20 output [1:0] out;\n\
+             \x20 wire clk_n;\n\
+             \x20 wire sum;\n\
+             \x20 wire carry;\n\
+             \x20 wire q;\n\
+             \x20 wire one;\n\
+             \x20 assign one = 1'b1;\n\
+             \x20 half_adder ha (.a(in[0]), .b(in[1]), .s(sum), .carry(carry));\n\
+             \x20 not \\$inv  (clk_n, clk);\n\
+             \x20 d_flip_flop #(.init(\"1\")) ff (.D(sum), .CLK(clk_n), .Q(q));\n\
+             \x20 tri_state_buffer #(.width(\"2\")) \\tri  (.A({one, q}), .EN(carry), .Y(out));\n\
+             endmodule\n",
+            verilog
+        );
+        let imported = Netlist::from_verilog(&verilog, None).unwrap();
+        assert!(imported
+            .to_string()
+            .contains("\ndevice ff d_flip_flop init=1\n"));
+        assert_eq!(run(&netlist, 1), run(&imported, 1));
+        assert_eq!(run(&netlist, 0), run(&imported, 0));
+    }
+
+    #[test]
+    fn round_trips_luts() {
+        let registry = DeviceRegistry::new();
+        // xor of the two bits of `in`, over nets named after reserved words
+        let netlist = Netlist::parse(
+            "device in test_probe direction=output width=2\n\
+             device out test_probe\n\
+             device table lut table=0110\n\
+             net time in.pin[0] table.A\n\
+             net signed in.pin[1] table.B\n\
+             net wait table.Y out.pin\n",
+        )
+        .unwrap();
+        let verilog = netlist.to_verilog(&registry, "top", &["in"]).unwrap();
+        assert!(verilog.contains("\n  wire \\time ;\n"));
+        assert!(verilog.contains("\n  assign {\\signed , \\time } = in;\n"));
+        assert!(verilog.contains(
+            "\n  lut #(.\\table (\"0110\")) \\table  (.A(\\time ), .B(\\signed ), .Y(\\wait ));\n"
+        ));
+        let imported = Netlist::from_verilog(&verilog, None).unwrap();
+        assert!(imported
+            .to_string()
+            .contains("\ndevice table lut table=0110\n"));
+
+        // sized numbers are read back as bit patterns too
+        let sized = Netlist::from_verilog(
+            "module m; lut #(.table(4'b0110)) l (.A(a)); endmodule",
+            None,
+        )
+        .unwrap();
+        assert!(sized.to_string().starts_with("device l lut table=0110\n"));
+
+        for netlist in [netlist, imported] {
+            let (devices, nets) = netlist.build(&registry).unwrap();
+            let mut circuit = Circuit::new(devices, nets).unwrap();
+            let mut values = String::new();
+            for input in 0..4 {
+                TestProbe::set_output_bus(&circuit, "in", BusValue::from_u64(2, input)).unwrap();
+                circuit.run_until(10 * (input + 1)).unwrap();
+                values.push_str(
+                    &TestProbe::get_bus_value(&circuit, "out")
+                        .unwrap()
+                        .to_string(),
+                );
+            }
+            assert_eq!("0110", values);
+        }
+    }
+
+    #[test]
+    fn round_trips_keyword_ports() {
+        let registry = DeviceRegistry::new();
+        let netlist = Netlist::parse(
+            "device input test_probe direction=output\n\
+             device wire test_probe\n\
+             device inv not\n\
+             net a input.pin inv.A\n\
+             net y inv.Y wire.pin\n",
+        )
+        .unwrap();
+        let verilog = netlist
+            .to_verilog(&registry, "top", &["input", "wire"])
+            .unwrap();
+        assert!(verilog.starts_with("module top(\\input , \\wire );\n"));
+        let imported = Netlist::from_verilog(&verilog, None).unwrap();
+        let (devices, nets) = imported.build(&registry).unwrap();
+        let mut circuit = Circuit::new(devices, nets).unwrap();
+        TestProbe::set_output_bus(&circuit, "input", BusValue::from_u64(1, 0)).unwrap();
+        circuit.run_until(10).unwrap();
+        assert_eq!(
+            BusValue::from_u64(1, 1),
+            TestProbe::get_bus_value(&circuit, "wire").unwrap()
+        );
+    }
+
+    #[test]
+    fn reports_errors() {
+        let registry = DeviceRegistry::new();
+        let netlist = Netlist::parse(NETLIST).unwrap();
+        assert_eq!(
+            Err(VerilogExportError::NotATestProbe {
+                name: "ff".to_string()
+            }),
+            netlist.to_verilog(&registry, "top", &["ff"])
+        );
+        let netlist = Netlist::parse("device a test_probe\nnet a pull=up a.pin").unwrap();
+        assert_eq!(
+            Err(VerilogExportError::UnsupportedNet {
+                net: "a".to_string()
+            }),
+            netlist.to_verilog(&registry, "top", &[])
+        );
+        // building errors keep their line
+        let netlist = Netlist::parse("device a test_probe\ndevice b nope").unwrap();
+        assert_eq!(
+            Err(VerilogExportError::Build(NetlistParseError::new(
+                2,
+                "unknown device type nope"
+            ))),
+            netlist.to_verilog(&registry, "top", &[])
+        );
+    }
+}
@@ -0,0 +1,278 @@
+use crate::BusValue;
+use std::io;
+use std::io::Write;
+
+/// Every value change of the nets of a circuit from the tick recording started,
+/// see `Circuit::start_waveform`.
+#[derive(Debug, Clone)]
+pub struct Waveform {
+    nets: Vec<WaveformNet>,
+    start_tick: u64,
+    changes: Vec<WaveformChange>,
+}
+
+/// Name, width and starting value of a recorded net.
+#[derive(Debug, Clone)]
+struct WaveformNet {
+    name: String,
+    initial_value: BusValue,
+}
+
+/// `value` of net `net` from `tick` on.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct WaveformChange {
+    tick: u64,
+    net: usize,
+    value: BusValue,
+}
+
+impl WaveformChange {
+    pub fn get_tick(&self) -> u64 {
+        return self.tick;
+    }
+
+    pub fn get_net(&self) -> usize {
+        return self.net;
+    }
+
+    pub fn get_value(&self) -> BusValue {
+        return self.value;
+    }
+}
+
+impl Waveform {
+    /// Timescale written to VCD files, one tick is one nanosecond.
+    pub const VCD_TIMESCALE: &'static str = "1ns";
+
+    pub(crate) fn new(start_tick: u64, nets: Vec<(String, BusValue)>) -> Waveform {
+        return Waveform {
+            nets: nets
+                .into_iter()
+                .map(|(name, initial_value)| WaveformNet {
+                    name,
+                    initial_value,
+                })
+                .collect(),
+            start_tick,
+            changes: Vec::new(),
+        };
+    }
+
+    pub(crate) fn record(&mut self, tick: u64, net: usize, value: BusValue) {
+        self.changes.push(WaveformChange { tick, net, value });
+    }
+
+    pub fn get_start_tick(&self) -> u64 {
+        return self.start_tick;
+    }
+
+    /// Gets the changes in the order they were routed, a net may change more
+    /// than once within a tick.
+    pub fn get_changes(&self) -> &[WaveformChange] {
+        return &self.changes;
+    }
+
+    /// Gets the value of the net called `name` at the end of `tick`.
+    pub fn get_value_at(&self, name: &str, tick: u64) -> Option<BusValue> {
+        let net_index = self.nets.iter().position(|net| net.name == name)?;
+        let mut value = self.nets[net_index].initial_value;
+        for change in &self.changes {
+            if change.tick > tick {
+                break;
+            }
+            if change.net == net_index {
+                value = change.value;
+            }
+        }
+        return Some(value);
+    }
+
+    /// Writes a Value Change Dump. Net names are split on `.` into nested
+    /// scopes under a `circuit` module, e.g. `alu.adder.sum` becomes variable
+    /// `sum` in scope `adder` in scope `alu`.
+    pub fn write_vcd<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(out, "$timescale {} $end", Waveform::VCD_TIMESCALE)?;
+        writeln!(out, "$scope module circuit $end")?;
+
+        let mut order: Vec<usize> = (0..self.nets.len()).collect();
+        order.sort_by(|a, b| {
+            let a_path: Vec<&str> = self.nets[*a].name.split('.').collect();
+            let b_path: Vec<&str> = self.nets[*b].name.split('.').collect();
+            a_path.cmp(&b_path)
+        });
+        let mut scopes: Vec<&str> = Vec::new();
+        for net_index in order {
+            let net = &self.nets[net_index];
+            let path: Vec<&str> = net.name.split('.').collect();
+            let (var_name, net_scopes) = path.split_last().unwrap();
+            let common = scopes
+                .iter()
+                .zip(net_scopes.iter())
+                .take_while(|(a, b)| a == b)
+                .count();
+            while scopes.len() > common {
+                scopes.pop();
+                writeln!(out, "$upscope $end")?;
+            }
+            for scope in &net_scopes[common..] {
+                writeln!(out, "$scope module {} $end", scope)?;
+                scopes.push(scope);
+            }
+            let width = net.initial_value.get_width();
+            if width == 1 {
+                writeln!(
+                    out,
+                    "$var wire 1 {} {} $end",
+                    Waveform::vcd_id(net_index),
+                    var_name
+                )?;
+            } else {
+                writeln!(
+                    out,
+                    "$var wire {} {} {} [{}:0] $end",
+                    width,
+                    Waveform::vcd_id(net_index),
+                    var_name,
+                    width - 1
+                )?;
+            }
+        }
+        for _scope in scopes {
+            writeln!(out, "$upscope $end")?;
+        }
+        writeln!(out, "$upscope $end")?;
+        writeln!(out, "$enddefinitions $end")?;
+
+        writeln!(out, "#{}", self.start_tick)?;
+        writeln!(out, "$dumpvars")?;
+        for (net_index, net) in self.nets.iter().enumerate() {
+            Waveform::write_vcd_value(out, net_index, net.initial_value)?;
+        }
+        writeln!(out, "$end")?;
+        let mut last_tick = self.start_tick;
+        for change in &self.changes {
+            if change.tick != last_tick {
+                writeln!(out, "#{}", change.tick)?;
+                last_tick = change.tick;
+            }
+            Waveform::write_vcd_value(out, change.net, change.value)?;
+        }
+        return Ok(());
+    }
+
+    fn write_vcd_value<W: Write>(out: &mut W, net_index: usize, value: BusValue) -> io::Result<()> {
+        if value.get_width() == 1 {
+            return writeln!(out, "{}{}", value, Waveform::vcd_id(net_index));
+        }
+        return writeln!(out, "b{} {}", value, Waveform::vcd_id(net_index));
+    }
+
+    /// Short identifier of a net made of the printable characters `!` to `~`.
+    fn vcd_id(net_index: usize) -> String {
+        let mut id = String::new();
+        let mut remaining = net_index;
+        loop {
+            id.push((b'!' + (remaining % 94) as u8) as char);
+            remaining /= 94;
+            if remaining == 0 {
+                break;
+            }
+            remaining -= 1;
+        }
+        return id;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::device::AndGate;
+    use crate::device::TestProbe;
+    use crate::BusValue;
+    use crate::CircuitBuilder;
+    use crate::LogicValue;
+    use crate::Net;
+    use crate::NetConnection;
+    use crate::PinDirection;
+
+    #[test]
+    fn writes_vcd() {
+        let mut builder = CircuitBuilder::new();
+        builder.add_device(TestProbe::new("a", LogicValue::One, PinDirection::Output));
+        builder.add_device(TestProbe::new("b", LogicValue::Zero, PinDirection::Output));
+        builder.add_device(AndGate::new("and"));
+        builder.add_device(TestProbe::new("y", LogicValue::Z, PinDirection::Input));
+        builder.add_device(TestProbe::new_bus(
+            "d_out",
+            BusValue::from_u64(2, 1),
+            PinDirection::Output,
+        ));
+        builder.add_device(TestProbe::new_bus(
+            "d_in",
+            BusValue::z(2),
+            PinDirection::Input,
+        ));
+        builder.add_net(Net::named(
+            "inputs.a",
+            vec![NetConnection::named("a.pin"), NetConnection::named("and.A")],
+        ));
+        builder.add_net(Net::named(
+            "inputs.b",
+            vec![NetConnection::named("b.pin"), NetConnection::named("and.B")],
+        ));
+        builder.add_net(Net::named(
+            "y",
+            vec![NetConnection::named("and.Y"), NetConnection::named("y.pin")],
+        ));
+        builder.add_net(Net::named(
+            "data",
+            vec![
+                NetConnection::named("d_out.pin"),
+                NetConnection::named("d_in.pin"),
+            ],
+        ));
+        let (mut circuit, _devices) = builder.build().unwrap();
+        circuit.start_waveform();
+        circuit.tick(1).unwrap();
+        circuit.tick(2).unwrap();
+        TestProbe::set_output_high(&circuit, "b").unwrap();
+        circuit.tick(3).unwrap();
+        circuit.tick(4).unwrap();
+
+        let waveform = circuit.get_waveform().unwrap();
+        assert_eq!(Some(LogicValue::X.into()), waveform.get_value_at("y", 1));
+        assert_eq!(Some(LogicValue::Zero.into()), waveform.get_value_at("y", 3));
+        let mut vcd: Vec<u8> = Vec::new();
+        waveform.write_vcd(&mut vcd).unwrap();
+        assert_eq!(
+            "$timescale 1ns $end\n\
+             $scope module circuit $end\n\
+             $var wire 2 $ data [1:0] $end\n\
+             $scope module inputs $end\n\
+             $var wire 1 ! a $end\n\
+             $var wire 1 \" b $end\n\
+             $upscope $end\n\
+             $var wire 1 # y $end\n\
+             $upscope $end\n\
+             $enddefinitions $end\n\
+             #0\n\
+             $dumpvars\n\
+             z!\n\
+             z\"\n\
+             z#\n\
+             bzz $\n\
+             $end\n\
+             #1\n\
+             1!\n\
+             0\"\n\
+             x#\n\
+             b01 $\n\
+             #2\n\
+             0#\n\
+             #3\n\
+             1\"\n\
+             #4\n\
+             1#\n",
+            String::from_utf8(vcd).unwrap()
+        );
+    }
+}
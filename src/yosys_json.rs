@@ -0,0 +1,783 @@
+use crate::device::Lut;
+use crate::json::Json;
+use crate::BusValue;
+use crate::DeviceParams;
+use crate::LogicValue;
+use crate::Netlist;
+use crate::NetlistAssembler;
+use crate::NetlistParseError;
+use crate::PinDirection;
+use std::collections::HashMap;
+
+type GateInputs = &'static [(&'static str, &'static str)];
+type CellFunction = fn(&[bool]) -> bool;
+
+/// Gate cells that are gates here, with their `(cell pin, device pin)`
+/// inputs. The output is `Y` on both.
+const GATES: [(&str, &str, GateInputs); 9] = [
+    ("$_BUF_", "buf", &[("A", "A")]),
+    ("$_NOT_", "not", &[("A", "A")]),
+    ("$_AND_", "and", &[("A", "A"), ("B", "B")]),
+    ("$_OR_", "or", &[("A", "A"), ("B", "B")]),
+    ("$_NAND_", "nand", &[("A", "A"), ("B", "B")]),
+    ("$_NOR_", "nor", &[("A", "A"), ("B", "B")]),
+    ("$_XOR_", "xor", &[("A", "A"), ("B", "B")]),
+    ("$_XNOR_", "xnor", &[("A", "A"), ("B", "B")]),
+    ("$_TBUF_", "tri_state_buffer", &[("A", "A"), ("E", "EN")]),
+];
+
+/// Gate cells without a matching gate, they become look-up tables of their
+/// inputs in this order.
+const LUT_CELLS: [(&str, &[&str], CellFunction); 8] = [
+    ("$_ANDNOT_", &["A", "B"], |x| x[0] && !x[1]),
+    ("$_ORNOT_", &["A", "B"], |x| x[0] || !x[1]),
+    (
+        "$_MUX_",
+        &["A", "B", "S"],
+        |x| if x[2] { x[1] } else { x[0] },
+    ),
+    ("$_NMUX_", &["A", "B", "S"], |x| {
+        if x[2] {
+            !x[1]
+        } else {
+            !x[0]
+        }
+    }),
+    ("$_AOI3_", &["A", "B", "C"], |x| !(x[0] && x[1] || x[2])),
+    ("$_OAI3_", &["A", "B", "C"], |x| !((x[0] || x[1]) && x[2])),
+    ("$_AOI4_", &["A", "B", "C", "D"], |x| {
+        !(x[0] && x[1] || x[2] && x[3])
+    }),
+    ("$_OAI4_", &["A", "B", "C", "D"], |x| {
+        !((x[0] || x[1]) && (x[2] || x[3]))
+    }),
+];
+
+impl Netlist {
+    /// Imports a netlist written by Yosys with `write_json`, typically after
+    /// `synth` or `techmap`, which leave the internal gate cells:
+    ///
+    /// | cells | device type |
+    /// |---|---|
+    /// | `$_BUF_`, `$_NOT_`, `$_AND_`, `$_OR_`, `$_NAND_`, `$_NOR_`, `$_XOR_`, `$_XNOR_` | the gate |
+    /// | `$_TBUF_` | `tri_state_buffer` |
+    /// | `$_ANDNOT_`, `$_ORNOT_`, `$_MUX_`, `$_NMUX_`, `$_AOI3_`, `$_OAI3_`, `$_AOI4_`, `$_OAI4_`, `$lut` | `lut` |
+    /// | `$_DFF_*`, `$_DFFE_*`, `$_DFFSR_*`, `$_DFFSRE_*` | `d_flip_flop` |
+    /// | `$_DLATCH_N_`, `$_DLATCH_P_` | `d_latch` |
+    /// | `$_SR_*` | `sr_latch` |
+    ///
+    /// Active low clocks, enables, sets and resets get a `not` in front. Any
+    /// other cell type starting with `$` is an error.
+    ///
+    /// Modules, ports and constants are imported like
+    /// [`Netlist::from_verilog`] does. `top` is the module to import, by
+    /// default the one with the `top` attribute or else the only module not
+    /// instantiated by another. Black boxes and other cell types not defined
+    /// in `text` are created by the registry with their ports as pins. Every
+    /// named net becomes a net of the same width, the bits Yosys left unnamed
+    /// become one bit nets called `$<bit>`, as do cells it left unnamed.
+    pub fn from_yosys_json(text: &str, top: Option<&str>) -> Result<Netlist, NetlistParseError> {
+        let json = Json::parse(text)?;
+        let modules: Vec<(&str, &Json)> = json
+            .get("modules")
+            .and_then(|modules| modules.as_object())
+            .ok_or_else(|| NetlistParseError::new(json.get_line(), "no modules found"))?
+            .iter()
+            .filter(|(_, module)| !is_set(module.get("attributes"), "blackbox"))
+            .map(|(name, module)| (name.as_str(), module))
+            .collect();
+        let first_line = modules
+            .first()
+            .map(|(_, module)| module.get_line())
+            .unwrap_or(json.get_line());
+        let top = match top {
+            Some(top) => *modules
+                .iter()
+                .find(|(name, _)| *name == top)
+                .ok_or_else(|| {
+                    NetlistParseError::new(first_line, &format!("module {} is not defined", top))
+                })?,
+            None => {
+                let marked: Vec<(&str, &Json)> = modules
+                    .iter()
+                    .filter(|(_, module)| is_set(module.get("attributes"), "top"))
+                    .copied()
+                    .collect();
+                let candidates: Vec<(&str, &Json)> = if marked.is_empty() {
+                    modules
+                        .iter()
+                        .filter(|(name, _)| {
+                            !modules.iter().any(|(_, other)| {
+                                get_members(other, "cells").iter().any(|(_, cell)| {
+                                    cell.get("type").and_then(|type_name| type_name.as_str())
+                                        == Some(*name)
+                                })
+                            })
+                        })
+                        .copied()
+                        .collect()
+                } else {
+                    marked
+                };
+                match candidates[..] {
+                    [top] => top,
+                    [] => return Err(NetlistParseError::new(first_line, "no top module found")),
+                    _ => {
+                        let names: Vec<&str> = candidates.iter().map(|(name, _)| *name).collect();
+                        return Err(NetlistParseError::new(
+                            first_line,
+                            &format!("choose the top module from {}", names.join(", ")),
+                        ));
+                    }
+                }
+            }
+        };
+        let mut netlist = Elaborator::new(&modules).elaborate(top, true)?;
+        for module in &modules {
+            if module.0 != top.0 {
+                netlist.add_module(Elaborator::new(&modules).elaborate(*module, false)?);
+            }
+        }
+        return Ok(netlist);
+    }
+}
+
+/// Gets the members of object `key` of `json`, none if it is missing.
+fn get_members<'a>(json: &'a Json, key: &str) -> &'a [(String, Json)] {
+    return json
+        .get(key)
+        .and_then(|members| members.as_object())
+        .unwrap_or(&[]);
+}
+
+/// Reads a parameter or attribute, Yosys writes them as binary strings.
+fn parse_number(json: &Json) -> Option<u64> {
+    let Some(text) = json.as_str() else {
+        return json.as_u64();
+    };
+    if text.is_empty() || !text.chars().all(|c| c == '0' || c == '1') {
+        return None;
+    }
+    let digits = text.trim_start_matches('0');
+    if digits.is_empty() {
+        return Some(0);
+    }
+    return u64::from_str_radix(digits, 2).ok();
+}
+
+/// Tells if attribute `name` of `attributes` is there and not zero.
+fn is_set(attributes: Option<&Json>, name: &str) -> bool {
+    return attributes
+        .and_then(|attributes| attributes.get(name))
+        .and_then(parse_number)
+        .is_some_and(|value| value != 0);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Bit {
+    Net(u64),
+    Constant(LogicValue),
+}
+
+fn parse_bits(json: &Json, owner: &str) -> Result<Vec<Bit>, NetlistParseError> {
+    let error = |message: String| NetlistParseError::new(json.get_line(), &message);
+    let bits = json
+        .as_array()
+        .ok_or_else(|| error(format!("expected the bits of {}", owner)))?;
+    return bits
+        .iter()
+        .map(|bit| match (bit.as_u64(), bit.as_str()) {
+            (Some(id), _) => Ok(Bit::Net(id)),
+            (_, Some("0")) => Ok(Bit::Constant(LogicValue::Zero)),
+            (_, Some("1")) => Ok(Bit::Constant(LogicValue::One)),
+            (_, Some("x")) => Ok(Bit::Constant(LogicValue::X)),
+            (_, Some("z")) => Ok(Bit::Constant(LogicValue::Z)),
+            _ => Err(error(format!("invalid bit in {}", owner))),
+        })
+        .collect();
+}
+
+/// Gets the `bits` member of a port or net, which becomes a net and so can
+/// be at most `BusValue::MAX_WIDTH` bits wide.
+fn get_bits(json: &Json, owner: &str) -> Result<Vec<Bit>, NetlistParseError> {
+    let error = |message: String| NetlistParseError::new(json.get_line(), &message);
+    let bits = match json.get("bits") {
+        Some(bits) => parse_bits(bits, owner)?,
+        None => return Err(error(format!("{} has no bits", owner))),
+    };
+    if bits.len() > BusValue::MAX_WIDTH {
+        return Err(error(format!(
+            "{} is wider than {} bits",
+            owner,
+            BusValue::MAX_WIDTH
+        )));
+    }
+    return Ok(bits);
+}
+
+/// Formats a look-up table as the `table` parameter, last entry first.
+fn format_table(table: &[bool]) -> String {
+    return table
+        .iter()
+        .rev()
+        .map(|entry| if *entry { '1' } else { '0' })
+        .collect();
+}
+
+/// A cell as a device, `pins` are `(cell pin, bit of the cell pin, device pin,
+/// inverted)`.
+struct CellMapping {
+    type_name: &'static str,
+    params: DeviceParams,
+    pins: Vec<(String, usize, String, bool)>,
+}
+
+impl CellMapping {
+    fn new(type_name: &'static str) -> CellMapping {
+        return CellMapping {
+            type_name,
+            params: DeviceParams::new(),
+            pins: Vec::new(),
+        };
+    }
+
+    fn add_pin(&mut self, cell_pin: &str, bit: usize, device_pin: &str, inverted: bool) {
+        self.pins
+            .push((cell_pin.to_string(), bit, device_pin.to_string(), inverted));
+    }
+
+    /// Maps the gate cells, `None` if `type_name` is not one of them.
+    fn from_cell(
+        cell_name: &str,
+        type_name: &str,
+        cell: &Json,
+    ) -> Result<Option<CellMapping>, NetlistParseError> {
+        if let Some((_, device_type, inputs)) = GATES.iter().find(|(gate, _, _)| *gate == type_name)
+        {
+            let mut mapping = CellMapping::new(device_type);
+            for (cell_pin, device_pin) in inputs.iter() {
+                mapping.add_pin(cell_pin, 0, device_pin, false);
+            }
+            mapping.add_pin("Y", 0, "Y", false);
+            return Ok(Some(mapping));
+        }
+        if let Some((_, inputs, function)) = LUT_CELLS.iter().find(|(lut, _, _)| *lut == type_name)
+        {
+            let mut mapping = CellMapping::new("lut");
+            let table = Lut::from_function(cell_name, inputs.len(), *function);
+            mapping
+                .params
+                .set("table", &format_table(table.get_table()));
+            for (index, cell_pin) in inputs.iter().enumerate() {
+                mapping.add_pin(cell_pin, 0, &Lut::get_input_name(index), false);
+            }
+            mapping.add_pin("Y", 0, "Y", false);
+            return Ok(Some(mapping));
+        }
+        if type_name == "$lut" {
+            return CellMapping::from_lut(cell_name, cell).map(Some);
+        }
+        return Ok(CellMapping::from_storage(type_name));
+    }
+
+    /// Maps a `$lut` cell, its `LUT` parameter is the table, last entry first.
+    fn from_lut(cell_name: &str, cell: &Json) -> Result<CellMapping, NetlistParseError> {
+        let error = |message: String| NetlistParseError::new(cell.get_line(), &message);
+        let parameters = cell.get("parameters");
+        let width = parameters
+            .and_then(|parameters| parameters.get("WIDTH"))
+            .and_then(parse_number)
+            .map(|width| width as usize)
+            .filter(|width| *width <= Lut::MAX_INPUTS)
+            .ok_or_else(|| error(format!("cell {} has an invalid WIDTH", cell_name)))?;
+        let table = parameters
+            .and_then(|parameters| parameters.get("LUT"))
+            .and_then(|table| match table.as_str() {
+                Some(text) => Some(text.to_string()),
+                None => table
+                    .as_u64()
+                    .map(|value| format!("{:0width$b}", value, width = 1 << width)),
+            })
+            .filter(|table| table.chars().all(|c| c == '0' || c == '1'))
+            .ok_or_else(|| error(format!("cell {} has an invalid LUT", cell_name)))?;
+        if table.len() != 1 << width {
+            return Err(error(format!(
+                "cell {} has a LUT of {} bits for {} inputs",
+                cell_name,
+                table.len(),
+                width
+            )));
+        }
+        let mut mapping = CellMapping::new("lut");
+        mapping.params.set("table", &table);
+        for index in 0..width {
+            mapping.add_pin("A", index, &Lut::get_input_name(index), false);
+        }
+        mapping.add_pin("Y", 0, "Y", false);
+        return Ok(mapping);
+    }
+
+    /// Maps the flip-flop and latch cells, named after their kind and the
+    /// polarities of their control pins, `P` or `N`, where a reset is followed
+    /// by the value it sets.
+    fn from_storage(type_name: &str) -> Option<CellMapping> {
+        let (kind, letters) = type_name
+            .strip_prefix("$_")?
+            .strip_suffix('_')?
+            .split_once('_')?;
+        let mut letters = letters.chars();
+        // (cell pin, device pin), in the order of the letters
+        let (device_type, controls, reset_value): (&'static str, &[(&str, &str)], bool) =
+            match (kind, letters.clone().count()) {
+                ("DFF", 1) => ("d_flip_flop", &[("C", "CLK")], false),
+                ("DFF", 3) => ("d_flip_flop", &[("C", "CLK"), ("R", "RST")], true),
+                ("DFFE", 2) => ("d_flip_flop", &[("C", "CLK"), ("E", "EN")], false),
+                ("DFFE", 4) => (
+                    "d_flip_flop",
+                    &[("C", "CLK"), ("R", "RST"), ("E", "EN")],
+                    true,
+                ),
+                ("DFFSR", 3) => (
+                    "d_flip_flop",
+                    &[("C", "CLK"), ("S", "SET"), ("R", "RST")],
+                    false,
+                ),
+                ("DFFSRE", 4) => (
+                    "d_flip_flop",
+                    &[("C", "CLK"), ("S", "SET"), ("R", "RST"), ("E", "EN")],
+                    false,
+                ),
+                ("DLATCH", 1) => ("d_latch", &[("E", "EN")], false),
+                ("SR", 2) => ("sr_latch", &[("S", "S"), ("R", "R")], false),
+                _ => return None,
+            };
+        let mut mapping = CellMapping::new(device_type);
+        for (cell_pin, device_pin) in controls {
+            let inverted = match letters.next()? {
+                'P' => false,
+                'N' => true,
+                _ => return None,
+            };
+            let device_pin = match (*cell_pin, reset_value) {
+                ("R", true) => match letters.next()? {
+                    '0' => "RST",
+                    '1' => "SET",
+                    _ => return None,
+                },
+                _ => device_pin,
+            };
+            mapping.add_pin(cell_pin, 0, device_pin, inverted);
+        }
+        if device_type != "sr_latch" {
+            mapping.add_pin("D", 0, "D", false);
+        }
+        mapping.add_pin("Q", 0, "Q", false);
+        return Some(mapping);
+    }
+}
+
+/// Turns a module of the JSON into a netlist module.
+struct Elaborator<'a> {
+    modules: &'a [(&'a str, &'a Json)],
+    assembler: NetlistAssembler,
+    // the (net, bit) of the bits numbered by Yosys
+    bits: HashMap<u64, (usize, usize)>,
+    // nets of the one bit constants
+    constants: HashMap<LogicValue, usize>,
+}
+
+impl<'a> Elaborator<'a> {
+    fn new(modules: &'a [(&'a str, &'a Json)]) -> Elaborator<'a> {
+        return Elaborator {
+            modules,
+            assembler: NetlistAssembler::new(None),
+            bits: HashMap::new(),
+            constants: HashMap::new(),
+        };
+    }
+
+    fn elaborate(
+        mut self,
+        (name, module): (&str, &Json),
+        top: bool,
+    ) -> Result<Netlist, NetlistParseError> {
+        if !top {
+            self.assembler = NetlistAssembler::new(Some(name));
+        }
+        // inputs first, so the nets they drive are theirs
+        let mut ports: Vec<(&String, &Json, PinDirection)> = Vec::new();
+        for (port_name, port) in get_members(module, "ports") {
+            let direction = match port
+                .get("direction")
+                .and_then(|direction| direction.as_str())
+            {
+                Some("input") => PinDirection::Input,
+                Some("output") => PinDirection::Output,
+                Some("inout") => PinDirection::InOut,
+                _ => {
+                    return Err(NetlistParseError::new(
+                        port.get_line(),
+                        &format!("port {} of module {} has no direction", port_name, name),
+                    ))
+                }
+            };
+            ports.push((port_name, port, direction));
+        }
+        ports.sort_by_key(|(_, _, direction)| *direction != PinDirection::Input);
+        for (port_name, port, direction) in &ports {
+            let bits = get_bits(port, port_name)?;
+            let net = self.assembler.add_net(port_name, bits.len());
+            for (index, bit) in bits.iter().enumerate() {
+                match bit {
+                    Bit::Net(id) if !self.bits.contains_key(id) => {
+                        self.bits.insert(*id, (net, index));
+                    }
+                    // an output driven by a constant or another port
+                    _ if *direction == PinDirection::Output => {
+                        let driver = self.resolve(&[*bit], port.get_line());
+                        let buf_name = self.assembler.generate_name("assign");
+                        self.assembler.add_device(
+                            &buf_name,
+                            "buf",
+                            DeviceParams::new(),
+                            port.get_line(),
+                        );
+                        self.assembler.connect(&format!("{}.A", buf_name), &driver);
+                        self.assembler
+                            .connect(&format!("{}.Y", buf_name), &[(net, index)]);
+                    }
+                    _ => {
+                        return Err(NetlistParseError::new(
+                            port.get_line(),
+                            &format!(
+                                "port {} of module {} shares bits with another port",
+                                port_name, name
+                            ),
+                        ))
+                    }
+                }
+            }
+            self.assembler
+                .add_port(port_name, *direction, net, port.get_line());
+        }
+        for (net_name, netname) in get_members(module, "netnames") {
+            let hidden = netname.get("hide_name").and_then(|hidden| hidden.as_u64()) == Some(1);
+            if hidden || ports.iter().any(|(port_name, _, _)| *port_name == net_name) {
+                continue;
+            }
+            let bits = get_bits(netname, net_name)?;
+            let net = self.assembler.add_net(net_name, bits.len());
+            for (index, bit) in bits.iter().enumerate() {
+                if let Bit::Net(id) = bit {
+                    self.bits.entry(*id).or_insert((net, index));
+                }
+            }
+        }
+        for (cell_name, cell) in get_members(module, "cells") {
+            self.add_cell(cell_name, cell)?;
+        }
+        return Ok(self.assembler.finish());
+    }
+
+    fn add_cell(&mut self, cell_name: &str, cell: &Json) -> Result<(), NetlistParseError> {
+        let line = cell.get_line();
+        let error = |message: String| NetlistParseError::new(line, &message);
+        let type_name = cell
+            .get("type")
+            .and_then(|type_name| type_name.as_str())
+            .ok_or_else(|| error(format!("cell {} has no type", cell_name)))?;
+        let hidden = cell.get("hide_name").and_then(|hidden| hidden.as_u64()) == Some(1);
+        let mut connections: Vec<(&str, Vec<Bit>)> = Vec::new();
+        for (pin, bits) in get_members(cell, "connections") {
+            connections.push((pin, parse_bits(bits, cell_name)?));
+        }
+        let module = self
+            .modules
+            .iter()
+            .find(|(module_name, _)| *module_name == type_name);
+        if !type_name.starts_with('$') || module.is_some() {
+            let name = if hidden {
+                self.assembler.generate_name(type_name)
+            } else {
+                cell_name.to_string()
+            };
+            self.assembler
+                .add_device(&name, type_name, DeviceParams::new(), line);
+            for (port, bits) in &connections {
+                let pin_name = format!("{}.{}", name, port);
+                let resolved = self.resolve(bits, line);
+                let Some((module_name, module)) = module else {
+                    self.assembler.connect(&pin_name, &resolved);
+                    continue;
+                };
+                let width = match get_members(module, "ports")
+                    .iter()
+                    .find(|(port_name, _)| port_name == port)
+                {
+                    Some((port_name, port)) => get_bits(port, port_name)?.len(),
+                    None => {
+                        return Err(error(format!(
+                            "module {} has no port {}",
+                            module_name, port
+                        )))
+                    }
+                };
+                if width != resolved.len() {
+                    return Err(error(format!(
+                        "expected {} bits, got {}",
+                        width,
+                        resolved.len()
+                    )));
+                }
+                // a port is merged with the net outside, so it cannot be split
+                if self.assembler.connect(&pin_name, &resolved) > 1 {
+                    return Err(error(format!(
+                        "port {} of {} must connect to one net or part of one",
+                        port, name
+                    )));
+                }
+            }
+            return Ok(());
+        }
+        // names the scopes of flattened modules, it has no logic
+        if type_name == "$scopeinfo" {
+            return Ok(());
+        }
+        let mapping = CellMapping::from_cell(cell_name, type_name, cell)?.ok_or_else(|| {
+            error(format!(
+                "cell {} has unsupported type {}",
+                cell_name, type_name
+            ))
+        })?;
+        let name = if hidden {
+            self.assembler.generate_name(mapping.type_name)
+        } else {
+            cell_name.to_string()
+        };
+        self.assembler
+            .add_device(&name, mapping.type_name, mapping.params, line);
+        for (cell_pin, bit, device_pin, inverted) in mapping.pins {
+            let bit = connections
+                .iter()
+                .find(|(pin, _)| *pin == cell_pin)
+                .and_then(|(_, bits)| bits.get(bit))
+                .ok_or_else(|| {
+                    error(format!(
+                        "pin {} of cell {} is not connected",
+                        cell_pin, cell_name
+                    ))
+                })?;
+            let mut resolved = self.resolve(&[*bit], line);
+            if inverted {
+                let not_name = self.assembler.generate_name("not");
+                self.assembler
+                    .add_device(&not_name, "not", DeviceParams::new(), line);
+                self.assembler
+                    .connect(&format!("{}.A", not_name), &resolved);
+                let net = self.assembler.add_net(&not_name, 1);
+                resolved = vec![(net, 0)];
+                self.assembler
+                    .connect(&format!("{}.Y", not_name), &resolved);
+            }
+            self.assembler
+                .connect(&format!("{}.{}", name, device_pin), &resolved);
+        }
+        return Ok(());
+    }
+
+    /// Gets the `(net, bit)` of every bit, least significant first. Runs of
+    /// constant bits become one constant, split every `BusValue::MAX_WIDTH`
+    /// bits.
+    fn resolve(&mut self, bits: &[Bit], line: usize) -> Vec<(usize, usize)> {
+        let mut resolved: Vec<(usize, usize)> = Vec::new();
+        let mut start = 0;
+        while start < bits.len() {
+            if let Bit::Net(id) = bits[start] {
+                if !self.bits.contains_key(&id) {
+                    let net = self.assembler.add_net(&format!("${}", id), 1);
+                    self.bits.insert(id, (net, 0));
+                }
+                resolved.push(self.bits[&id]);
+                start += 1;
+                continue;
+            }
+            let mut run: Vec<LogicValue> = Vec::new();
+            while let (true, Some(Bit::Constant(bit))) =
+                (run.len() < BusValue::MAX_WIDTH, bits.get(start + run.len()))
+            {
+                run.push(*bit);
+            }
+            let mut value = BusValue::z(run.len());
+            for (bit, bit_value) in run.into_iter().enumerate() {
+                value.set_bit(bit, bit_value);
+            }
+            let net = match (value.get_width(), self.constants.get(&value.get_bit(0))) {
+                (1, Some(net)) => *net,
+                (1, None) => {
+                    let net = self.assembler.add_constant(value, line);
+                    self.constants.insert(value.get_bit(0), net);
+                    net
+                }
+                _ => self.assembler.add_constant(value, line),
+            };
+            resolved.extend((0..value.get_width()).map(|bit| (net, bit)));
+            start += value.get_width();
+        }
+        return resolved;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::device::TestProbe;
+    use crate::BusValue;
+    use crate::Circuit;
+    use crate::DeviceRegistry;
+    use crate::Netlist;
+    use crate::NetlistParseError;
+
+    // y = s ? a : (a & b) and q follows y on falling clock edges, out of
+    // `synth; write_json`, trimmed
+    const MUX_DFF: &str = r#"{
+  "creator": "Yosys",
+  "modules": {
+    "inner": {
+      "attributes": {},
+      "ports": {
+        "a": { "direction": "input", "bits": [ 2 ] },
+        "b": { "direction": "input", "bits": [ 3 ] },
+        "y": { "direction": "output", "bits": [ 4 ] }
+      },
+      "cells": {
+        "$abc$10$auto$1": {
+          "hide_name": 1,
+          "type": "$_AND_",
+          "parameters": {},
+          "connections": { "A": [ 2 ], "B": [ 3 ], "Y": [ 4 ] }
+        }
+      },
+      "netnames": {
+        "a": { "hide_name": 0, "bits": [ 2 ] },
+        "b": { "hide_name": 0, "bits": [ 3 ] },
+        "y": { "hide_name": 0, "bits": [ 4 ] }
+      }
+    },
+    "top": {
+      "attributes": { "top": "00000000000000000000000000000001" },
+      "ports": {
+        "in": { "direction": "input", "bits": [ 2, 3 ] },
+        "s": { "direction": "input", "bits": [ 4 ] },
+        "clk": { "direction": "input", "bits": [ 5 ] },
+        "y": { "direction": "output", "bits": [ 6 ] },
+        "q": { "direction": "output", "bits": [ 7, "0" ] }
+      },
+      "cells": {
+        "u1": {
+          "hide_name": 0,
+          "type": "inner",
+          "connections": { "a": [ 2 ], "b": [ 3 ], "y": [ 8 ] }
+        },
+        "$abc$20$auto$2": {
+          "hide_name": 1,
+          "type": "$_MUX_",
+          "parameters": {},
+          "connections": { "A": [ 8 ], "B": [ 2 ], "S": [ 4 ], "Y": [ 6 ] }
+        },
+        "q_reg": {
+          "hide_name": 0,
+          "type": "$_DFF_N_",
+          "parameters": {},
+          "connections": { "C": [ 5 ], "D": [ 6 ], "Q": [ 7 ] }
+        }
+      },
+      "netnames": {
+        "in": { "hide_name": 0, "bits": [ 2, 3 ] },
+        "$and$5": { "hide_name": 1, "bits": [ 8 ] }
+      }
+    }
+  }
+}"#;
+
+    #[test]
+    fn imports_cells() {
+        let netlist = Netlist::from_yosys_json(MUX_DFF, None).unwrap();
+        let text = netlist.to_string();
+        assert!(text.contains("\ndevice $lut3 lut table=11001010\n"));
+        assert!(text.contains("\ndevice q_reg d_flip_flop\n"));
+        assert!(text.contains("\nnet in width=2 in.pin u1.a u1.b@1 $lut3.B\n"));
+        assert!(text.contains("\nnet $8 u1.y $lut3.A\n"));
+        assert!(text.contains("\nnet $not4 $not4.Y q_reg.CLK\n"));
+        assert!(text.contains("\nnet q width=2 $assign2.Y@1 q.pin q_reg.Q\n"));
+        assert!(text.contains("\n  device $and1 and\n"));
+        assert_eq!(text, Netlist::parse(&text).unwrap().to_string());
+
+        let (devices, nets) = netlist.build(&DeviceRegistry::new()).unwrap();
+        let mut circuit = Circuit::new(devices, nets).unwrap();
+        let set = |circuit: &Circuit, name: &str, width: usize, value: u64| {
+            TestProbe::set_output_bus(circuit, name, BusValue::from_u64(width, value)).unwrap();
+        };
+        let get_value = |circuit: &Circuit, name: &str| {
+            return TestProbe::get_bus_value(circuit, name).unwrap().to_string();
+        };
+        set(&circuit, "in", 2, 0b01);
+        set(&circuit, "s", 1, 0);
+        set(&circuit, "clk", 1, 1);
+        circuit.run_until(10).unwrap();
+        assert_eq!("0", get_value(&circuit, "y"));
+        set(&circuit, "s", 1, 1);
+        circuit.run_until(20).unwrap();
+        assert_eq!("1", get_value(&circuit, "y"));
+        assert_eq!("0x", get_value(&circuit, "q"));
+        set(&circuit, "clk", 1, 0);
+        circuit.run_until(30).unwrap();
+        assert_eq!("01", get_value(&circuit, "q"));
+    }
+
+    #[test]
+    fn reports_unsupported_cells() {
+        let text = MUX_DFF
+            .replace("$_DFF_N_", "$_DFF_PP0_")
+            .replace("$_MUX_", "$mul");
+        assert_eq!(
+            NetlistParseError::new(40, "cell $abc$20$auto$2 has unsupported type $mul"),
+            Netlist::from_yosys_json(&text, None).unwrap_err()
+        );
+        assert_eq!(
+            NetlistParseError::new(4, "module bottom is not defined"),
+            Netlist::from_yosys_json(MUX_DFF, Some("bottom")).unwrap_err()
+        );
+        // a reset is expected on pin R
+        let text = MUX_DFF.replace("$_DFF_N_", "$_DFF_PN1_");
+        assert_eq!(
+            NetlistParseError::new(46, "pin R of cell q_reg is not connected"),
+            Netlist::from_yosys_json(&text, None).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn splits_wide_constants() {
+        // a black box with 70 constant bits on A and a net of `width` bits
+        let text = |width: u64| {
+            return format!(
+                r#"{{ "modules": {{ "top": {{
+  "cells": {{ "ram": {{ "type": "ram", "connections": {{ "A": [ {} ] }} }} }},
+  "netnames": {{ "wide": {{ "bits": [ {} ] }} }}
+}} }} }}"#,
+                vec![r#""0""#; 70].join(", "),
+                (1..=width)
+                    .map(|bit| bit.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            );
+        };
+        assert_eq!(
+            NetlistParseError::new(3, "wide is wider than 64 bits"),
+            Netlist::from_yosys_json(&text(65), None).unwrap_err()
+        );
+        let netlist = Netlist::from_yosys_json(&text(64), None)
+            .unwrap()
+            .to_string();
+        assert!(netlist.contains("\nnet $const1 width=64 $const1.pin ram.A[63:0]\n"));
+        assert!(netlist.contains("\nnet $const2 width=6 $const2.pin ram.A[69:64]\n"));
+    }
+}